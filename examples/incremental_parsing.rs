@@ -0,0 +1,70 @@
+//! Compares parsing a 10,000-line note from scratch with updating it after a single edit, as the
+//! language server does on each keystroke.
+//!
+//! Run it with `cargo run --release --example incremental_parsing`.
+
+use grimoire_lsp::ctx::Config;
+use grimoire_lsp::models::File;
+use grimoire_lsp::parsers::IncrementalParser;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+const LINES: usize = 10_000;
+const RUNS: u32 = 20;
+
+fn main() {
+    let path = PathBuf::from("meeting-log.md");
+    let config = Config::default();
+    let note = meeting_log();
+    let edited = note.replacen("[[Person 5003]]", "[[Someone Else]]", 1);
+
+    let full = time(|| {
+        File::parse_from_str(path.clone(), &edited, &config).unwrap();
+    });
+
+    let mut parser = IncrementalParser::parse(&note);
+    let mut version = 0;
+    let update = time(|| {
+        version += 1;
+        let text = match version % 2 {
+            0 => &note,
+            _ => &edited,
+        };
+        parser.update(text);
+    });
+    let assemble = time(|| {
+        File::from_parser(path.clone(), &parser, &config).unwrap();
+    });
+
+    println!("{LINES}-line note, one line edited, mean of {RUNS} runs");
+    println!("full parse:          {full:?}");
+    println!("incremental update:  {update:?}");
+    println!("building the note:   {assemble:?}");
+    println!(
+        "speedup:             {:.1}x",
+        full.as_secs_f64() / (update + assemble).as_secs_f64()
+    );
+}
+
+fn meeting_log() -> String {
+    let mut note = String::from("# Meeting log\n\n");
+    for line in 2..LINES {
+        let text = match line % 10 {
+            0 => format!("## Meeting {line}\n"),
+            1 => String::from("\n"),
+            2 => format!("- [ ] Follow up with @person{line} due:2024-05-01\n"),
+            3 => format!(
+                "Discussed [[Person {line}]] and [the plan](plans/plan-{line}.md) #meeting\n"
+            ),
+            _ => format!("Notes taken during the meeting, line {line}, see [[Project#Status]]\n"),
+        };
+        note.push_str(&text);
+    }
+    note
+}
+
+fn time(mut run: impl FnMut()) -> Duration {
+    let start = Instant::now();
+    (0..RUNS).for_each(|_| run());
+    start.elapsed() / RUNS
+}
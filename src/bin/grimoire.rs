@@ -10,6 +10,14 @@ enum Grimoire {
     Reindex(grimoire_lsp::subcommands::Reindex),
     /// Runs the LSP server for a Grimoire project
     Lsp(grimoire_lsp::subcommands::Lsp),
+    /// Prints a note with its embeds expanded
+    Render(grimoire_lsp::subcommands::Render),
+    /// Searches the text of notes, listing the matching lines of the best matches first
+    Search(grimoire_lsp::subcommands::Search),
+    /// Lists the tags used across notes with their number of occurrences
+    Tags(grimoire_lsp::subcommands::Tags),
+    /// Lists the tasks across notes, optionally filtered by status or due date
+    Tasks(grimoire_lsp::subcommands::Tasks),
 }
 
 fn main() -> grimoire_lsp::Result<()> {
@@ -17,5 +25,9 @@ fn main() -> grimoire_lsp::Result<()> {
         Grimoire::Init(args) => grimoire_lsp::subcommands::Init::call(args),
         Grimoire::Lsp(args) => grimoire_lsp::subcommands::Lsp::call(args),
         Grimoire::Reindex(args) => grimoire_lsp::subcommands::Reindex::call(args),
+        Grimoire::Render(args) => grimoire_lsp::subcommands::Render::call(args),
+        Grimoire::Search(args) => grimoire_lsp::subcommands::Search::call(args),
+        Grimoire::Tags(args) => grimoire_lsp::subcommands::Tags::call(args),
+        Grimoire::Tasks(args) => grimoire_lsp::subcommands::Tasks::call(args),
     }
 }
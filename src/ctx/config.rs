@@ -0,0 +1,219 @@
+use crate::ctx::ProjectRoot;
+use crate::models::{content_hash, normalize_key, slug_key};
+use crate::parsers::NoteFormat;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Project settings read from `.grimoire/config.toml`.
+#[derive(Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Config {
+    header: HeaderPolicy,
+    link_matching: LinkMatching,
+    extensions: Extensions,
+}
+
+/// Which heading identifies a note.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum HeaderPolicy {
+    /// The front matter `title`, falling back to the first H1.
+    #[default]
+    FrontMatterTitle,
+    /// The first H1, even when the front matter has a `title`.
+    FirstH1,
+    /// The first heading of any level, falling back to the first H1.
+    FirstHeading,
+}
+
+/// How link targets are matched against the headers and aliases of notes.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum LinkMatching {
+    /// Regardless of Unicode normalization, case and whitespace, or written as a slug such as
+    /// `my-favorite-recipes`.
+    #[default]
+    Normalized,
+    /// Only exactly as written.
+    Strict,
+}
+
+impl LinkMatching {
+    /// Whether the link `target` names a note with the header or alias `name`.
+    pub fn matches(self, target: &str, name: &str) -> bool {
+        match self {
+            Self::Strict => target == name,
+            Self::Normalized => {
+                target == name
+                    || normalize_key(target) == normalize_key(name)
+                    || slug_key(target) == slug_key(name)
+            }
+        }
+    }
+}
+
+/// The file extensions of the notes written in each format, such as `["md", "markdown"]`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct Extensions {
+    markdown: Vec<String>,
+    org: Vec<String>,
+}
+
+impl Default for Extensions {
+    fn default() -> Self {
+        Self {
+            markdown: vec![String::from("md")],
+            org: vec![String::from("org")],
+        }
+    }
+}
+
+impl Extensions {
+    /// The format of the notes with `extension`, compared without its case or a leading dot.
+    pub fn format(&self, extension: &str) -> Option<NoteFormat> {
+        let matches = |extensions: &Vec<String>| {
+            extensions.iter().any(|candidate| {
+                let candidate = candidate.strip_prefix('.').unwrap_or(candidate);
+                candidate.eq_ignore_ascii_case(extension)
+            })
+        };
+        if matches(&self.markdown) {
+            Some(NoteFormat::Markdown)
+        } else if matches(&self.org) {
+            Some(NoteFormat::Org)
+        } else {
+            None
+        }
+    }
+}
+
+impl Config {
+    /// Reads the project configuration, using the defaults when there is no config file.
+    pub fn load(project_root: &ProjectRoot) -> crate::Result<Self> {
+        let path = project_root.config_file_path();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let contents = std::fs::read_to_string(&path)?;
+        toml::from_str(&contents).map_err(|e| crate::errors::invalid_config(&path, e))
+    }
+
+    pub fn header(&self) -> HeaderPolicy {
+        self.header
+    }
+
+    pub fn link_matching(&self) -> LinkMatching {
+        self.link_matching
+    }
+
+    pub fn extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+
+    /// A hash of the settings notes are parsed and indexed with, so that the index is rebuilt
+    /// when they change even though the notes didn't.
+    pub fn index_hash(&self) -> crate::Result<u64> {
+        let settings = (self.header, self.link_matching, &self.extensions);
+        Ok(content_hash(&serde_json::to_string(&settings)?))
+    }
+
+    /// The format of the note at `path`, or `None` when its extension isn't a note's.
+    pub fn format(&self, path: &Path) -> Option<NoteFormat> {
+        path.extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(|extension| self.extensions.format(extension))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_load_missing_file() {
+        let config = Config::load(&ProjectRoot::mock()).unwrap();
+        assert_eq!(config, Config::default());
+        assert_eq!(config.header(), HeaderPolicy::FrontMatterTitle);
+    }
+
+    #[test]
+    fn test_config_load() {
+        let project_root = ProjectRoot::mock();
+        std::fs::create_dir_all(project_root.config_file_directory()).unwrap();
+        std::fs::write(project_root.config_file_path(), "").unwrap();
+        assert_eq!(Config::load(&project_root).unwrap(), Config::default());
+
+        std::fs::write(
+            project_root.config_file_path(),
+            "header = \"first-heading\"\n",
+        )
+        .unwrap();
+        let config = Config::load(&project_root).unwrap();
+        assert_eq!(config.header(), HeaderPolicy::FirstHeading);
+
+        std::fs::write(project_root.config_file_path(), "header = \"last-h2\"\n").unwrap();
+        assert!(Config::load(&project_root).is_err());
+    }
+
+    #[test]
+    fn test_config_link_matching() {
+        let project_root = ProjectRoot::mock();
+        assert_eq!(
+            Config::load(&project_root).unwrap().link_matching(),
+            LinkMatching::Normalized
+        );
+        std::fs::create_dir_all(project_root.config_file_directory()).unwrap();
+        std::fs::write(
+            project_root.config_file_path(),
+            "link-matching = \"strict\"\n",
+        )
+        .unwrap();
+        let config = Config::load(&project_root).unwrap();
+        assert_eq!(config.link_matching(), LinkMatching::Strict);
+    }
+
+    #[test]
+    fn test_link_matching_matches() {
+        let normalized = LinkMatching::Normalized;
+        assert!(normalized.matches("my favorite  recipes", "My Favorite Recipes"));
+        assert!(normalized.matches("my-favorite-recipes", "My Favorite Recipes"));
+        assert!(!normalized.matches("my recipes", "My Favorite Recipes"));
+        let strict = LinkMatching::Strict;
+        assert!(strict.matches("My Favorite Recipes", "My Favorite Recipes"));
+        assert!(!strict.matches("my favorite recipes", "My Favorite Recipes"));
+    }
+
+    #[test]
+    fn test_config_format() {
+        let config = Config::default();
+        assert_eq!(config.format(Path::new("a.md")), Some(NoteFormat::Markdown));
+        assert_eq!(config.format(Path::new("a.MD")), Some(NoteFormat::Markdown));
+        assert_eq!(config.format(Path::new("a.org")), Some(NoteFormat::Org));
+        assert_eq!(config.format(Path::new("a.mdx")), None);
+        assert_eq!(config.format(Path::new("md")), None);
+    }
+
+    #[test]
+    fn test_config_load_extensions() {
+        let project_root = ProjectRoot::mock();
+        std::fs::create_dir_all(project_root.config_file_directory()).unwrap();
+        std::fs::write(
+            project_root.config_file_path(),
+            "[extensions]\nmarkdown = [\"md\", \".markdown\", \"mdx\"]\n",
+        )
+        .unwrap();
+        let config = Config::load(&project_root).unwrap();
+        assert_eq!(config.header(), HeaderPolicy::FrontMatterTitle);
+        assert_eq!(
+            config.format(Path::new("a.mdx")),
+            Some(NoteFormat::Markdown)
+        );
+        assert_eq!(
+            config.format(Path::new("a.markdown")),
+            Some(NoteFormat::Markdown)
+        );
+        // The formats left out keep their default extensions.
+        assert_eq!(config.format(Path::new("a.org")), Some(NoteFormat::Org));
+    }
+}
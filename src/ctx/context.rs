@@ -1,9 +1,10 @@
-use crate::ctx::{Database, ProjectRoot};
-use crate::ctx::{Logger, StandardLogger};
+use crate::ctx::{Config, Database, LinkResolver, MemoryStorage, ProjectRoot};
+use crate::ctx::{Logger, StandardLogger, StderrLogger};
 
 #[derive(Debug)]
 pub struct Context {
     project_root: ProjectRoot,
+    config: Config,
     db: Database,
 }
 
@@ -15,17 +16,31 @@ impl Context {
         let args = args.into();
         let project_root = args.project_root?;
         args.logger.initialize(&project_root)?;
+        let config = Config::load(&project_root)?;
         let db = args.db?;
-        Ok(Self { project_root, db })
+        Ok(Self {
+            project_root,
+            config,
+            db,
+        })
     }
 
     pub fn project_root(&self) -> &ProjectRoot {
         &self.project_root
     }
 
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
     pub fn db(&self) -> &Database {
         &self.db
     }
+
+    /// Resolves links by the project's link matching, and path-qualified links from its root.
+    pub fn link_resolver(&self) -> LinkResolver {
+        LinkResolver::new(self.project_root.file_path(), self.config.link_matching())
+    }
 }
 
 pub struct ContextArgs {
@@ -51,6 +66,20 @@ impl Default for ContextArgs {
     }
 }
 
+impl ContextArgs {
+    /// Keeps the index in memory instead of in the project, for sessions that shouldn't write
+    /// to it. Every note is parsed again on startup, and logs go to stderr.
+    pub fn in_memory() -> Self {
+        let project_root =
+            ProjectRoot::current(()).ok_or_else(crate::errors::project_uninitialized);
+        Self {
+            project_root,
+            logger: Box::new(StderrLogger::new()),
+            db: Database::new(MemoryStorage::new()),
+        }
+    }
+}
+
 impl From<()> for ContextArgs {
     fn from(_: ()) -> Self {
         Self::default()
@@ -58,16 +87,28 @@ impl From<()> for ContextArgs {
 }
 
 #[cfg(test)]
-pub mod mocks {
+mod mocks {
     use super::*;
 
     impl Context {
         pub fn mock() -> Self {
             Self {
                 project_root: ProjectRoot::mock(),
+                config: Config::default(),
                 db: Database::mock(),
             }
         }
+
+        pub fn mock_with_project_root(project_root: ProjectRoot) -> Self {
+            Self {
+                project_root,
+                ..Self::mock()
+            }
+        }
+
+        pub fn with_config(self, config: Config) -> Self {
+            Self { config, ..self }
+        }
     }
 }
 
@@ -142,6 +183,12 @@ mod tests {
         assert_ne!(context.project_root().file_path(), PathBuf::from(""));
     }
 
+    #[test]
+    fn test_context_config() {
+        let context = Context::mock();
+        assert_eq!(context.config(), &Config::default());
+    }
+
     #[test]
     fn test_context_db() {
         let context = Context::mock();
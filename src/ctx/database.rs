@@ -1,12 +1,20 @@
-use crate::ctx::ProjectRoot;
+use crate::ctx::{MemoryStorage, ProjectRoot, SledStorage, Storage, TreeChanges};
 use crate::repositories::RepositoryBuilder;
+use std::cell::Cell;
+use std::rc::Rc;
 
-pub type DatabaseTable = sled::Tree;
 pub type Connection = sled::Db;
 
+/// The version of the encodings stored in the index. Bump it whenever a model stored in the index
+/// or the layout of its keys changes, so existing indexes are rebuilt rather than misread.
+pub const SCHEMA_VERSION: u32 = 4;
+
 #[derive(Debug)]
 pub struct Database {
+    storage: Rc<dyn Storage>,
     repository_builder: RepositoryBuilder,
+    // How many calls to `execute` are running, since only the outermost one commits.
+    depth: Cell<usize>,
 }
 
 impl Database {
@@ -15,9 +23,32 @@ impl Database {
         T: Into<DatabaseArgs>,
     {
         let args = args.into();
-        let conn = args.conn?;
-        let repository_builder = RepositoryBuilder::new(&conn)?;
-        Ok(Self { repository_builder })
+        let storage = args.storage?;
+        let repository_builder = RepositoryBuilder::new(storage.clone());
+        let db = Self {
+            storage,
+            repository_builder,
+            depth: Cell::new(0),
+        };
+        db.migrate()?;
+        Ok(db)
+    }
+
+    /// Clears an index written with another schema version, so the next reindex rebuilds it
+    /// from the notes instead of requests failing to decode it.
+    fn migrate(&self) -> crate::Result<()> {
+        self.execute(|repository| {
+            let metadata = repository.metadata();
+            match metadata.schema_version()? {
+                Some(SCHEMA_VERSION) => return Ok(()),
+                Some(version) => log::warn!(
+                    "The index was written with schema version {version}, rebuilding it at version {SCHEMA_VERSION}"
+                ),
+                None => log::info!("Initializing the index at schema version {SCHEMA_VERSION}"),
+            }
+            repository.clear()?;
+            metadata.set_schema_version(SCHEMA_VERSION)
+        })
     }
 
     pub fn drop(&self) -> crate::Result<()> {
@@ -28,19 +59,42 @@ impl Database {
         Ok(())
     }
 
+    /// Runs `func` as a transaction: its writes are committed together to every tree when it
+    /// succeeds, and discarded when it fails, so readers never see a partial update.
     pub fn execute<F>(&self, mut func: F) -> crate::Result<()>
     where
         F: FnMut(&RepositoryBuilder) -> crate::Result<()>,
     {
-        // TODO: incorporate sled's transactions here
+        self.depth.set(self.depth.get() + 1);
         let result = func(&self.repository_builder);
-        self.repository_builder.flush()?;
-        result
+        self.depth.set(self.depth.get() - 1);
+        if self.depth.get() > 0 {
+            return result;
+        }
+
+        match result {
+            Ok(()) => {
+                let changes: Vec<TreeChanges> = self
+                    .repository_builder
+                    .tables()
+                    .iter()
+                    .map(|table| table.take_changes())
+                    .collect();
+                self.storage.commit(&changes)?;
+                self.repository_builder.flush()
+            }
+            Err(error) => {
+                for table in self.repository_builder.tables() {
+                    table.discard_changes();
+                }
+                Err(error)
+            }
+        }
     }
 }
 
 pub struct DatabaseArgs {
-    conn: crate::Result<Connection>,
+    storage: crate::Result<Rc<dyn Storage>>,
 }
 
 impl DatabaseArgs {
@@ -61,27 +115,38 @@ impl DatabaseArgs {
 
 impl From<crate::Result<&ProjectRoot>> for DatabaseArgs {
     fn from(project_root: crate::Result<&ProjectRoot>) -> Self {
+        let conn = project_root.and_then(Self::new_connection);
         Self {
-            conn: project_root.and_then(Self::new_connection),
+            storage: conn.map(|conn| Rc::new(SledStorage::new(conn)) as Rc<dyn Storage>),
         }
     }
 }
 
 impl From<Connection> for DatabaseArgs {
     fn from(conn: Connection) -> Self {
-        Self { conn: Ok(conn) }
+        Self {
+            storage: Ok(Rc::new(SledStorage::new(conn))),
+        }
+    }
+}
+
+impl From<MemoryStorage> for DatabaseArgs {
+    fn from(storage: MemoryStorage) -> Self {
+        Self {
+            storage: Ok(Rc::new(storage)),
+        }
     }
 }
 
 #[cfg(test)]
-pub mod mocks {
+mod mocks {
     use super::*;
     use std::path::PathBuf;
     use tempfile::{Builder, TempDir};
 
     impl Database {
         pub fn mock() -> Self {
-            Self::new(Self::mock_connection()).unwrap()
+            Self::new(MemoryStorage::new()).unwrap()
         }
         pub fn mock_connection() -> Connection {
             let tmp_dir = Builder::new().prefix("grimoire").tempdir().unwrap();
@@ -99,12 +164,14 @@ pub mod mocks {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ctx::Config;
+    use crate::models::{File, Reference};
 
     #[test]
     fn test_database_args_from_connection() {
         let conn = Database::mock_connection();
         let args: DatabaseArgs = conn.into();
-        assert!(args.conn.is_ok());
+        assert!(args.storage.is_ok());
     }
 
     #[test]
@@ -112,7 +179,7 @@ mod tests {
         let project_root = ProjectRoot::mock();
         let project_root_result = Ok(&project_root);
         let args: DatabaseArgs = project_root_result.into();
-        assert!(args.conn.is_ok());
+        assert!(args.storage.is_ok());
     }
 
     #[test]
@@ -129,9 +196,110 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_database_execute_commits() -> crate::Result<()> {
+        let db = Database::mock();
+        let file = File::mock(None);
+        db.execute(|repository| {
+            repository.files().create_file(&file)?;
+            repository.stamps().delete(file.path())
+        })?;
+        let mut found = None;
+        db.execute(|repository| {
+            found = repository.files().find_by_path(file.path())?;
+            Ok(())
+        })?;
+        assert!(found.is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_database_execute_rolls_back_on_error() -> crate::Result<()> {
+        let db = Database::mock();
+        let first = File::mock(Some(String::from("# First\n")));
+        let second = File::parse_from_str(first.path().clone(), "# Second\n", &Config::default())?;
+        // Indexing the same path twice fails after the first file was written.
+        let result = db.execute(|repository| {
+            let reference = Reference::mock();
+            repository.references().upsert_reference(&reference)?;
+            repository.files().create_file(&first)?;
+            repository.files().create_file(&second)
+        });
+        assert!(result.is_err());
+
+        db.execute(|repository| {
+            assert!(repository.files().find_all()?.is_empty());
+            assert!(repository
+                .references()
+                .find_by_target("A Nice Reference")?
+                .is_empty());
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_database_execute_nested_commits_once() -> crate::Result<()> {
+        let db = Database::mock();
+        let file = File::mock(None);
+        let result = db.execute(|repository| {
+            db.execute(|repository| repository.files().create_file(&file))?;
+            assert!(repository.files().find_by_path(file.path())?.is_some());
+            Err(anyhow::anyhow!("failed after the nested call"))
+        });
+        assert!(result.is_err());
+        db.execute(|repository| {
+            assert!(repository.files().find_by_path(file.path())?.is_none());
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_database_new_rebuilds_other_schema_version() -> crate::Result<()> {
+        let conn = Database::mock_connection();
+        let file = File::mock(None);
+        let db = Database::new(conn.clone())?;
+        db.execute(|repository| repository.files().create_file(&file))?;
+
+        // Reopening at the same version keeps the index.
+        let db = Database::new(conn.clone())?;
+        let mut files = Vec::new();
+        db.execute(|repository| {
+            files = repository.files().find_all()?;
+            Ok(())
+        })?;
+        assert_eq!(files.len(), 1);
+
+        conn.open_tree(b"metadata")?
+            .insert(b"schema_version", b"0".to_vec())?;
+        conn.open_tree(b"files")?
+            .insert(b"undecodable", b"{".to_vec())?;
+        let db = Database::new(conn)?;
+        db.execute(|repository| {
+            assert!(repository.files().find_all()?.is_empty());
+            assert_eq!(
+                repository.metadata().schema_version()?,
+                Some(SCHEMA_VERSION)
+            );
+            Ok(())
+        })
+    }
+
     #[test]
     fn test_database_drop() {
         let db = Database::mock();
         assert!(db.drop().is_ok());
     }
+
+    #[test]
+    fn test_database_drop_keeps_schema_version() -> crate::Result<()> {
+        let db = Database::mock();
+        db.drop()?;
+        db.execute(|repository| {
+            assert_eq!(
+                repository.metadata().schema_version()?,
+                Some(SCHEMA_VERSION)
+            );
+            Ok(())
+        })
+    }
 }
@@ -0,0 +1,174 @@
+use crate::ctx::{Storage, TreeChanges};
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+/// A tree of the index whose writes are buffered until `Database::execute` commits them, so reads
+/// within the same call see them and a call that fails leaves the tree untouched.
+#[derive(Debug)]
+pub struct DatabaseTable {
+    storage: Rc<dyn Storage>,
+    name: &'static str,
+    // The keys written since the last commit, with their new value or `None` once removed.
+    pending: RefCell<BTreeMap<Vec<u8>, Option<Vec<u8>>>>,
+    cleared: Cell<bool>,
+}
+
+impl DatabaseTable {
+    pub fn new(storage: Rc<dyn Storage>, name: &'static str) -> Self {
+        Self {
+            storage,
+            name,
+            pending: RefCell::new(BTreeMap::new()),
+            cleared: Cell::new(false),
+        }
+    }
+
+    pub fn get<K: AsRef<[u8]>>(&self, key: K) -> crate::Result<Option<Vec<u8>>> {
+        if let Some(value) = self.pending.borrow().get(key.as_ref()) {
+            return Ok(value.clone());
+        }
+        if self.cleared.get() {
+            return Ok(None);
+        }
+        self.storage.get(self.name, key.as_ref())
+    }
+
+    pub fn contains_key<K: AsRef<[u8]>>(&self, key: K) -> crate::Result<bool> {
+        Ok(self.get(key)?.is_some())
+    }
+
+    pub fn insert<K: AsRef<[u8]>, V: AsRef<[u8]>>(&self, key: K, value: V) -> crate::Result<()> {
+        self.pending
+            .borrow_mut()
+            .insert(key.as_ref().to_vec(), Some(value.as_ref().to_vec()));
+        Ok(())
+    }
+
+    pub fn remove<K: AsRef<[u8]>>(&self, key: K) -> crate::Result<()> {
+        self.pending
+            .borrow_mut()
+            .insert(key.as_ref().to_vec(), None);
+        Ok(())
+    }
+
+    pub fn clear(&self) -> crate::Result<()> {
+        self.pending.borrow_mut().clear();
+        self.cleared.set(true);
+        Ok(())
+    }
+
+    pub fn iter(&self) -> std::vec::IntoIter<crate::Result<(Vec<u8>, Vec<u8>)>> {
+        self.scan_prefix([])
+    }
+
+    /// The entries whose key starts with `prefix` in key order, including the pending writes.
+    /// They are collected up front, so the table may be written to while they are visited.
+    pub fn scan_prefix<P: AsRef<[u8]>>(
+        &self,
+        prefix: P,
+    ) -> std::vec::IntoIter<crate::Result<(Vec<u8>, Vec<u8>)>> {
+        let prefix = prefix.as_ref();
+        let mut entries: BTreeMap<Vec<u8>, Vec<u8>> = BTreeMap::new();
+        if !self.cleared.get() {
+            match self.storage.scan_prefix(self.name, prefix) {
+                Ok(stored) => entries.extend(stored),
+                Err(error) => return vec![Err(error)].into_iter(),
+            }
+        }
+        let pending = self.pending.borrow();
+        let written = pending
+            .range(prefix.to_vec()..)
+            .take_while(|(key, _)| key.starts_with(prefix));
+        for (key, value) in written {
+            match value {
+                Some(value) => entries.insert(key.clone(), value.clone()),
+                None => entries.remove(key),
+            };
+        }
+        entries.into_iter().map(Ok).collect::<Vec<_>>().into_iter()
+    }
+
+    /// Empties the buffer into the changes a commit applies to the storage.
+    pub(crate) fn take_changes(&self) -> TreeChanges {
+        let pending = std::mem::take(&mut *self.pending.borrow_mut());
+        TreeChanges {
+            tree: self.name,
+            cleared: self.cleared.replace(false),
+            writes: pending.into_iter().collect(),
+        }
+    }
+
+    pub(crate) fn discard_changes(&self) {
+        self.pending.borrow_mut().clear();
+        self.cleared.set(false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ctx::MemoryStorage;
+
+    fn table() -> DatabaseTable {
+        let storage = MemoryStorage::new();
+        storage
+            .commit(&[TreeChanges {
+                tree: "test",
+                cleared: false,
+                writes: vec![
+                    (b"a1".to_vec(), Some(b"stored".to_vec())),
+                    (b"a2".to_vec(), Some(b"stored".to_vec())),
+                ],
+            }])
+            .unwrap();
+        DatabaseTable::new(Rc::new(storage), "test")
+    }
+
+    fn keys(table: &DatabaseTable, prefix: &[u8]) -> Vec<Vec<u8>> {
+        table
+            .scan_prefix(prefix)
+            .map(|entry_result| entry_result.unwrap().0)
+            .collect()
+    }
+
+    #[test]
+    fn test_database_table_reads_pending_writes() {
+        let table = table();
+        table.insert(b"a3", b"pending").unwrap();
+        table.remove(b"a1").unwrap();
+
+        assert_eq!(table.get(b"a3").unwrap(), Some(b"pending".to_vec()));
+        assert!(!table.contains_key(b"a1").unwrap());
+        assert_eq!(keys(&table, b"a"), vec![b"a2".to_vec(), b"a3".to_vec()]);
+        assert!(table.storage.get("test", b"a3").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_database_table_clear() {
+        let table = table();
+        table.clear().unwrap();
+        table.insert(b"a3", b"pending").unwrap();
+        assert!(table.get(b"a1").unwrap().is_none());
+        assert_eq!(keys(&table, b""), vec![b"a3".to_vec()]);
+
+        assert_eq!(
+            table.take_changes(),
+            TreeChanges {
+                tree: "test",
+                cleared: true,
+                writes: vec![(b"a3".to_vec(), Some(b"pending".to_vec()))],
+            }
+        );
+        assert_eq!(table.take_changes().writes, vec![]);
+    }
+
+    #[test]
+    fn test_database_table_discard_changes() {
+        let table = table();
+        table.clear().unwrap();
+        table.insert(b"a3", b"pending").unwrap();
+        table.discard_changes();
+        assert_eq!(keys(&table, b""), vec![b"a1".to_vec(), b"a2".to_vec()]);
+    }
+}
@@ -0,0 +1,253 @@
+use crate::ctx::LinkMatching;
+use crate::models::{normalize_key, slug_key, File, Reference};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Decides which notes a wiki link target names: by header or alias, or by a path such as
+/// `folder/Note` when notes in different folders share a header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkResolver {
+    root: PathBuf,
+    matching: LinkMatching,
+}
+
+impl LinkResolver {
+    pub fn new(root: PathBuf, matching: LinkMatching) -> Self {
+        Self { root, matching }
+    }
+
+    pub fn matching(&self) -> LinkMatching {
+        self.matching
+    }
+
+    /// Whether `target` names `file` by its header, one of its aliases or its path.
+    pub fn names(&self, target: &str, file: &File) -> bool {
+        std::iter::once(file.header())
+            .chain(file.aliases())
+            .any(|name| self.matching.matches(target, &name))
+            || self.names_path(target, file.path())
+    }
+
+    /// Whether `reference` links to `file`, by name or by the path of a Markdown link.
+    pub fn links_to(&self, reference: &Reference, file: &File) -> bool {
+        match reference.target_path() {
+            Some(path) => path == file.path(),
+            None => self.names(&reference.header(), file),
+        }
+    }
+
+    /// Whether the path-qualified `target` names the note at `path`.
+    ///
+    /// `folder/Note` matches the end of the path relative to the project root, its last segment
+    /// being the note's file stem or file name. A leading `/` anchors it at the project root, as
+    /// in `/Note` for a note at the root. Without either, `target` is a header rather than a path.
+    pub fn names_path(&self, target: &str, path: &Path) -> bool {
+        let anchored = target.starts_with('/');
+        let segments: Vec<&str> = target
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .collect();
+        if segments.is_empty() || (segments.len() < 2 && !anchored) {
+            return false;
+        }
+        let components = match relative_components(&self.root, path) {
+            Some(components) => components,
+            None => return false,
+        };
+        if segments.len() > components.len() || (anchored && segments.len() != components.len()) {
+            return false;
+        }
+        let tail = &components[components.len() - segments.len()..];
+        let (last_segment, folders) = segments.split_last().unwrap();
+        let (file_name, tail_folders) = tail.split_last().unwrap();
+        let stem = Path::new(file_name)
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or(file_name);
+        folders
+            .iter()
+            .zip(tail_folders)
+            .all(|(segment, folder)| self.matching.matches(segment, folder))
+            && (self.matching.matches(last_segment, stem)
+                || self.matching.matches(last_segment, file_name))
+    }
+
+    /// The shortest target naming each of `notes`, given as paths and headers, alone among them:
+    /// its header when no other note shares it, or else its path qualified with as few folders as
+    /// needed.
+    ///
+    /// Header and file name keys are computed once, so only notes sharing a header are compared,
+    /// and only with the notes sharing their file name.
+    pub fn shortest_targets(&self, notes: &[(PathBuf, String)]) -> Vec<String> {
+        let mut header_counts: HashMap<HeaderKey, usize> = HashMap::new();
+        let mut names: HashMap<String, Vec<&Path>> = HashMap::new();
+        for (path, header) in notes {
+            for key in self.header_keys(header) {
+                *header_counts.entry(key).or_default() += 1;
+            }
+            for key in name_keys(path) {
+                names.entry(key).or_default().push(path);
+            }
+        }
+
+        notes
+            .iter()
+            .map(|(path, header)| {
+                let shared = self
+                    .header_keys(header)
+                    .into_iter()
+                    .any(|key| header_counts.get(&key).is_some_and(|count| *count > 1));
+                if !shared {
+                    return String::from(header);
+                }
+                let mut others: Vec<&Path> = name_keys(path)
+                    .iter()
+                    .filter_map(|key| names.get(key))
+                    .flatten()
+                    .copied()
+                    .filter(|other| *other != path)
+                    .collect();
+                others.sort();
+                others.dedup();
+                self.qualified_target(path, &others)
+            })
+            .collect()
+    }
+
+    /// The path of the note at `path` qualified with as few folders as needed so that it names
+    /// none of `others`.
+    fn qualified_target(&self, path: &Path, others: &[&Path]) -> String {
+        let components = relative_components(&self.root, path).unwrap_or_default();
+        let mut stems = components.clone();
+        if let Some(last) = stems.last_mut() {
+            if let Some(stem) = Path::new(last.as_str()).file_stem() {
+                *last = stem.to_string_lossy().into_owned();
+            }
+        }
+        let candidates = (2..=stems.len())
+            .map(|length| stems[stems.len() - length..].join("/"))
+            .chain([
+                format!("/{}", stems.join("/")),
+                format!("/{}", components.join("/")),
+            ]);
+        let mut last = String::new();
+        for candidate in candidates {
+            if !others
+                .iter()
+                .any(|other| self.names_path(&candidate, other))
+            {
+                return candidate;
+            }
+            last = candidate;
+        }
+        last
+    }
+
+    /// The keys two headers share when one matches the other.
+    fn header_keys(&self, header: &str) -> Vec<HeaderKey> {
+        match self.matching {
+            LinkMatching::Strict => vec![HeaderKey::Exact(String::from(header))],
+            LinkMatching::Normalized => vec![
+                HeaderKey::Normalized(normalize_key(header)),
+                HeaderKey::Slug(slug_key(header)),
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum HeaderKey {
+    Exact(String),
+    Normalized(String),
+    Slug(String),
+}
+
+/// The keys of the file stem and file name of a note, shared by every note a path-qualified link
+/// ending with either may name.
+fn name_keys(path: &Path) -> Vec<String> {
+    let mut keys: Vec<String> = file_names(path).iter().map(|name| slug_key(name)).collect();
+    keys.dedup();
+    keys
+}
+
+/// The file stem and file name of a note, either of which a path-qualified link may end with.
+pub fn file_names(path: &Path) -> Vec<String> {
+    let mut names: Vec<String> = [path.file_stem(), path.file_name()]
+        .into_iter()
+        .flatten()
+        .map(|name| name.to_string_lossy().into_owned())
+        .collect();
+    names.dedup();
+    names
+}
+
+fn relative_components(root: &Path, path: &Path) -> Option<Vec<String>> {
+    let relative = path.strip_prefix(root).ok()?;
+    Some(
+        relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy().into_owned())
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vault_resolver(matching: LinkMatching) -> LinkResolver {
+        LinkResolver::new(PathBuf::from("/vault"), matching)
+    }
+
+    #[test]
+    fn test_link_resolver_names_path() {
+        let resolver = vault_resolver(LinkMatching::Normalized);
+        let path = Path::new("/vault/work/projects/Meeting.md");
+        assert!(resolver.names_path("projects/Meeting", path));
+        assert!(resolver.names_path("work/projects/meeting", path));
+        assert!(resolver.names_path("projects/Meeting.md", path));
+        assert!(resolver.names_path("/work/projects/Meeting", path));
+        assert!(!resolver.names_path("/projects/Meeting", path));
+        assert!(!resolver.names_path("Meeting", path));
+        assert!(!resolver.names_path("home/Meeting", path));
+        assert!(!resolver.names_path("vault/work/projects/Meeting", path));
+
+        let strict = vault_resolver(LinkMatching::Strict);
+        assert!(!strict.names_path("projects/meeting", path));
+        assert!(strict.names_path("/Note", Path::new("/vault/Note.org")));
+    }
+
+    #[test]
+    fn test_link_resolver_shortest_targets() {
+        let resolver = vault_resolver(LinkMatching::Normalized);
+        let notes = vec![
+            (
+                PathBuf::from("/vault/work/a/Meeting.md"),
+                String::from("Meeting"),
+            ),
+            (
+                PathBuf::from("/vault/home/a/Meeting.md"),
+                String::from("Meeting"),
+            ),
+            (PathBuf::from("/vault/Meeting.md"), String::from("Meeting")),
+            (PathBuf::from("/vault/Meeting.org"), String::from("meeting")),
+            (
+                PathBuf::from("/vault/work/Recipes.md"),
+                String::from("Recipes"),
+            ),
+        ];
+        assert_eq!(
+            resolver.shortest_targets(&notes),
+            vec![
+                "work/a/Meeting",
+                "home/a/Meeting",
+                "/Meeting.md",
+                "/Meeting.org",
+                "Recipes"
+            ]
+        );
+
+        let strict = vault_resolver(LinkMatching::Strict);
+        assert_eq!(strict.shortest_targets(&notes)[3], "meeting");
+    }
+}
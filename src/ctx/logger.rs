@@ -1,3 +1,4 @@
+use log4rs::append::console::{ConsoleAppender, Target};
 use log4rs::append::file::FileAppender;
 use log4rs::encode::pattern::PatternEncoder;
 
@@ -47,6 +48,42 @@ impl Logger for StandardLogger {
     }
 }
 
+/// Logs to stderr, for sessions that write nothing to the project. Stdout is left to the language
+/// server protocol.
+pub struct StderrLogger {}
+
+impl StderrLogger {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Default for StderrLogger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Logger for StderrLogger {
+    fn initialize(&self, _project_root: &ProjectRoot) -> crate::Result<()> {
+        let log = ConsoleAppender::builder()
+            .encoder(Box::new(PatternEncoder::new("{d} - {m}{n}")))
+            .target(Target::Stderr)
+            .build();
+
+        let log_config = Config::builder()
+            .appender(Appender::builder().build("log", Box::new(log)))
+            .build(
+                Root::builder()
+                    .appender("log")
+                    .build(log_level(env_var_log_level())),
+            )?;
+
+        log4rs::init_config(log_config).unwrap();
+        Ok(())
+    }
+}
+
 fn log_level(env_var_log_level: crate::Result<String>) -> LevelFilter {
     match env_var_log_level {
         Ok(lvl) => match &*lvl {
@@ -89,6 +126,16 @@ pub mod mocks {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_stderr_logger_writes_nothing_to_the_project() -> crate::Result<()> {
+        let tmp_dir = tempfile::Builder::new().prefix("grimoire").tempdir()?;
+        let project_root = ProjectRoot::new(tmp_dir.path().to_path_buf())?;
+        StderrLogger::new().initialize(&project_root)?;
+        log::warn!("logged to stderr");
+        assert_eq!(std::fs::read_dir(tmp_dir.path())?.count(), 0);
+        Ok(())
+    }
+
     #[test]
     fn test_log_level_default() {
         assert_eq!(
@@ -0,0 +1,106 @@
+use crate::ctx::{Storage, TreeChanges};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+
+type Tree = BTreeMap<Vec<u8>, Vec<u8>>;
+
+/// Keeps the index in memory for the length of the process, for tests and for sessions that
+/// can't or shouldn't write to the project, such as on read-only media.
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    trees: RefCell<HashMap<String, Tree>>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn get(&self, tree: &str, key: &[u8]) -> crate::Result<Option<Vec<u8>>> {
+        Ok(self
+            .trees
+            .borrow()
+            .get(tree)
+            .and_then(|tree| tree.get(key).cloned()))
+    }
+
+    fn scan_prefix(&self, tree: &str, prefix: &[u8]) -> crate::Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let trees = self.trees.borrow();
+        let tree = match trees.get(tree) {
+            Some(tree) => tree,
+            None => return Ok(Vec::new()),
+        };
+        Ok(tree
+            .range(prefix.to_vec()..)
+            .take_while(|(key, _)| key.starts_with(prefix))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect())
+    }
+
+    fn commit(&self, changes: &[TreeChanges]) -> crate::Result<()> {
+        let mut trees = self.trees.borrow_mut();
+        for change in changes {
+            let tree = trees.entry(String::from(change.tree)).or_default();
+            if change.cleared {
+                tree.clear();
+            }
+            for (key, value) in change.writes.iter() {
+                match value {
+                    Some(value) => tree.insert(key.clone(), value.clone()),
+                    None => tree.remove(key),
+                };
+            }
+        }
+        Ok(())
+    }
+
+    fn flush(&self) -> crate::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_storage_commit() -> crate::Result<()> {
+        let storage = MemoryStorage::new();
+        assert_eq!(storage.get("files", b"a1")?, None);
+        assert!(storage.scan_prefix("files", b"")?.is_empty());
+
+        storage.commit(&[TreeChanges {
+            tree: "files",
+            cleared: false,
+            writes: vec![
+                (b"a1".to_vec(), Some(b"first".to_vec())),
+                (b"a2".to_vec(), Some(b"second".to_vec())),
+                (b"b1".to_vec(), Some(b"third".to_vec())),
+            ],
+        }])?;
+        assert_eq!(storage.get("files", b"a2")?, Some(b"second".to_vec()));
+        assert_eq!(
+            storage.scan_prefix("files", b"a")?,
+            vec![
+                (b"a1".to_vec(), b"first".to_vec()),
+                (b"a2".to_vec(), b"second".to_vec())
+            ]
+        );
+
+        storage.commit(&[TreeChanges {
+            tree: "files",
+            cleared: true,
+            writes: vec![
+                (b"b1".to_vec(), None),
+                (b"c1".to_vec(), Some(b"new".to_vec())),
+            ],
+        }])?;
+        assert_eq!(
+            storage.scan_prefix("files", b"")?,
+            vec![(b"c1".to_vec(), b"new".to_vec())]
+        );
+        Ok(())
+    }
+}
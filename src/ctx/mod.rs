@@ -1,9 +1,27 @@
+mod config;
+pub use self::config::*;
+
+mod link_resolver;
+pub use self::link_resolver::*;
+
 mod context;
 pub use self::context::*;
 
 mod database;
 pub use self::database::*;
 
+mod database_table;
+pub use self::database_table::*;
+
+mod storage;
+pub use self::storage::*;
+
+mod sled_storage;
+pub use self::sled_storage::*;
+
+mod memory_storage;
+pub use self::memory_storage::*;
+
 mod logger;
 pub use self::logger::*;
 
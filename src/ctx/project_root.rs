@@ -86,7 +86,7 @@ impl From<PathBuf> for CurrentDirectoryArgs {
 }
 
 #[cfg(test)]
-pub mod mocks {
+mod mocks {
     use super::*;
     use tempfile::Builder;
 
@@ -0,0 +1,130 @@
+use crate::ctx::{Storage, TreeChanges};
+use sled::Transactional;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+/// Keeps the index on disk in a sled database, the default for a project.
+#[derive(Debug)]
+pub struct SledStorage {
+    db: sled::Db,
+    trees: RefCell<HashMap<String, sled::Tree>>,
+}
+
+impl SledStorage {
+    pub fn new(db: sled::Db) -> Self {
+        Self {
+            db,
+            trees: RefCell::new(HashMap::new()),
+        }
+    }
+
+    fn tree(&self, name: &str) -> crate::Result<sled::Tree> {
+        if let Some(tree) = self.trees.borrow().get(name) {
+            return Ok(tree.clone());
+        }
+        let tree = self.db.open_tree(name)?;
+        self.trees
+            .borrow_mut()
+            .insert(String::from(name), tree.clone());
+        Ok(tree)
+    }
+}
+
+impl Storage for SledStorage {
+    fn get(&self, tree: &str, key: &[u8]) -> crate::Result<Option<Vec<u8>>> {
+        Ok(self.tree(tree)?.get(key)?.map(|value| value.to_vec()))
+    }
+
+    fn scan_prefix(&self, tree: &str, prefix: &[u8]) -> crate::Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let mut entries = Vec::new();
+        for entry_result in self.tree(tree)?.scan_prefix(prefix) {
+            let (key, value) = entry_result?;
+            entries.push((key.to_vec(), value.to_vec()));
+        }
+        Ok(entries)
+    }
+
+    fn commit(&self, changes: &[TreeChanges]) -> crate::Result<()> {
+        let trees = changes
+            .iter()
+            .map(|change| self.tree(change.tree))
+            .collect::<crate::Result<Vec<sled::Tree>>>()?;
+        // Transactions can't iterate over a tree, so the keys of cleared trees are listed first.
+        let mut cleared_keys = Vec::new();
+        for (tree, change) in trees.iter().zip(changes) {
+            let mut keys = Vec::new();
+            if change.cleared {
+                for key_result in tree.iter().keys() {
+                    keys.push(key_result?);
+                }
+            }
+            cleared_keys.push(keys);
+        }
+
+        let trees: Vec<&sled::Tree> = trees.iter().collect();
+        let result: sled::transaction::TransactionResult<(), sled::Error> =
+            trees.as_slice().transaction(|trees| {
+                for ((tree, change), keys) in trees.iter().zip(changes).zip(&cleared_keys) {
+                    for key in keys {
+                        tree.remove(key)?;
+                    }
+                    for (key, value) in change.writes.iter() {
+                        match value {
+                            Some(value) => tree.insert(key.as_slice(), value.as_slice())?,
+                            None => tree.remove(key.as_slice())?,
+                        };
+                    }
+                }
+                Ok(())
+            });
+        result?;
+        Ok(())
+    }
+
+    fn flush(&self) -> crate::Result<()> {
+        self.db.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ctx::Database;
+
+    #[test]
+    fn test_sled_storage_commit() -> crate::Result<()> {
+        let storage = SledStorage::new(Database::mock_connection());
+        storage.commit(&[TreeChanges {
+            tree: "files",
+            cleared: false,
+            writes: vec![
+                (b"a1".to_vec(), Some(b"first".to_vec())),
+                (b"a2".to_vec(), Some(b"second".to_vec())),
+                (b"b1".to_vec(), Some(b"third".to_vec())),
+            ],
+        }])?;
+        assert_eq!(storage.get("files", b"a1")?, Some(b"first".to_vec()));
+        assert_eq!(storage.scan_prefix("files", b"a")?.len(), 2);
+
+        storage.commit(&[
+            TreeChanges {
+                tree: "files",
+                cleared: true,
+                writes: vec![(b"a2".to_vec(), Some(b"kept".to_vec()))],
+            },
+            TreeChanges {
+                tree: "tags",
+                cleared: false,
+                writes: vec![(b"t".to_vec(), Some(b"tag".to_vec()))],
+            },
+        ])?;
+        assert_eq!(
+            storage.scan_prefix("files", b"")?,
+            vec![(b"a2".to_vec(), b"kept".to_vec())]
+        );
+        assert_eq!(storage.get("tags", b"t")?, Some(b"tag".to_vec()));
+        assert!(storage.flush().is_ok());
+        Ok(())
+    }
+}
@@ -0,0 +1,23 @@
+/// The writes to one tree that `Storage::commit` applies together with those to other trees.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TreeChanges {
+    pub tree: &'static str,
+    /// Whether every key of the tree is removed before the writes are applied.
+    pub cleared: bool,
+    /// The values inserted by key, or `None` for the keys removed.
+    pub writes: Vec<(Vec<u8>, Option<Vec<u8>>)>,
+}
+
+/// Where the index is kept: a set of named trees of ordered byte keys and values.
+pub trait Storage: std::fmt::Debug {
+    fn get(&self, tree: &str, key: &[u8]) -> crate::Result<Option<Vec<u8>>>;
+
+    /// The entries of `tree` whose key starts with `prefix`, in key order.
+    fn scan_prefix(&self, tree: &str, prefix: &[u8]) -> crate::Result<Vec<(Vec<u8>, Vec<u8>)>>;
+
+    /// Clears, inserts and removes across trees atomically: either every change is applied or
+    /// none is.
+    fn commit(&self, changes: &[TreeChanges]) -> crate::Result<()>;
+
+    fn flush(&self) -> crate::Result<()>;
+}
@@ -40,11 +40,26 @@ pub fn path_cannot_convert_to_string(path: &Path) -> Error {
     anyhow!(msg)
 }
 
-pub fn file_with_duplicate_header_created(old_file: &File, new_file: &File) -> Error {
-    let new_file_path = new_file.path();
-    let new_file_header = new_file.header();
-    let old_file_path = old_file.path();
-    let msg = format!("A file {new_file_path:?} was attempted to be saved with header {new_file_header:?}, but another file with the same header already existed at {old_file_path:?}");
+pub fn invalid_config(path: &Path, error: toml::de::Error) -> Error {
+    let msg = format!("The configuration file {path:?} is invalid: {error}");
+    log::error!("{}", msg);
+    anyhow!(msg)
+}
+
+pub fn invalid_date(date: &str) -> Error {
+    let msg = format!("The date {date:?} should be written as YYYY-MM-DD.");
+    log::warn!("{}", msg);
+    anyhow!(msg)
+}
+
+pub fn note_not_found(note: &str) -> Error {
+    let msg = format!("No note could be found for {note:?}");
+    log::warn!("{}", msg);
+    anyhow!(msg)
+}
+
+pub fn file_already_indexed(path: &Path) -> Error {
+    let msg = format!("The file {path:?} has already been indexed");
     log::warn!("{}", msg);
     anyhow!(msg)
 }
@@ -97,12 +112,27 @@ mod tests {
         );
     }
     #[test]
-    fn test_file_with_duplicate_header_created() {
-        let old_file = File::mock(None);
-        let new_file = File::mock(None);
+    fn test_file_already_indexed() {
+        let path = PathBuf::from("/notes/Note.md");
+        assert_eq!(
+            file_already_indexed(&path).to_string(),
+            "The file \"/notes/Note.md\" has already been indexed"
+        );
+    }
+
+    #[test]
+    fn test_note_not_found() {
+        assert_eq!(
+            note_not_found("Missing").to_string(),
+            "No note could be found for \"Missing\""
+        );
+    }
+
+    #[test]
+    fn test_invalid_date() {
         assert_eq!(
-            file_with_duplicate_header_created(&old_file, &new_file).to_string(),
-            format!("A file {:?} was attempted to be saved with header {:?}, but another file with the same header already existed at {:?}", new_file.serializable_path().unwrap(), new_file.header(), old_file.serializable_path().unwrap())
+            invalid_date("tomorrow").to_string(),
+            "The date \"tomorrow\" should be written as YYYY-MM-DD."
         );
     }
 
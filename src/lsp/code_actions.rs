@@ -0,0 +1,143 @@
+use lsp_types::{
+    CodeAction, CodeActionKind, CodeActionOrCommand, CodeActionParams, CodeActionResponse,
+    TextEdit, WorkspaceEdit,
+};
+use std::collections::{HashMap, HashSet};
+
+use crate::ctx::Context;
+use crate::lsp::Documents;
+use crate::parsers::{parse_task_line, LineIndex};
+
+/// Offers to check or uncheck the tasks within the requested range. Only the lines the note was
+/// parsed to have a task on qualify, so checkboxes in code blocks are left alone.
+pub fn code_actions(
+    context: &Context,
+    documents: &Documents,
+    params: CodeActionParams,
+) -> crate::Result<Option<CodeActionResponse>> {
+    let url = params.text_document.uri;
+    let task_lines: HashSet<u32> = documents
+        .parse(&url, context.config())?
+        .tasks()
+        .iter()
+        .map(|task| task.line())
+        .collect();
+    let text = documents.text(&url)?;
+    let index = LineIndex::new(&text);
+    let lines: Vec<&str> = text.lines().collect();
+    let mut actions = Vec::new();
+    for line_position in params.range.start.line..=params.range.end.line {
+        let line = match lines.get(line_position as usize) {
+            Some(line) => line,
+            None => break,
+        };
+        if !task_lines.contains(&line_position) {
+            continue;
+        }
+        let task = match parse_task_line(line, line_position) {
+            Some(task) => task,
+            None => continue,
+        };
+        let (title, checkbox) = match task.done {
+            true => (format!("Mark {:?} as open", task.text), " "),
+            false => (format!("Mark {:?} as done", task.text), "x"),
+        };
+        let edit = TextEdit::new(
+            index.location(&task.checkbox_location).range(),
+            String::from(checkbox),
+        );
+        actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+            title,
+            kind: Some(CodeActionKind::REFACTOR_REWRITE),
+            edit: Some(WorkspaceEdit {
+                changes: Some(HashMap::from([(url.clone(), vec![edit])])),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }));
+    }
+    Ok(Some(actions))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::File;
+    use lsp_types::{
+        CodeActionContext, PartialResultParams, Position, Range, TextDocumentIdentifier, Url,
+        WorkDoneProgressParams,
+    };
+
+    fn params(url: Url, range: Range) -> CodeActionParams {
+        CodeActionParams {
+            text_document: TextDocumentIdentifier { uri: url },
+            range,
+            context: CodeActionContext::default(),
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        }
+    }
+
+    fn edits(action: &CodeActionOrCommand, url: &Url) -> Vec<TextEdit> {
+        match action {
+            CodeActionOrCommand::CodeAction(action) => {
+                action.edit.as_ref().unwrap().changes.as_ref().unwrap()[url].clone()
+            }
+            other => panic!("expected a code action, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_code_actions_toggle_tasks() {
+        let documents = Documents::new();
+        let data = String::from("# Title\n\n- [ ] Open\n  - [x] Done\ntext\n");
+        let (_tmp_dir, path) = File::mock_disk_file(Some(data));
+        let url = Url::from_file_path(&path).unwrap();
+
+        let range = Range::new(Position::new(0, 0), Position::new(10, 0));
+        let actions = code_actions(&Context::mock(), &documents, params(url.clone(), range))
+            .unwrap()
+            .unwrap();
+        assert_eq!(actions.len(), 2);
+        assert_eq!(
+            edits(&actions[0], &url),
+            vec![TextEdit::new(
+                Range::new(Position::new(2, 3), Position::new(2, 4)),
+                String::from("x")
+            )]
+        );
+        assert_eq!(
+            edits(&actions[1], &url),
+            vec![TextEdit::new(
+                Range::new(Position::new(3, 5), Position::new(3, 6)),
+                String::from(" ")
+            )]
+        );
+    }
+
+    #[test]
+    fn test_code_actions_skip_code_blocks() {
+        let documents = Documents::new();
+        let data = String::from("# Title\n\n```\n- [ ] Code\n```\n- [ ] Open\n");
+        let (_tmp_dir, path) = File::mock_disk_file(Some(data));
+        let url = Url::from_file_path(&path).unwrap();
+
+        let range = Range::new(Position::new(0, 0), Position::new(10, 0));
+        let actions = code_actions(&Context::mock(), &documents, params(url.clone(), range))
+            .unwrap()
+            .unwrap();
+        assert_eq!(actions.len(), 1);
+        assert_eq!(edits(&actions[0], &url)[0].range.start, Position::new(5, 3));
+    }
+
+    #[test]
+    fn test_code_actions_outside_tasks() {
+        let documents = Documents::new();
+        let (_tmp_dir, path) = File::mock_disk_file(Some(String::from("# Title\n\n- [ ] Open\n")));
+        let url = Url::from_file_path(&path).unwrap();
+
+        let range = Range::new(Position::new(0, 2), Position::new(0, 2));
+        let actions = code_actions(&Context::mock(), &documents, params(url, range)).unwrap();
+        assert_eq!(actions, Some(vec![]));
+    }
+}
@@ -0,0 +1,368 @@
+use lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionParams, CompletionResponse, CompletionTextEdit,
+    Position, Range, TextEdit, Url,
+};
+
+use crate::ctx::Context;
+use crate::lsp::Documents;
+use crate::models::{normalize_key, File};
+use crate::parsers::{is_tag_char, LineIndex};
+
+pub fn completion(
+    context: &Context,
+    documents: &Documents,
+    params: CompletionParams,
+) -> crate::Result<Option<CompletionResponse>> {
+    let url = params.text_document_position.text_document.uri;
+    let position = params.text_document_position.position;
+    let line = match documents.line(&url, position.line)? {
+        Some(line) => line,
+        None => return Ok(None),
+    };
+    // Columns count UTF-16 code units, so the typed text is sliced at the matching byte.
+    let cursor = LineIndex::from_lines([(line.as_str(), 0)]).position_utf16(0, position.character);
+    let prefix = &line[..cursor.utf8_column as usize];
+    let position = Position::new(position.line, cursor.utf16_column);
+    let link = match open_wiki_link(prefix) {
+        Some(link) => link,
+        None => return tag_completion(context, prefix, position),
+    };
+
+    match link.split_once('^') {
+        Some((target, block)) => {
+            let target = target.split('#').next().unwrap_or_default().trim();
+            let replace_start = replace_start(position, block);
+            let range = Range::new(Position::new(position.line, replace_start), position);
+            let items = match target_file(context, documents, &url, target)? {
+                Some(file) => block_completion_items(&file, range),
+                None => Vec::new(),
+            };
+            Ok(Some(CompletionResponse::Array(items)))
+        }
+        None if link.contains(['#', '|']) => Ok(None),
+        None => note_completion(context, &url, link, position),
+    }
+}
+
+/// The column where `typed`, which ends at the cursor, starts.
+fn replace_start(position: Position, typed: &str) -> u32 {
+    position
+        .character
+        .saturating_sub(typed.encode_utf16().count() as u32)
+}
+
+fn open_wiki_link(prefix: &str) -> Option<&str> {
+    let start = prefix.rfind("[[")?;
+    let link = &prefix[start + 2..];
+    match link.contains("]]") {
+        true => None,
+        false => Some(link),
+    }
+}
+
+fn tag_completion(
+    context: &Context,
+    prefix: &str,
+    position: Position,
+) -> crate::Result<Option<CompletionResponse>> {
+    let tag = match open_tag(prefix) {
+        Some(tag) => tag,
+        None => return Ok(None),
+    };
+    let replace_start = replace_start(position, tag);
+    let range = Range::new(Position::new(position.line, replace_start), position);
+
+    let mut names: Vec<String> = crate::services::all_tags(context)?
+        .into_iter()
+        .map(|tag| String::from(tag.name()))
+        .filter(|name| name.starts_with(tag))
+        .collect();
+    names.sort();
+    names.dedup();
+    let items = names
+        .into_iter()
+        .map(|name| CompletionItem {
+            label: name.clone(),
+            kind: Some(CompletionItemKind::KEYWORD),
+            text_edit: Some(CompletionTextEdit::Edit(TextEdit::new(range, name))),
+            ..Default::default()
+        })
+        .collect();
+    Ok(Some(CompletionResponse::Array(items)))
+}
+
+fn open_tag(prefix: &str) -> Option<&str> {
+    let start = prefix.rfind('#')?;
+    let tag = &prefix[start + 1..];
+    let preceded_by_whitespace = prefix[..start]
+        .chars()
+        .last()
+        .map(char::is_whitespace)
+        .unwrap_or(true);
+    match preceded_by_whitespace && tag.chars().all(is_tag_char) {
+        true => Some(tag),
+        false => None,
+    }
+}
+
+/// Proposes the notes a link can target, each by the shortest target naming it alone: its
+/// header, or a path such as `folder/Note` when several notes share it.
+fn note_completion(
+    context: &Context,
+    url: &Url,
+    typed: &str,
+    position: Position,
+) -> crate::Result<Option<CompletionResponse>> {
+    let replace_start = replace_start(position, typed);
+    let range = Range::new(Position::new(position.line, replace_start), position);
+
+    let mut files = Vec::new();
+    context.db().execute(|repository| {
+        files = repository.files().find_all()?;
+        Ok(())
+    })?;
+    let notes: Vec<(std::path::PathBuf, String)> = files
+        .iter()
+        .map(|file| (file.path().clone(), file.header()))
+        .collect();
+    let current_path = url.to_file_path().ok();
+    let typed = normalize_key(typed);
+    let resolver = context.link_resolver();
+
+    let mut items: Vec<CompletionItem> = notes
+        .iter()
+        .zip(resolver.shortest_targets(&notes))
+        .filter(|((path, _), _)| Some(path) != current_path.as_ref())
+        .filter_map(|((path, header), target)| {
+            let matches_typed =
+                normalize_key(&target).contains(&typed) || normalize_key(header).contains(&typed);
+            matches_typed.then(|| CompletionItem {
+                label: target.clone(),
+                kind: Some(CompletionItemKind::FILE),
+                detail: Some(format!("{header} ({})", path.display())),
+                text_edit: Some(CompletionTextEdit::Edit(TextEdit::new(range, target))),
+                ..Default::default()
+            })
+        })
+        .collect();
+    items.sort_by(|a, b| a.label.cmp(&b.label));
+    Ok(Some(CompletionResponse::Array(items)))
+}
+
+fn target_file(
+    context: &Context,
+    documents: &Documents,
+    url: &Url,
+    target: &str,
+) -> crate::Result<Option<File>> {
+    if target.is_empty() {
+        return documents.parse(url, context.config()).map(Some);
+    }
+
+    let mut file_option = None;
+    context.db().execute(|repository| {
+        file_option = repository.files().find(target, &context.link_resolver())?;
+        Ok(())
+    })?;
+    Ok(file_option)
+}
+
+fn block_completion_items(file: &File, range: Range) -> Vec<CompletionItem> {
+    file.blocks()
+        .iter()
+        .map(|block| CompletionItem {
+            label: String::from(block.id()),
+            kind: Some(CompletionItemKind::REFERENCE),
+            detail: Some(format!(
+                "{} (line {})",
+                file.header(),
+                block.location().line() + 1
+            )),
+            text_edit: Some(CompletionTextEdit::Edit(TextEdit::new(
+                range,
+                String::from(block.id()),
+            ))),
+            ..Default::default()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ctx::Config;
+    use lsp_types::{
+        PartialResultParams, TextDocumentIdentifier, TextDocumentPositionParams,
+        WorkDoneProgressParams,
+    };
+
+    fn params(url: Url, position: Position) -> CompletionParams {
+        CompletionParams {
+            text_document_position: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri: url },
+                position,
+            },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+            context: None,
+        }
+    }
+
+    fn labels(response: Option<CompletionResponse>) -> Vec<String> {
+        match response {
+            Some(CompletionResponse::Array(items)) => {
+                items.into_iter().map(|item| item.label).collect()
+            }
+            other => panic!("expected completion items, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_open_wiki_link() {
+        assert_eq!(open_wiki_link("see [[Note^bl"), Some("Note^bl"));
+        assert_eq!(open_wiki_link("see [[Note]] and ^"), None);
+        assert_eq!(open_wiki_link("no link"), None);
+    }
+
+    #[test]
+    fn test_open_tag() {
+        assert_eq!(open_tag("text #proj"), Some("proj"));
+        assert_eq!(open_tag("#"), Some(""));
+        assert_eq!(open_tag("text#proj"), None);
+        assert_eq!(open_tag("#done and more"), None);
+    }
+
+    #[test]
+    fn test_completion_tags() {
+        let context = Context::mock();
+        let (_tagged_dir, tagged_path) = File::mock_disk_file(Some(String::from(
+            "# Tagged\n\n#project/lsp #project/lsp #personal #inbox\n",
+        )));
+        let mut file = File::parse_from_path(tagged_path).unwrap();
+        context
+            .db()
+            .execute(|repository| {
+                for tag in file.tags_mut() {
+                    tag.upsert(&repository.tags())?;
+                }
+                Ok(())
+            })
+            .unwrap();
+        let (_source_dir, source_path) =
+            File::mock_disk_file(Some(String::from("# Source\n\nsee #p\n")));
+        let url = Url::from_file_path(&source_path).unwrap();
+        let documents = Documents::new();
+
+        let response = completion(&context, &documents, params(url, Position::new(2, 6)));
+        assert_eq!(labels(response.unwrap()), vec!["personal", "project/lsp"]);
+    }
+
+    #[test]
+    fn test_completion_blocks_of_target_note() {
+        let context = Context::mock();
+        let (_target_dir, target_path) =
+            File::mock_disk_file(Some(String::from("# Target\n\nfirst ^one\nsecond ^two\n")));
+        let mut target = File::parse_from_path(target_path).unwrap();
+        context
+            .db()
+            .execute(|repository| target.create(&repository.files()))
+            .unwrap();
+
+        let (_source_dir, source_path) =
+            File::mock_disk_file(Some(String::from("# Source\n\n[[Target^\n")));
+        let url = Url::from_file_path(&source_path).unwrap();
+        let documents = Documents::new();
+
+        let response = completion(&context, &documents, params(url, Position::new(2, 9)));
+        assert_eq!(labels(response.unwrap()), vec!["one", "two"]);
+    }
+
+    #[test]
+    fn test_completion_blocks_of_current_note() {
+        let context = Context::mock();
+        let (_tmp_dir, path) =
+            File::mock_disk_file(Some(String::from("# Source\n\nlocal ^here\n[[^h\n")));
+        let url = Url::from_file_path(&path).unwrap();
+        let documents = Documents::new();
+
+        let response = completion(&context, &documents, params(url, Position::new(3, 4)));
+        assert_eq!(labels(response.unwrap()), vec!["here"]);
+    }
+
+    #[test]
+    fn test_completion_notes_by_shortest_target() {
+        let context = Context::mock();
+        let root = context.project_root().file_path();
+        let config = Config::default();
+        for (path, text) in [
+            ("work/meeting.md", "# Meeting\n"),
+            ("home/meeting.md", "# Meeting\n"),
+            ("recipes.md", "# Recipes\n"),
+        ] {
+            let file = File::parse_from_str(root.join(path), text, &config).unwrap();
+            context
+                .db()
+                .execute(|repository| repository.files().create_file(&file))
+                .unwrap();
+        }
+
+        let (_source_dir, source_path) =
+            File::mock_disk_file(Some(String::from("# Source\n\n[[mee\n")));
+        let url = Url::from_file_path(&source_path).unwrap();
+        let documents = Documents::new();
+
+        let response = completion(&context, &documents, params(url, Position::new(2, 5)));
+        assert_eq!(
+            labels(response.unwrap()),
+            vec!["home/meeting", "work/meeting"]
+        );
+    }
+
+    #[test]
+    fn test_completion_counts_utf16_columns() {
+        let context = Context::mock();
+        let (_tmp_dir, path) =
+            File::mock_disk_file(Some(String::from("# Source\n\n😀 [[^h\nlocal ^here\n")));
+        let url = Url::from_file_path(&path).unwrap();
+        let documents = Documents::new();
+
+        // The emoji is two UTF-16 code units, so `h` ends at column 7.
+        let response = completion(
+            &context,
+            &documents,
+            params(url.clone(), Position::new(2, 7)),
+        );
+        let items = match response.unwrap() {
+            Some(CompletionResponse::Array(items)) => items,
+            _ => panic!("expected completion items"),
+        };
+        assert_eq!(
+            items[0].text_edit,
+            Some(CompletionTextEdit::Edit(TextEdit::new(
+                Range::new(Position::new(2, 6), Position::new(2, 7)),
+                String::from("here"),
+            )))
+        );
+
+        // A column past the end of the line or inside the emoji doesn't underflow.
+        let response = completion(
+            &context,
+            &documents,
+            params(url.clone(), Position::new(2, 50)),
+        );
+        assert_eq!(labels(response.unwrap()), vec!["here"]);
+        let response = completion(&context, &documents, params(url, Position::new(2, 1)));
+        assert!(response.unwrap().is_none());
+    }
+
+    #[test]
+    fn test_completion_outside_of_link() {
+        let context = Context::mock();
+        let (_tmp_dir, path) = File::mock_disk_file(None);
+        let url = Url::from_file_path(&path).unwrap();
+        let documents = Documents::new();
+
+        let response = completion(&context, &documents, params(url, Position::new(0, 4)));
+        assert!(response.unwrap().is_none());
+    }
+}
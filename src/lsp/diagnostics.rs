@@ -0,0 +1,571 @@
+use lsp_types::{
+    Diagnostic, DiagnosticSeverity, DocumentDiagnosticParams, DocumentDiagnosticReport,
+    DocumentDiagnosticReportResult, FullDocumentDiagnosticReport, NumberOrString, Position, Range,
+    RelatedFullDocumentDiagnosticReport, RelatedUnchangedDocumentDiagnosticReport,
+    UnchangedDocumentDiagnosticReport, Url, WorkspaceDiagnosticParams, WorkspaceDiagnosticReport,
+    WorkspaceDiagnosticReportResult, WorkspaceDocumentDiagnosticReport,
+    WorkspaceFullDocumentDiagnosticReport, WorkspaceUnchangedDocumentDiagnosticReport,
+};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+use crate::ctx::Context;
+use crate::lsp::Documents;
+use crate::models::File;
+
+pub const DIAGNOSTIC_SOURCE: &str = "grimoire";
+
+const BROKEN_LINK_CODE: &str = "broken-link";
+const BROKEN_SECTION_LINK_CODE: &str = "broken-section-link";
+const BROKEN_BLOCK_LINK_CODE: &str = "broken-block-link";
+const MALFORMED_LINK_CODE: &str = "malformed-link";
+const MISSING_HEADER_CODE: &str = "missing-header";
+const MULTIPLE_H1_CODE: &str = "multiple-h1";
+const OVERLAPPING_REFERENCES_CODE: &str = "overlapping-references";
+
+pub fn document_diagnostics(
+    context: &Context,
+    documents: &Documents,
+    params: DocumentDiagnosticParams,
+) -> crate::Result<DocumentDiagnosticReportResult> {
+    let url = params.text_document.uri;
+    if url.to_file_path().is_err() {
+        return Err(crate::errors::invalid_path_from_url(url));
+    }
+    let items = diagnostics_for_file(context, documents.parse(&url, context.config()))?;
+    let result_id = result_id(&items)?;
+
+    let report = if params.previous_result_id.as_ref() == Some(&result_id) {
+        DocumentDiagnosticReport::Unchanged(RelatedUnchangedDocumentDiagnosticReport {
+            related_documents: None,
+            unchanged_document_diagnostic_report: UnchangedDocumentDiagnosticReport { result_id },
+        })
+    } else {
+        DocumentDiagnosticReport::Full(RelatedFullDocumentDiagnosticReport {
+            related_documents: None,
+            full_document_diagnostic_report: FullDocumentDiagnosticReport {
+                result_id: Some(result_id),
+                items,
+            },
+        })
+    };
+    Ok(DocumentDiagnosticReportResult::Report(report))
+}
+
+pub fn workspace_diagnostics(
+    context: &Context,
+    documents: &Documents,
+    params: WorkspaceDiagnosticParams,
+) -> crate::Result<WorkspaceDiagnosticReportResult> {
+    let previous_result_ids: HashMap<Url, String> = params
+        .previous_result_ids
+        .into_iter()
+        .map(|previous| (previous.uri, previous.value))
+        .collect();
+
+    let mut reports = Vec::new();
+    for path in crate::services::note_paths_iter(context.project_root(), context.config()) {
+        let uri = match Url::from_file_path(&path) {
+            Ok(uri) => uri,
+            Err(_) => return Err(crate::errors::path_cannot_convert_to_string(&path)),
+        };
+        let items = diagnostics_for_file(context, documents.parse(&uri, context.config()))?;
+        let result_id = result_id(&items)?;
+
+        let report = if previous_result_ids.get(&uri) == Some(&result_id) {
+            WorkspaceDocumentDiagnosticReport::Unchanged(
+                WorkspaceUnchangedDocumentDiagnosticReport {
+                    uri,
+                    version: None,
+                    unchanged_document_diagnostic_report: UnchangedDocumentDiagnosticReport {
+                        result_id,
+                    },
+                },
+            )
+        } else {
+            WorkspaceDocumentDiagnosticReport::Full(WorkspaceFullDocumentDiagnosticReport {
+                uri,
+                version: None,
+                full_document_diagnostic_report: FullDocumentDiagnosticReport {
+                    result_id: Some(result_id),
+                    items,
+                },
+            })
+        };
+        reports.push(report);
+    }
+
+    Ok(WorkspaceDiagnosticReportResult::Report(
+        WorkspaceDiagnosticReport { items: reports },
+    ))
+}
+
+/// Diagnoses a parsed note, a note that couldn't be parsed being reported as a single error.
+fn diagnostics_for_file(
+    context: &Context,
+    file: crate::Result<File>,
+) -> crate::Result<Vec<Diagnostic>> {
+    let file = match file {
+        Ok(file) => file,
+        Err(err) => {
+            return Ok(vec![diagnostic(
+                Range::new(Position::new(0, 0), Position::new(0, 0)),
+                DiagnosticSeverity::ERROR,
+                MISSING_HEADER_CODE,
+                err.to_string(),
+            )])
+        }
+    };
+
+    let mut diagnostics = overlapping_references_diagnostics(&file);
+    if file.header_is_synthetic() {
+        diagnostics.push(diagnostic(
+            Range::new(Position::new(0, 0), Position::new(0, 0)),
+            DiagnosticSeverity::INFORMATION,
+            MISSING_HEADER_CODE,
+            format!(
+                "Note has no header, its file name {:?} identifies it",
+                file.header()
+            ),
+        ));
+    }
+    diagnostics.extend(multiple_h1_diagnostics(&file));
+    diagnostics.extend(file.malformed_references().iter().map(|malformed| {
+        diagnostic(
+            malformed.location().range(),
+            DiagnosticSeverity::WARNING,
+            MALFORMED_LINK_CODE,
+            String::from(malformed.message()),
+        )
+    }));
+    context.db().execute(|repository| {
+        for reference in file.references() {
+            let target = match repository
+                .files()
+                .find_target(reference, &context.link_resolver())?
+            {
+                Some(target) => target,
+                None => {
+                    diagnostics.push(diagnostic(
+                        reference.location().range(),
+                        DiagnosticSeverity::WARNING,
+                        BROKEN_LINK_CODE,
+                        format!("No note found {}", reference.target_description()),
+                    ));
+                    continue;
+                }
+            };
+            if let Some(block) = reference.block() {
+                if target.block(block).is_none() {
+                    diagnostics.push(diagnostic(
+                        reference.location().range(),
+                        DiagnosticSeverity::WARNING,
+                        BROKEN_BLOCK_LINK_CODE,
+                        format!("Note {:?} has no block {:?}", reference.header(), block),
+                    ));
+                }
+            }
+            if let Some(section) = reference.section() {
+                if target.heading(section).is_none() {
+                    diagnostics.push(diagnostic(
+                        reference.location().range(),
+                        DiagnosticSeverity::WARNING,
+                        BROKEN_SECTION_LINK_CODE,
+                        format!("Note {:?} has no heading {:?}", reference.header(), section),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    })?;
+    Ok(diagnostics)
+}
+
+fn overlapping_references_diagnostics(file: &File) -> Vec<Diagnostic> {
+    let references = file.references();
+    let mut diagnostics = Vec::new();
+    for (i, reference) in references.iter().enumerate() {
+        let range = reference.location().range();
+        let overlaps = references.iter().enumerate().any(|(j, other)| {
+            let other_range = other.location().range();
+            i != j
+                && range.start.line == other_range.start.line
+                && range.start < other_range.end
+                && other_range.start < range.end
+        });
+        if overlaps {
+            diagnostics.push(diagnostic(
+                range,
+                DiagnosticSeverity::ERROR,
+                OVERLAPPING_REFERENCES_CODE,
+                format!(
+                    "Reference to {:?} overlaps another reference",
+                    reference.header()
+                ),
+            ));
+        }
+    }
+    diagnostics
+}
+
+/// When a note has several H1s, every H1 other than the one the header policy selected competes
+/// with the note's header. The header may also come from the front matter or a lower heading.
+fn multiple_h1_diagnostics(file: &File) -> Vec<Diagnostic> {
+    let h1s: Vec<_> = file
+        .headings()
+        .iter()
+        .filter(|heading| heading.level() == 1)
+        .collect();
+    if h1s.len() < 2 {
+        return Vec::new();
+    }
+    let header_range = file.header_location().map(|location| location.range());
+    h1s.into_iter()
+        .filter(|heading| Some(heading.location().range()) != header_range)
+        .map(|heading| {
+            diagnostic(
+                heading.location().range(),
+                DiagnosticSeverity::WARNING,
+                MULTIPLE_H1_CODE,
+                format!(
+                    "Note has several H1 headings and its header is {:?}, so {:?} does not name it",
+                    file.header(),
+                    heading.text()
+                ),
+            )
+        })
+        .collect()
+}
+
+fn diagnostic(
+    range: Range,
+    severity: DiagnosticSeverity,
+    code: &str,
+    message: String,
+) -> Diagnostic {
+    Diagnostic {
+        range,
+        severity: Some(severity),
+        code: Some(NumberOrString::String(String::from(code))),
+        source: Some(String::from(DIAGNOSTIC_SOURCE)),
+        message,
+        ..Default::default()
+    }
+}
+
+fn result_id(diagnostics: &[Diagnostic]) -> crate::Result<String> {
+    let mut hasher = DefaultHasher::new();
+    serde_json::to_string(diagnostics)?.hash(&mut hasher);
+    Ok(format!("{:016x}", hasher.finish()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Reference;
+    use lsp_types::{PartialResultParams, TextDocumentIdentifier, WorkDoneProgressParams};
+
+    fn document_params(url: Url, previous_result_id: Option<String>) -> DocumentDiagnosticParams {
+        DocumentDiagnosticParams {
+            text_document: TextDocumentIdentifier { uri: url },
+            identifier: None,
+            previous_result_id,
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        }
+    }
+
+    fn full_items(result: DocumentDiagnosticReportResult) -> (String, Vec<Diagnostic>) {
+        match result {
+            DocumentDiagnosticReportResult::Report(DocumentDiagnosticReport::Full(report)) => {
+                let report = report.full_document_diagnostic_report;
+                (report.result_id.unwrap(), report.items)
+            }
+            other => panic!("expected a full report, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_document_diagnostics_broken_link() {
+        let context = Context::mock();
+        let (_tmp_dir, path) = File::mock_disk_file(None);
+        let url = Url::from_file_path(&path).unwrap();
+
+        let (_, items) = full_items(
+            document_diagnostics(&context, &Documents::new(), document_params(url, None)).unwrap(),
+        );
+        assert_eq!(items.len(), 1);
+        assert_eq!(
+            items[0].code,
+            Some(NumberOrString::String(String::from(BROKEN_LINK_CODE)))
+        );
+    }
+
+    #[test]
+    fn test_document_diagnostics_resolved_link() {
+        let context = Context::mock();
+        let (_tmp_dir, path) = File::mock_disk_file(Some(String::from(
+            "# Test Reference\n\n[[Test Reference]]\n",
+        )));
+        let mut file = File::parse_from_path(path.clone()).unwrap();
+        context
+            .db()
+            .execute(|repository| file.create(&repository.files()))
+            .unwrap();
+        let url = Url::from_file_path(&path).unwrap();
+
+        let (_, items) = full_items(
+            document_diagnostics(&context, &Documents::new(), document_params(url, None)).unwrap(),
+        );
+        assert!(items.is_empty());
+    }
+
+    #[test]
+    fn test_document_diagnostics_markdown_links() {
+        let context = Context::mock();
+        let (_tmp_dir, path) = File::mock_disk_file(Some(String::from(
+            "# Test Reference\n\n## Section\n\n[ok](test.md#section) [broken](missing.md)\n",
+        )));
+        let mut file = File::parse_from_path(path.clone()).unwrap();
+        context
+            .db()
+            .execute(|repository| file.create(&repository.files()))
+            .unwrap();
+        let url = Url::from_file_path(&path).unwrap();
+
+        let (_, items) = full_items(
+            document_diagnostics(&context, &Documents::new(), document_params(url, None)).unwrap(),
+        );
+        assert_eq!(items.len(), 1);
+        assert_eq!(
+            items[0].code,
+            Some(NumberOrString::String(String::from(BROKEN_LINK_CODE)))
+        );
+        assert_eq!(
+            items[0].message,
+            format!(
+                "No note found at path {:?}",
+                path.parent().unwrap().join("missing.md")
+            )
+        );
+    }
+
+    #[test]
+    fn test_document_diagnostics_broken_section_link() {
+        let context = Context::mock();
+        let (_tmp_dir, path) = File::mock_disk_file(Some(String::from(
+            "# Test Reference\n\n## Section\n\n[[Test Reference#Section]] [[#Missing]]\n",
+        )));
+        let mut file = File::parse_from_path(path.clone()).unwrap();
+        context
+            .db()
+            .execute(|repository| file.create(&repository.files()))
+            .unwrap();
+        let url = Url::from_file_path(&path).unwrap();
+
+        let (_, items) = full_items(
+            document_diagnostics(&context, &Documents::new(), document_params(url, None)).unwrap(),
+        );
+        assert_eq!(items.len(), 1);
+        assert_eq!(
+            items[0].code,
+            Some(NumberOrString::String(String::from(
+                BROKEN_SECTION_LINK_CODE
+            )))
+        );
+    }
+
+    #[test]
+    fn test_document_diagnostics_broken_block_link() {
+        let context = Context::mock();
+        let (_tmp_dir, path) = File::mock_disk_file(Some(String::from(
+            "# Test Reference\n\nA quote ^quote\n\n[[^quote]] [[Test Reference^missing]]\n",
+        )));
+        let mut file = File::parse_from_path(path.clone()).unwrap();
+        context
+            .db()
+            .execute(|repository| file.create(&repository.files()))
+            .unwrap();
+        let url = Url::from_file_path(&path).unwrap();
+
+        let (_, items) = full_items(
+            document_diagnostics(&context, &Documents::new(), document_params(url, None)).unwrap(),
+        );
+        assert_eq!(items.len(), 1);
+        assert_eq!(
+            items[0].code,
+            Some(NumberOrString::String(String::from(BROKEN_BLOCK_LINK_CODE)))
+        );
+    }
+
+    #[test]
+    fn test_document_diagnostics_multiple_h1() {
+        let context = Context::mock();
+        let (_tmp_dir, path) = File::mock_disk_file(Some(String::from(
+            "# Test Reference\n\nOther\n=====\n\n## Section\n",
+        )));
+        let url = Url::from_file_path(&path).unwrap();
+
+        let (_, items) = full_items(
+            document_diagnostics(&context, &Documents::new(), document_params(url, None)).unwrap(),
+        );
+        assert_eq!(items.len(), 1);
+        assert_eq!(
+            items[0].code,
+            Some(NumberOrString::String(String::from(MULTIPLE_H1_CODE)))
+        );
+        assert_eq!(items[0].range.start, Position::new(2, 0));
+        assert_eq!(items[0].severity, Some(DiagnosticSeverity::WARNING));
+        assert!(items[0]
+            .message
+            .contains("its header is \"Test Reference\""));
+    }
+
+    #[test]
+    fn test_document_diagnostics_multiple_h1_with_front_matter_title() {
+        let context = Context::mock();
+        let (_tmp_dir, path) = File::mock_disk_file(Some(String::from(
+            "---\ntitle: From Front Matter\n---\n# First\n\n# Second\n",
+        )));
+        let url = Url::from_file_path(&path).unwrap();
+
+        let (_, items) = full_items(
+            document_diagnostics(&context, &Documents::new(), document_params(url, None)).unwrap(),
+        );
+        let starts: Vec<Position> = items.iter().map(|item| item.range.start).collect();
+        assert_eq!(starts, vec![Position::new(3, 0), Position::new(5, 0)]);
+        assert!(items[0]
+            .message
+            .contains("its header is \"From Front Matter\", so \"First\""));
+    }
+
+    #[test]
+    fn test_document_diagnostics_malformed_links() {
+        let context = Context::mock();
+        let (_tmp_dir, path) = File::mock_disk_file(Some(String::from(
+            "# Test Reference\n\nSee [[Test Reference\n",
+        )));
+        let url = Url::from_file_path(&path).unwrap();
+
+        let (_, items) = full_items(
+            document_diagnostics(&context, &Documents::new(), document_params(url, None)).unwrap(),
+        );
+        assert_eq!(items.len(), 1);
+        assert_eq!(
+            items[0].code,
+            Some(NumberOrString::String(String::from(MALFORMED_LINK_CODE)))
+        );
+        assert_eq!(
+            items[0].range,
+            Range::new(Position::new(2, 4), Position::new(2, 20))
+        );
+        assert_eq!(items[0].message, "Link is never closed with `]]`");
+    }
+
+    #[test]
+    fn test_document_diagnostics_missing_header() {
+        let context = Context::mock();
+        let (_tmp_dir, path) = File::mock_disk_file(Some(String::from("no header here\n")));
+        let url = Url::from_file_path(&path).unwrap();
+
+        let (_, items) = full_items(
+            document_diagnostics(&context, &Documents::new(), document_params(url, None)).unwrap(),
+        );
+        assert_eq!(items.len(), 1);
+        assert_eq!(
+            items[0].code,
+            Some(NumberOrString::String(String::from(MISSING_HEADER_CODE)))
+        );
+        assert_eq!(items[0].severity, Some(DiagnosticSeverity::INFORMATION));
+        assert_eq!(
+            items[0].message,
+            "Note has no header, its file name \"test\" identifies it"
+        );
+    }
+
+    #[test]
+    fn test_document_diagnostics_unchanged() {
+        let context = Context::mock();
+        let (_tmp_dir, path) = File::mock_disk_file(None);
+        let url = Url::from_file_path(&path).unwrap();
+
+        let (result_id, _) = full_items(
+            document_diagnostics(
+                &context,
+                &Documents::new(),
+                document_params(url.clone(), None),
+            )
+            .unwrap(),
+        );
+        let result = document_diagnostics(
+            &context,
+            &Documents::new(),
+            document_params(url, Some(result_id.clone())),
+        )
+        .unwrap();
+        match result {
+            DocumentDiagnosticReportResult::Report(DocumentDiagnosticReport::Unchanged(report)) => {
+                assert_eq!(
+                    report.unchanged_document_diagnostic_report.result_id,
+                    result_id
+                )
+            }
+            other => panic!("expected an unchanged report, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_document_diagnostics_invalid_url() {
+        let context = Context::mock();
+        let url = Url::parse("https://example.com/note.md").unwrap();
+        assert!(
+            document_diagnostics(&context, &Documents::new(), document_params(url, None)).is_err()
+        );
+    }
+
+    #[test]
+    fn test_workspace_diagnostics_empty_project() {
+        let context = Context::mock();
+        let params = WorkspaceDiagnosticParams {
+            identifier: None,
+            previous_result_ids: Vec::new(),
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+        match workspace_diagnostics(&context, &Documents::new(), params).unwrap() {
+            WorkspaceDiagnosticReportResult::Report(report) => assert!(report.items.is_empty()),
+            other => panic!("expected a report, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_overlapping_references_diagnostics() {
+        let mut file = File::mock(None);
+        assert!(overlapping_references_diagnostics(&file).is_empty());
+
+        let reference = file.references().first().unwrap();
+        let duplicate = Reference::new(
+            file.path().clone(),
+            reference.header(),
+            reference.location().clone(),
+        );
+        file.references_mut().push(duplicate);
+        assert_eq!(overlapping_references_diagnostics(&file).len(), 2);
+    }
+
+    #[test]
+    fn test_result_id_is_stable() {
+        let diagnostics = vec![diagnostic(
+            Range::default(),
+            DiagnosticSeverity::WARNING,
+            BROKEN_LINK_CODE,
+            String::from("message"),
+        )];
+        assert_eq!(
+            result_id(&diagnostics).unwrap(),
+            result_id(&diagnostics).unwrap()
+        );
+        assert_ne!(result_id(&diagnostics).unwrap(), result_id(&[]).unwrap());
+    }
+}
@@ -0,0 +1,219 @@
+use lsp_types::{
+    DidChangeTextDocumentParams, DidCloseTextDocumentParams, DidOpenTextDocumentParams, Url,
+};
+use std::collections::HashMap;
+
+use crate::ctx::Config;
+use crate::models::File;
+use crate::parsers::{IncrementalParser, NoteFormat};
+
+/// The open buffers, each kept parsed line by line so that an edit only parses what it changed.
+#[derive(Debug, Default)]
+pub struct Documents {
+    documents: HashMap<Url, Document>,
+    // Decides the format each buffer is parsed in, from the extension of its path.
+    config: Config,
+}
+
+#[derive(Debug)]
+struct Document {
+    text: String,
+    parser: IncrementalParser,
+}
+
+impl Documents {
+    pub fn new() -> Self {
+        Self::with_config(Config::default())
+    }
+
+    pub fn with_config(config: Config) -> Self {
+        Self {
+            documents: HashMap::new(),
+            config,
+        }
+    }
+
+    pub fn open(&mut self, params: DidOpenTextDocumentParams) {
+        let text = params.text_document.text;
+        let parser =
+            IncrementalParser::parse_with_format(&text, self.format(&params.text_document.uri));
+        self.documents
+            .insert(params.text_document.uri, Document { text, parser });
+    }
+
+    pub fn change(&mut self, params: DidChangeTextDocumentParams) {
+        let change = match params.content_changes.into_iter().last() {
+            Some(change) => change,
+            None => return,
+        };
+        match self.documents.get_mut(&params.text_document.uri) {
+            Some(document) => {
+                let parsed = document.parser.update(&change.text);
+                log::debug!("Parsed {parsed} lines of {}", params.text_document.uri);
+                document.text = change.text;
+            }
+            None => {
+                let format = self.format(&params.text_document.uri);
+                let parser = IncrementalParser::parse_with_format(&change.text, format);
+                let document = Document {
+                    text: change.text,
+                    parser,
+                };
+                self.documents.insert(params.text_document.uri, document);
+            }
+        }
+    }
+
+    fn format(&self, url: &Url) -> NoteFormat {
+        url.to_file_path()
+            .ok()
+            .and_then(|path| self.config.format(&path))
+            .unwrap_or_default()
+    }
+
+    pub fn close(&mut self, params: DidCloseTextDocumentParams) {
+        self.documents.remove(&params.text_document.uri);
+    }
+
+    pub fn text(&self, url: &Url) -> crate::Result<String> {
+        if let Some(document) = self.documents.get(url) {
+            return Ok(document.text.clone());
+        }
+        let path = match url.to_file_path() {
+            Ok(path) => path,
+            Err(_) => return Err(crate::errors::invalid_path_from_url(url.clone())),
+        };
+        Ok(std::fs::read_to_string(path)?)
+    }
+
+    pub fn line(&self, url: &Url, line: u32) -> crate::Result<Option<String>> {
+        Ok(self.text(url)?.lines().nth(line as usize).map(String::from))
+    }
+
+    /// Parses the note at `url` from its open buffer, unsaved edits included, or from disk.
+    pub fn parse(&self, url: &Url, config: &Config) -> crate::Result<File> {
+        let path = match url.to_file_path() {
+            Ok(path) => path,
+            Err(_) => return Err(crate::errors::invalid_path_from_url(url.clone())),
+        };
+        match self.documents.get(url) {
+            Some(document) => File::from_parser(path, &document.parser, config),
+            None => File::parse_from_path_with_config(path, config),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lsp_types::{
+        TextDocumentContentChangeEvent, TextDocumentIdentifier, TextDocumentItem,
+        VersionedTextDocumentIdentifier,
+    };
+
+    fn url() -> Url {
+        Url::parse("file:///tmp/grimoire/note.md").unwrap()
+    }
+
+    fn open(documents: &mut Documents, text: &str) {
+        documents.open(DidOpenTextDocumentParams {
+            text_document: TextDocumentItem {
+                uri: url(),
+                language_id: String::from("markdown"),
+                version: 1,
+                text: String::from(text),
+            },
+        });
+    }
+
+    #[test]
+    fn test_documents_open() {
+        let mut documents = Documents::new();
+        open(&mut documents, "# Note\n");
+        assert_eq!(documents.text(&url()).unwrap(), "# Note\n");
+    }
+
+    #[test]
+    fn test_documents_change() {
+        let mut documents = Documents::new();
+        open(&mut documents, "# Note\n");
+        documents.change(DidChangeTextDocumentParams {
+            text_document: VersionedTextDocumentIdentifier {
+                uri: url(),
+                version: 2,
+            },
+            content_changes: vec![TextDocumentContentChangeEvent {
+                range: None,
+                range_length: None,
+                text: String::from("# Changed\nsecond line"),
+            }],
+        });
+        assert_eq!(
+            documents.line(&url(), 1).unwrap(),
+            Some(String::from("second line"))
+        );
+        assert_eq!(documents.line(&url(), 2).unwrap(), None);
+        let file = documents.parse(&url(), &Config::default()).unwrap();
+        assert_eq!(file.header(), "Changed");
+    }
+
+    #[test]
+    fn test_documents_close_falls_back_to_disk() {
+        let (_tmp_dir, path) = File::mock_disk_file(Some(String::from("# On disk\n")));
+        let url = Url::from_file_path(&path).unwrap();
+        let mut documents = Documents::new();
+        documents.open(DidOpenTextDocumentParams {
+            text_document: TextDocumentItem {
+                uri: url.clone(),
+                language_id: String::from("markdown"),
+                version: 1,
+                text: String::from("# In memory\n"),
+            },
+        });
+        assert_eq!(documents.text(&url).unwrap(), "# In memory\n");
+
+        documents.close(DidCloseTextDocumentParams {
+            text_document: TextDocumentIdentifier { uri: url.clone() },
+        });
+        assert_eq!(documents.text(&url).unwrap(), "# On disk\n");
+    }
+
+    #[test]
+    fn test_documents_parse_dirty_buffer() {
+        let (_tmp_dir, path) = File::mock_disk_file(Some(String::from("# On disk\n")));
+        let url = Url::from_file_path(&path).unwrap();
+        let mut documents = Documents::new();
+        let config = Config::default();
+        assert_eq!(documents.parse(&url, &config).unwrap().header(), "On disk");
+
+        documents.open(DidOpenTextDocumentParams {
+            text_document: TextDocumentItem {
+                uri: url.clone(),
+                language_id: String::from("markdown"),
+                version: 1,
+                text: String::from("# Unsaved\n[[Link]]\n"),
+            },
+        });
+        let file = documents.parse(&url, &config).unwrap();
+        assert_eq!(file.path(), &path);
+        assert_eq!(file.header(), "Unsaved");
+        assert_eq!(file.references().len(), 1);
+    }
+
+    #[test]
+    fn test_documents_parse_org_buffer() {
+        let url = Url::parse("file:///tmp/grimoire/note.org").unwrap();
+        let mut documents = Documents::new();
+        documents.open(DidOpenTextDocumentParams {
+            text_document: TextDocumentItem {
+                uri: url.clone(),
+                language_id: String::from("org"),
+                version: 1,
+                text: String::from("* Org note\n[[Other]]\n"),
+            },
+        });
+        let file = documents.parse(&url, &Config::default()).unwrap();
+        assert_eq!(file.header(), "Org note");
+        assert_eq!(file.references()[0].header(), "Other");
+    }
+}
@@ -1,21 +1,16 @@
 use lsp_types::{Location, Position, Range, Url};
 
 use crate::ctx::Context;
-use crate::models::{File, Reference};
+use crate::lsp::Documents;
+use crate::models::{File, Location as FileLocation, Reference};
 
 pub fn find_markdown_references(
     context: &Context,
+    documents: &Documents,
     url: Url,
     position: Position,
 ) -> crate::Result<Vec<Location>> {
-    let path = match url.to_file_path() {
-        Ok(path) => path,
-        Err(_) => return Err(crate::errors::invalid_path_from_url(url)),
-    };
-    let source_file = match File::parse_from_path(path) {
-        Ok(source_file) => source_file,
-        Err(err) => return Err(err),
-    };
+    let source_file = documents.parse(&url, context.config())?;
     let references: Vec<&Reference> = source_file
         .references()
         .iter()
@@ -42,7 +37,9 @@ pub fn find_markdown_references(
 
     let mut file_option = None;
     context.db().execute(|repository| {
-        file_option = repository.files().find(&reference.header())?;
+        file_option = repository
+            .files()
+            .find_target(reference, &context.link_resolver())?;
         Ok(())
     })?;
 
@@ -51,14 +48,25 @@ pub fn find_markdown_references(
         None => return Ok(Vec::new()),
     };
 
-    Ok(vec![lsp_location_from_file(&file)?])
+    let location = match (reference.block(), reference.section()) {
+        (Some(block), _) => file.block(block).map(|block| block.location()),
+        (None, Some(section)) => file.heading(section).map(|heading| heading.location()),
+        (None, None) => None,
+    }
+    .or_else(|| file.header_location());
+
+    Ok(vec![lsp_location(&file, location)?])
 }
 
 fn lsp_location_from_file(file: &File) -> crate::Result<Location> {
-    let position = Position {
-        line: file.header_location().line_position,
-        character: file.header_location().start_char_position as u32,
-    };
+    lsp_location(file, file.header_location())
+}
+
+/// Points at `location`, or at the start of the note when its header is synthetic.
+fn lsp_location(file: &File, location: Option<&FileLocation>) -> crate::Result<Location> {
+    let position = location
+        .map(|location| location.range().start)
+        .unwrap_or_default();
     let path = file.serializable_path()?;
     let mut prefixed_path = String::from("file:");
     prefixed_path.push_str(&path);
@@ -82,4 +90,173 @@ mod tests {
         let location = lsp_location_from_file(&file);
         assert!(location.is_ok())
     }
+
+    #[test]
+    fn test_find_markdown_references_section() {
+        let context = Context::mock();
+        let data = String::from("# Target\n\ntext\n\n## Section\n\n[[Target#Section]]\n");
+        let (_tmp_dir, path) = File::mock_disk_file(Some(data));
+        let mut file = File::parse_from_path(path.clone()).unwrap();
+        context
+            .db()
+            .execute(|repository| file.create(&repository.files()))
+            .unwrap();
+
+        let url = Url::from_file_path(&path).unwrap();
+        let locations =
+            find_markdown_references(&context, &Documents::new(), url, Position::new(6, 3))
+                .unwrap();
+        assert_eq!(locations.len(), 1);
+        assert_eq!(locations[0].range.start, Position::new(4, 0));
+    }
+
+    #[test]
+    fn test_find_markdown_references_wiki_link_in_markdown_link_text() {
+        let context = Context::mock();
+        let data = String::from("# Target\n\n[see [[Other]]](#target)\n");
+        let (_tmp_dir, path) = File::mock_disk_file(Some(data));
+        let mut file = File::parse_from_path(path.clone()).unwrap();
+        context
+            .db()
+            .execute(|repository| file.create(&repository.files()))
+            .unwrap();
+
+        let url = Url::from_file_path(&path).unwrap();
+        let locations =
+            find_markdown_references(&context, &Documents::new(), url, Position::new(2, 8))
+                .unwrap();
+        assert_eq!(locations.len(), 1);
+        assert_eq!(locations[0].range.start, Position::new(0, 0));
+    }
+
+    #[test]
+    fn test_find_markdown_references_unsaved_buffer() {
+        let context = Context::mock();
+        let data = String::from("# Target\n\n## Section\n");
+        let (_tmp_dir, path) = File::mock_disk_file(Some(data));
+        let mut file = File::parse_from_path(path.clone()).unwrap();
+        context
+            .db()
+            .execute(|repository| file.create(&repository.files()))
+            .unwrap();
+
+        let url = Url::from_file_path(&path).unwrap();
+        let mut documents = Documents::new();
+        documents.open(lsp_types::DidOpenTextDocumentParams {
+            text_document: lsp_types::TextDocumentItem {
+                uri: url.clone(),
+                language_id: String::from("markdown"),
+                version: 2,
+                text: String::from("# Target\n\n## Section\n\n[[Target#Section]]\n"),
+            },
+        });
+        let locations =
+            find_markdown_references(&context, &documents, url, Position::new(4, 3)).unwrap();
+        assert_eq!(locations.len(), 1);
+        assert_eq!(locations[0].range.start, Position::new(2, 0));
+    }
+
+    #[test]
+    fn test_find_markdown_references_block() {
+        let context = Context::mock();
+        let data = String::from("# Target\n\nA quote ^quote\n\n[[Target^quote]]\n");
+        let (_tmp_dir, path) = File::mock_disk_file(Some(data));
+        let mut file = File::parse_from_path(path.clone()).unwrap();
+        context
+            .db()
+            .execute(|repository| file.create(&repository.files()))
+            .unwrap();
+
+        let url = Url::from_file_path(&path).unwrap();
+        let locations =
+            find_markdown_references(&context, &Documents::new(), url, Position::new(4, 3))
+                .unwrap();
+        assert_eq!(locations.len(), 1);
+        assert_eq!(locations[0].range.start, Position::new(2, 8));
+    }
+
+    #[test]
+    fn test_find_markdown_references_markdown_link() {
+        let context = Context::mock();
+        let data = String::from("# Target\n\n## Some Heading\n\n[see](./test.md#some-heading)\n");
+        let (_tmp_dir, path) = File::mock_disk_file(Some(data));
+        let mut file = File::parse_from_path(path.clone()).unwrap();
+        context
+            .db()
+            .execute(|repository| file.create(&repository.files()))
+            .unwrap();
+
+        let url = Url::from_file_path(&path).unwrap();
+        let locations =
+            find_markdown_references(&context, &Documents::new(), url, Position::new(4, 3))
+                .unwrap();
+        assert_eq!(locations.len(), 1);
+        assert_eq!(locations[0].range.start, Position::new(2, 0));
+    }
+
+    #[test]
+    fn test_find_markdown_references_file_name() {
+        let context = Context::mock();
+        let data = String::from("No header, only a link to [[test]]\n");
+        let (_tmp_dir, path) = File::mock_disk_file(Some(data));
+        let mut file = File::parse_from_path(path.clone()).unwrap();
+        context
+            .db()
+            .execute(|repository| file.create(&repository.files()))
+            .unwrap();
+
+        let url = Url::from_file_path(&path).unwrap();
+        let locations =
+            find_markdown_references(&context, &Documents::new(), url, Position::new(0, 30))
+                .unwrap();
+        assert_eq!(locations.len(), 1);
+        assert_eq!(locations[0].range.start, Position::new(0, 0));
+        assert_eq!(locations[0].uri, Url::from_file_path(&path).unwrap());
+    }
+
+    #[test]
+    fn test_find_markdown_references_missing_section() {
+        let context = Context::mock();
+        let data = String::from("# Target\n\n[[Target#Missing]]\n");
+        let (_tmp_dir, path) = File::mock_disk_file(Some(data));
+        let mut file = File::parse_from_path(path.clone()).unwrap();
+        context
+            .db()
+            .execute(|repository| file.create(&repository.files()))
+            .unwrap();
+
+        let url = Url::from_file_path(&path).unwrap();
+        let locations =
+            find_markdown_references(&context, &Documents::new(), url, Position::new(2, 3))
+                .unwrap();
+        assert_eq!(locations.len(), 1);
+        assert_eq!(locations[0].range.start, Position::new(0, 0));
+    }
+
+    #[test]
+    fn test_find_markdown_references_normalized_header() {
+        let context = Context::mock();
+        let data = String::from(
+            "# My Favorite Recipes\n\n[[my favorite recipes]] [[my-favorite-recipes]]\n",
+        );
+        let (_tmp_dir, path) = File::mock_disk_file(Some(data));
+        let mut file = File::parse_from_path(path.clone()).unwrap();
+        context
+            .db()
+            .execute(|repository| file.create(&repository.files()))
+            .unwrap();
+
+        let url = Url::from_file_path(&path).unwrap();
+        for character in [4, 30] {
+            let locations = find_markdown_references(
+                &context,
+                &Documents::new(),
+                url.clone(),
+                Position::new(2, character),
+            )
+            .unwrap();
+            assert_eq!(locations.len(), 1);
+            assert_eq!(locations[0].range.start, Position::new(0, 0));
+        }
+    }
 }
@@ -0,0 +1,92 @@
+use lsp_types::{Hover, HoverContents, HoverParams, MarkupContent, MarkupKind};
+
+use crate::ctx::Context;
+use crate::lsp::Documents;
+use crate::models::ReferenceKind;
+
+pub fn hover(
+    context: &Context,
+    documents: &Documents,
+    params: HoverParams,
+) -> crate::Result<Option<Hover>> {
+    let url = params.text_document_position_params.text_document.uri;
+    let position = params.text_document_position_params.position;
+    let source_file = documents.parse(&url, context.config())?;
+
+    let embed = match source_file.references().iter().find(|reference| {
+        reference.kind() == &ReferenceKind::Embed && reference.location().contains(&position)
+    }) {
+        Some(embed) => embed,
+        None => return Ok(None),
+    };
+
+    let content = match crate::services::render_reference(context, &source_file, embed)? {
+        Some(content) => content,
+        None => return Ok(None),
+    };
+    Ok(Some(Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value: content,
+        }),
+        range: Some(embed.location().range()),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::File;
+    use lsp_types::{
+        Position, TextDocumentIdentifier, TextDocumentPositionParams, Url, WorkDoneProgressParams,
+    };
+
+    fn params(url: Url, position: Position) -> HoverParams {
+        HoverParams {
+            text_document_position_params: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri: url },
+                position,
+            },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+        }
+    }
+
+    #[test]
+    fn test_hover_renders_embed() {
+        let context = Context::mock();
+        let (_target_dir, target_path) =
+            File::mock_disk_file(Some(String::from("# Target\nembedded text\n")));
+        let mut target = File::parse_from_path(target_path).unwrap();
+        context
+            .db()
+            .execute(|repository| target.create(&repository.files()))
+            .unwrap();
+
+        let (_source_dir, source_path) =
+            File::mock_disk_file(Some(String::from("# Source\n![[Target]] [[Target]]\n")));
+        let url = Url::from_file_path(&source_path).unwrap();
+
+        let embed_hover = hover(
+            &context,
+            &Documents::new(),
+            params(url.clone(), Position::new(1, 0)),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            embed_hover.contents,
+            HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: String::from("# Target\nembedded text"),
+            })
+        );
+
+        let link_hover = hover(
+            &context,
+            &Documents::new(),
+            params(url, Position::new(1, 15)),
+        )
+        .unwrap();
+        assert!(link_hover.is_none());
+    }
+}
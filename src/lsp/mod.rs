@@ -1,5 +1,32 @@
+mod code_actions;
+pub use self::code_actions::*;
+
+mod completion;
+pub use self::completion::*;
+
+mod diagnostics;
+pub use self::diagnostics::*;
+
+mod documents;
+pub use self::documents::*;
+
 mod goto;
 pub use self::goto::*;
 
+mod hover;
+pub use self::hover::*;
+
+mod references;
+pub use self::references::*;
+
+mod rename;
+pub use self::rename::*;
+
+mod search;
+pub use self::search::*;
+
 mod server_capabilities;
 pub use self::server_capabilities::*;
+
+mod symbols;
+pub use self::symbols::*;
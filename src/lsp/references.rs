@@ -0,0 +1,242 @@
+use lsp_types::{Location, ReferenceParams, Url};
+
+use crate::ctx::Context;
+use crate::lsp::Documents;
+use crate::models::{File, Tag};
+
+/// Lists every occurrence of the tag under the cursor, including the tags nested under it,
+/// or otherwise the backlinks to the note linked to or headed under the cursor.
+pub fn references(
+    context: &Context,
+    documents: &Documents,
+    params: ReferenceParams,
+) -> crate::Result<Option<Vec<Location>>> {
+    let url = params.text_document_position.text_document.uri;
+    let position = params.text_document_position.position;
+    let source_file = documents.parse(&url, context.config())?;
+
+    let tag = match source_file.tags().iter().find(|tag| {
+        tag.location()
+            .map(|location| location.contains(&position))
+            .unwrap_or(false)
+    }) {
+        Some(tag) => tag,
+        None => return backlinks(context, &source_file, position),
+    };
+
+    let locations = crate::services::find_tags(context, tag.name())?
+        .iter()
+        .map(tag_lsp_location)
+        .collect::<crate::Result<Vec<Location>>>()?;
+    Ok(Some(locations))
+}
+
+fn backlinks(
+    context: &Context,
+    source_file: &File,
+    position: lsp_types::Position,
+) -> crate::Result<Option<Vec<Location>>> {
+    let resolver = context.link_resolver();
+    let mut references = Vec::new();
+    let mut target = None;
+    context.db().execute(|repository| {
+        target = match source_file
+            .references()
+            .iter()
+            .find(|reference| reference.location().contains(&position))
+        {
+            Some(reference) => repository.files().find_target(reference, &resolver)?,
+            None if source_file
+                .header_location()
+                .is_some_and(|location| location.contains(&position)) =>
+            {
+                repository.files().find_by_path(source_file.path())?
+            }
+            None => None,
+        };
+        if let Some(target) = &target {
+            references = repository.references().find_linking_to(target, &resolver)?;
+        }
+        Ok(())
+    })?;
+    if target.is_none() {
+        return Ok(None);
+    }
+
+    let mut locations = Vec::new();
+    for reference in references.iter() {
+        let uri = match Url::from_file_path(reference.path()) {
+            Ok(uri) => uri,
+            Err(_) => {
+                return Err(crate::errors::path_cannot_convert_to_string(
+                    reference.path(),
+                ))
+            }
+        };
+        locations.push(Location::new(uri, reference.location().range()));
+    }
+    Ok(Some(locations))
+}
+
+/// Front matter tags have no inline location, so they point to the start of their note.
+pub fn tag_lsp_location(tag: &Tag) -> crate::Result<Location> {
+    let uri = match Url::from_file_path(tag.path()) {
+        Ok(uri) => uri,
+        Err(_) => return Err(crate::errors::path_cannot_convert_to_string(tag.path())),
+    };
+    let range = tag
+        .location()
+        .map(|location| location.range())
+        .unwrap_or_default();
+    Ok(Location::new(uri, range))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lsp_types::{
+        PartialResultParams, Position, Range, ReferenceContext, TextDocumentIdentifier,
+        TextDocumentPositionParams, WorkDoneProgressParams,
+    };
+
+    fn params(url: Url, position: Position) -> ReferenceParams {
+        ReferenceParams {
+            text_document_position: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri: url },
+                position,
+            },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+            context: ReferenceContext {
+                include_declaration: true,
+            },
+        }
+    }
+
+    fn index(context: &Context, data: &str) -> (tempfile::TempDir, std::path::PathBuf) {
+        let (tmp_dir, path) = File::mock_disk_file(Some(String::from(data)));
+        let mut file = File::parse_from_path(path.clone()).unwrap();
+        context
+            .db()
+            .execute(|repository| {
+                for tag in file.tags_mut() {
+                    tag.upsert(&repository.tags())?;
+                }
+                Ok(())
+            })
+            .unwrap();
+        (tmp_dir, path)
+    }
+
+    #[test]
+    fn test_references_on_tag() {
+        let context = Context::mock();
+        let (_first_dir, first_path) = index(&context, "# First\n\nsee #project\n");
+        let (_second_dir, second_path) = index(
+            &context,
+            "---\ntags: project\n---\n# Second\n#project/lsp #other\n",
+        );
+
+        let url = Url::from_file_path(&first_path).unwrap();
+        let mut locations = references(
+            &context,
+            &Documents::new(),
+            params(url.clone(), Position::new(2, 6)),
+        )
+        .unwrap()
+        .unwrap();
+        locations.sort_by_key(|location| (location.uri.to_string(), location.range.start));
+
+        let second_url = Url::from_file_path(&second_path).unwrap();
+        let mut expected = vec![
+            Location::new(url, Range::new(Position::new(2, 4), Position::new(2, 12))),
+            Location::new(second_url.clone(), Range::default()),
+            Location::new(
+                second_url,
+                Range::new(Position::new(4, 0), Position::new(4, 12)),
+            ),
+        ];
+        expected.sort_by_key(|location| (location.uri.to_string(), location.range.start));
+        assert_eq!(locations, expected);
+    }
+
+    #[test]
+    fn test_references_backlinks() {
+        let context = Context::mock();
+        let (_target_dir, target_path) = File::mock_disk_file(Some(String::from(
+            "---\naliases: [Nickname]\n---\n# Target\n",
+        )));
+        let target_name = target_path.file_name().unwrap().to_str().unwrap();
+        let target_link = format!(
+            "../{}/{}",
+            target_path
+                .parent()
+                .unwrap()
+                .file_name()
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            target_name
+        );
+        let (_source_dir, source_path) = File::mock_disk_file(Some(format!(
+            "# Source\n[[Target]] [[Nickname]]\n[markdown]({target_link}) [[Other]]\n"
+        )));
+        let mut target = File::parse_from_path(target_path.clone()).unwrap();
+        let mut source = File::parse_from_path(source_path.clone()).unwrap();
+        context
+            .db()
+            .execute(|repository| {
+                target.create(&repository.files())?;
+                source.create(&repository.files())?;
+                repository
+                    .references()
+                    .replace_references(source.path(), source.references())
+            })
+            .unwrap();
+
+        let source_url = Url::from_file_path(&source_path).unwrap();
+        let from_link = references(
+            &context,
+            &Documents::new(),
+            params(source_url.clone(), Position::new(1, 3)),
+        )
+        .unwrap()
+        .unwrap();
+        let target_url = Url::from_file_path(&target_path).unwrap();
+        let from_header = references(
+            &context,
+            &Documents::new(),
+            params(target_url, Position::new(3, 3)),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(from_link, from_header);
+        let ranges: Vec<Range> = from_link.iter().map(|location| location.range).collect();
+        assert!(from_link.iter().all(|location| location.uri == source_url));
+        assert_eq!(
+            ranges,
+            vec![
+                Range::new(Position::new(1, 0), Position::new(1, 10)),
+                Range::new(Position::new(1, 11), Position::new(1, 23)),
+                Range::new(
+                    Position::new(2, 0),
+                    Position::new(2, 12 + target_link.len() as u32)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_references_outside_of_tag_and_link() {
+        let context = Context::mock();
+        let (_tmp_dir, path) = index(&context, "# Title\n\nplain #text\n");
+        let url = Url::from_file_path(&path).unwrap();
+        assert!(references(
+            &context,
+            &Documents::new(),
+            params(url, Position::new(2, 2))
+        )
+        .unwrap()
+        .is_none());
+    }
+}
@@ -0,0 +1,348 @@
+use lsp_types::{Position, Range, RenameParams, TextEdit, Url, WorkspaceEdit};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::ctx::Context;
+use crate::lsp::Documents;
+use crate::models::File;
+
+pub fn rename(
+    context: &Context,
+    documents: &Documents,
+    params: RenameParams,
+) -> crate::Result<Option<WorkspaceEdit>> {
+    let url = params.text_document_position.text_document.uri;
+    let position = params.text_document_position.position;
+    let source_file = documents.parse(&url, context.config())?;
+
+    let reference = source_file
+        .references()
+        .iter()
+        .position(|reference| reference.location().contains(&position));
+    let on_header = source_file
+        .header_location()
+        .is_some_and(|location| location.contains(&position));
+
+    let resolver = context.link_resolver();
+    let target = match reference {
+        Some(index) => {
+            let reference = &source_file.references()[index];
+            let mut target = None;
+            context.db().execute(|repository| {
+                target = repository.files().find_target(reference, &resolver)?;
+                Ok(())
+            })?;
+            target
+        }
+        None if on_header => Some(source_file),
+        None => None,
+    };
+    let target = match target {
+        Some(target) => target,
+        None => return Ok(None),
+    };
+    // A synthetic header is the file stem, so links rewritten to the new name would no longer
+    // resolve unless the file was renamed too.
+    if target.header_is_synthetic() {
+        return Ok(None);
+    }
+    // A header that can't be edited in place, such as a front matter title written as a block
+    // scalar, would no longer match the rewritten links.
+    let header_range = match header_text_range(&target) {
+        Some(range) => range,
+        None => return Ok(None),
+    };
+    let mut references = Vec::new();
+    context.db().execute(|repository| {
+        // A name shared by several notes resolves to one of them, which may not be the target.
+        for reference in repository
+            .references()
+            .find_linking_to(&target, &resolver)?
+        {
+            let resolved = repository.files().find_target(&reference, &resolver)?;
+            if resolved.is_some_and(|file| file.path() == target.path()) {
+                references.push(reference);
+            }
+        }
+        Ok(())
+    })?;
+
+    let matching = context.config().link_matching();
+    let mut edits: HashMap<PathBuf, Vec<TextEdit>> = HashMap::new();
+    edits
+        .entry(target.path().clone())
+        .or_default()
+        .push(TextEdit::new(header_range, params.new_name.clone()));
+    // Only the links naming the note by its header are rewritten, and only their target, so
+    // sections, blocks and display text are kept. Links by alias, by path such as
+    // `[[work/Meeting]]` or by Markdown path still resolve after the rename.
+    for reference in references.iter().filter(|reference| {
+        reference.target_path().is_none() && matching.matches(&reference.header(), &target.header())
+    }) {
+        if let Some(location) = reference.target_location() {
+            edits
+                .entry(reference.path().clone())
+                .or_default()
+                .push(TextEdit::new(location.range(), params.new_name.clone()));
+        }
+    }
+
+    let mut changes: HashMap<Url, Vec<TextEdit>> = HashMap::new();
+    for (path, edits) in edits {
+        let uri = match Url::from_file_path(&path) {
+            Ok(uri) => uri,
+            Err(_) => return Err(crate::errors::path_cannot_convert_to_string(&path)),
+        };
+        changes.insert(uri, edits);
+    }
+
+    Ok(Some(WorkspaceEdit {
+        changes: Some(changes),
+        ..Default::default()
+    }))
+}
+
+fn header_text_range(file: &File) -> Option<Range> {
+    let end = file.header_location()?.range().end;
+    let start = end
+        .character
+        .saturating_sub(file.header().encode_utf16().count() as u32);
+    Some(Range::new(Position::new(end.line, start), end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ctx::{Config, ProjectRoot};
+    use lsp_types::{TextDocumentIdentifier, TextDocumentPositionParams, WorkDoneProgressParams};
+
+    fn params(url: Url, position: Position, new_name: &str) -> RenameParams {
+        RenameParams {
+            text_document_position: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri: url },
+                position,
+            },
+            new_name: String::from(new_name),
+            work_done_progress_params: WorkDoneProgressParams::default(),
+        }
+    }
+
+    #[test]
+    fn test_header_text_range() {
+        let file = File::mock(Some(String::from("  #  A Title  \n")));
+        assert_eq!(
+            header_text_range(&file),
+            Some(Range::new(Position::new(0, 5), Position::new(0, 12)))
+        );
+        assert!(header_text_range(&File::mock(Some(String::from("no header\n")))).is_none());
+    }
+
+    #[test]
+    fn test_header_text_range_front_matter_title() {
+        let file = File::mock(Some(String::from(
+            "---\ntitle: 'Daily Log'  # shown in sidebar\n---\n",
+        )));
+        assert_eq!(file.header(), "Daily Log");
+        assert_eq!(
+            header_text_range(&file),
+            Some(Range::new(Position::new(1, 8), Position::new(1, 17)))
+        );
+
+        let file = File::mock(Some(String::from("---\ntitle: >-\n  Daily Log\n---\n")));
+        assert_eq!(file.header(), "Daily Log");
+        assert!(header_text_range(&file).is_none());
+    }
+
+    #[test]
+    fn test_rename_block_scalar_title() {
+        let context = Context::mock();
+        let (_tmp_dir, path) = File::mock_disk_file(Some(String::from(
+            "---\ntitle: >-\n  Daily Log\n---\n\n[[Daily Log]]\n",
+        )));
+        let mut file = File::parse_from_path(path.clone()).unwrap();
+        context
+            .db()
+            .execute(|repository| {
+                file.create(&repository.files())?;
+                let found = repository
+                    .files()
+                    .find("Daily Log", &context.link_resolver())?;
+                assert!(found.is_some_and(|found| found.path() == &path));
+                Ok(())
+            })
+            .unwrap();
+
+        let url = Url::from_file_path(&path).unwrap();
+        let edit = rename(
+            &context,
+            &Documents::new(),
+            params(url, Position::new(5, 4), "New Log"),
+        )
+        .unwrap();
+        assert!(edit.is_none());
+    }
+
+    #[test]
+    fn test_rename_preserves_alias() {
+        let context = Context::mock();
+        let (_target_dir, target_path) = File::mock_disk_file(Some(String::from("# Old Name\n")));
+        let (_source_dir, source_path) = File::mock_disk_file(Some(String::from(
+            "# Source\n\n[[Old Name#Section|shown text]]\n",
+        )));
+        let mut target = File::parse_from_path(target_path.clone()).unwrap();
+        let mut source = File::parse_from_path(source_path.clone()).unwrap();
+        context
+            .db()
+            .execute(|repository| {
+                target.create(&repository.files())?;
+                source.create(&repository.files())?;
+                repository
+                    .references()
+                    .replace_references(source.path(), source.references())
+            })
+            .unwrap();
+
+        let source_url = Url::from_file_path(&source_path).unwrap();
+        let edit = rename(
+            &context,
+            &Documents::new(),
+            params(source_url.clone(), Position::new(2, 4), "New Name"),
+        )
+        .unwrap()
+        .unwrap();
+        let changes = edit.changes.unwrap();
+
+        let target_edits = &changes[&Url::from_file_path(&target_path).unwrap()];
+        assert_eq!(
+            target_edits,
+            &vec![TextEdit::new(
+                Range::new(Position::new(0, 2), Position::new(0, 10)),
+                String::from("New Name"),
+            )]
+        );
+        let source_edits = &changes[&source_url];
+        assert_eq!(
+            source_edits,
+            &vec![TextEdit::new(
+                Range::new(Position::new(2, 2), Position::new(2, 10)),
+                String::from("New Name"),
+            )]
+        );
+    }
+
+    #[test]
+    fn test_rename_resolves_the_target_note() {
+        let tmp_dir = tempfile::Builder::new()
+            .prefix("grimoire")
+            .tempdir()
+            .unwrap();
+        let root = tmp_dir.path();
+        let context =
+            Context::mock_with_project_root(ProjectRoot::new(root.to_path_buf()).unwrap());
+        let config = Config::default();
+        std::fs::create_dir(root.join("work")).unwrap();
+        std::fs::create_dir(root.join("home")).unwrap();
+        let notes = [
+            ("work/meeting.md", "# Meeting\n"),
+            ("home/meeting.md", "# Meeting\n"),
+            (
+                "source.md",
+                "# Source\n[[Meeting]] [[home/Meeting]] [[work/Meeting]]\n",
+            ),
+        ];
+        for (path, text) in notes {
+            std::fs::write(root.join(path), text).unwrap();
+            let file = File::parse_from_str(root.join(path), text, &config).unwrap();
+            context
+                .db()
+                .execute(|repository| {
+                    repository.files().create_file(&file)?;
+                    repository
+                        .references()
+                        .replace_references(file.path(), file.references())
+                })
+                .unwrap();
+        }
+
+        // `[[Meeting]]` resolves to the first note, so renaming the second one leaves it alone.
+        let source_url = Url::from_file_path(root.join("source.md")).unwrap();
+        let edit = rename(
+            &context,
+            &Documents::new(),
+            params(source_url.clone(), Position::new(1, 16), "Family"),
+        )
+        .unwrap()
+        .unwrap();
+        let changes = edit.changes.unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(
+            changes[&Url::from_file_path(root.join("home/meeting.md")).unwrap()],
+            vec![TextEdit::new(
+                Range::new(Position::new(0, 2), Position::new(0, 9)),
+                String::from("Family"),
+            )]
+        );
+
+        let edit = rename(
+            &context,
+            &Documents::new(),
+            params(source_url.clone(), Position::new(1, 4), "Standup"),
+        )
+        .unwrap()
+        .unwrap();
+        let changes = edit.changes.unwrap();
+        assert_eq!(changes.len(), 2);
+        assert!(changes.contains_key(&Url::from_file_path(root.join("work/meeting.md")).unwrap()));
+        assert_eq!(
+            changes[&source_url],
+            vec![TextEdit::new(
+                Range::new(Position::new(1, 2), Position::new(1, 9)),
+                String::from("Standup"),
+            )]
+        );
+    }
+
+    #[test]
+    fn test_rename_synthetic_header() {
+        let context = Context::mock();
+        let (_target_dir, target_path) = File::mock_disk_file(Some(String::from("no header\n")));
+        let mut target = File::parse_from_path(target_path.clone()).unwrap();
+        assert!(target.header_is_synthetic());
+        let (_source_dir, source_path) =
+            File::mock_disk_file(Some(format!("# Source\n\n[[{}]]\n", target.header())));
+        let mut source = File::parse_from_path(source_path.clone()).unwrap();
+        context
+            .db()
+            .execute(|repository| {
+                target.create(&repository.files())?;
+                source.create(&repository.files())?;
+                repository
+                    .references()
+                    .replace_references(source.path(), source.references())
+            })
+            .unwrap();
+
+        let source_url = Url::from_file_path(&source_path).unwrap();
+        let edit = rename(
+            &context,
+            &Documents::new(),
+            params(source_url, Position::new(2, 4), "New Name"),
+        )
+        .unwrap();
+        assert!(edit.is_none());
+    }
+
+    #[test]
+    fn test_rename_outside_of_reference_or_header() {
+        let context = Context::mock();
+        let (_tmp_dir, path) = File::mock_disk_file(Some(String::from("# Title\n\nplain text\n")));
+        let url = Url::from_file_path(&path).unwrap();
+        let edit = rename(
+            &context,
+            &Documents::new(),
+            params(url, Position::new(2, 2), "New"),
+        )
+        .unwrap();
+        assert!(edit.is_none());
+    }
+}
@@ -0,0 +1,111 @@
+use lsp_types::{Position, Range, Url};
+use serde::{Deserialize, Serialize};
+
+use crate::ctx::Context;
+use crate::models::{SearchResult, Snippet};
+
+const DEFAULT_LIMIT: usize = 20;
+
+/// The `grimoire/search` request, searching the text of every note as `grimoire search` does.
+pub enum SearchRequest {}
+
+impl lsp_types::request::Request for SearchRequest {
+    type Params = SearchParams;
+    type Result = Vec<SearchMatch>;
+    const METHOD: &'static str = "grimoire/search";
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchParams {
+    pub query: String,
+    /// The number of notes to return at most, 20 by default.
+    pub limit: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchMatch {
+    pub uri: Url,
+    pub header: String,
+    pub score: f64,
+    pub snippets: Vec<SearchSnippet>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchSnippet {
+    /// The whole lines the match spans.
+    pub range: Range,
+    pub text: String,
+}
+
+/// Searches the notes for the query, with the best matches first.
+pub fn search(context: &Context, params: SearchParams) -> crate::Result<Vec<SearchMatch>> {
+    let limit = params.limit.unwrap_or(DEFAULT_LIMIT);
+    crate::services::search(context, &params.query, limit)?
+        .iter()
+        .map(search_match)
+        .collect()
+}
+
+fn search_match(result: &SearchResult) -> crate::Result<SearchMatch> {
+    let uri = match Url::from_file_path(result.path()) {
+        Ok(uri) => uri,
+        Err(_) => return Err(crate::errors::path_cannot_convert_to_string(result.path())),
+    };
+    Ok(SearchMatch {
+        uri,
+        header: String::from(result.header()),
+        score: result.score(),
+        snippets: result.snippets().iter().map(search_snippet).collect(),
+    })
+}
+
+fn search_snippet(snippet: &Snippet) -> SearchSnippet {
+    let last_line = snippet.text().lines().last().unwrap_or_default();
+    let end = Position::new(snippet.end_line(), last_line.encode_utf16().count() as u32);
+    SearchSnippet {
+        range: Range::new(Position::new(snippet.start_line(), 0), end),
+        text: String::from(snippet.text()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ctx::ProjectRoot;
+
+    #[test]
+    fn test_search() -> crate::Result<()> {
+        let tmp_dir = tempfile::Builder::new().prefix("grimoire").tempdir()?;
+        let path = tmp_dir.path().join("note.md");
+        std::fs::write(&path, "# Note\nun café\ncafé noir\n")?;
+        let context =
+            Context::mock_with_project_root(ProjectRoot::new(tmp_dir.path().to_path_buf())?);
+        crate::services::reindex(&context)?;
+
+        let params = SearchParams {
+            query: String::from("\"café noir\""),
+            limit: None,
+        };
+        let matches = search(&context, params)?;
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].uri, Url::from_file_path(&path).unwrap());
+        assert_eq!(matches[0].header, "Note");
+        assert_eq!(
+            matches[0].snippets,
+            vec![SearchSnippet {
+                range: Range::new(Position::new(2, 0), Position::new(2, 9)),
+                text: String::from("café noir"),
+            }]
+        );
+
+        let params = SearchParams {
+            query: String::from("café"),
+            limit: Some(0),
+        };
+        assert!(search(&context, params)?.is_empty());
+        Ok(())
+    }
+}
@@ -1,8 +1,32 @@
-use lsp_types::ServerCapabilities;
+use lsp_types::{
+    CodeActionProviderCapability, CompletionOptions, DiagnosticOptions,
+    DiagnosticServerCapabilities, HoverProviderCapability, ServerCapabilities,
+    TextDocumentSyncCapability, TextDocumentSyncKind,
+};
 
 pub fn server_capabilities() -> ServerCapabilities {
     ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        completion_provider: Some(CompletionOptions {
+            trigger_characters: Some(vec![
+                String::from("["),
+                String::from("^"),
+                String::from("#"),
+            ]),
+            ..Default::default()
+        }),
         definition_provider: Some(lsp_types::OneOf::Left(true)),
+        rename_provider: Some(lsp_types::OneOf::Left(true)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        references_provider: Some(lsp_types::OneOf::Left(true)),
+        workspace_symbol_provider: Some(lsp_types::OneOf::Left(true)),
+        code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+        diagnostic_provider: Some(DiagnosticServerCapabilities::Options(DiagnosticOptions {
+            identifier: Some(String::from(crate::lsp::DIAGNOSTIC_SOURCE)),
+            inter_file_dependencies: true,
+            workspace_diagnostics: true,
+            ..Default::default()
+        })),
         ..Default::default()
     }
 }
@@ -15,4 +39,61 @@ mod tests {
     fn server_capabilities_allow_goto_definition() {
         assert!(server_capabilities().definition_provider.is_some());
     }
+
+    #[test]
+    fn server_capabilities_allow_hover() {
+        assert!(server_capabilities().hover_provider.is_some());
+    }
+
+    #[test]
+    fn server_capabilities_allow_references() {
+        assert!(server_capabilities().references_provider.is_some());
+    }
+
+    #[test]
+    fn server_capabilities_allow_workspace_symbols() {
+        assert!(server_capabilities().workspace_symbol_provider.is_some());
+    }
+
+    #[test]
+    fn server_capabilities_allow_code_actions() {
+        assert!(server_capabilities().code_action_provider.is_some());
+    }
+
+    #[test]
+    fn server_capabilities_allow_rename() {
+        assert!(server_capabilities().rename_provider.is_some());
+    }
+
+    #[test]
+    fn server_capabilities_sync_full_documents() {
+        assert_eq!(
+            server_capabilities().text_document_sync,
+            Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL))
+        );
+    }
+
+    #[test]
+    fn server_capabilities_complete_block_ids_and_tags() {
+        let completion_provider = server_capabilities().completion_provider.unwrap();
+        assert_eq!(
+            completion_provider.trigger_characters,
+            Some(vec![
+                String::from("["),
+                String::from("^"),
+                String::from("#")
+            ])
+        );
+    }
+
+    #[test]
+    fn server_capabilities_allow_pull_diagnostics() {
+        match server_capabilities().diagnostic_provider {
+            Some(DiagnosticServerCapabilities::Options(options)) => {
+                assert!(options.inter_file_dependencies);
+                assert!(options.workspace_diagnostics);
+            }
+            _ => panic!("expected diagnostic options"),
+        }
+    }
 }
@@ -0,0 +1,136 @@
+use lsp_types::{
+    Location, SymbolInformation, SymbolKind, Url, WorkspaceSymbolParams, WorkspaceSymbolResponse,
+};
+use std::path::Path;
+
+use crate::ctx::Context;
+use crate::lsp::tag_lsp_location;
+use crate::services::TaskFilter;
+
+/// Lists the tag occurrences and open tasks whose name contains the query, ignoring case.
+///
+/// Open tasks are named `[ ] text`, so querying `[ ]` lists all of them.
+pub fn workspace_symbols(
+    context: &Context,
+    params: WorkspaceSymbolParams,
+) -> crate::Result<Option<WorkspaceSymbolResponse>> {
+    let query = params.query.to_lowercase();
+    let mut symbols = Vec::new();
+    for tag in crate::services::all_tags(context)? {
+        if !tag.name().to_lowercase().contains(&query) {
+            continue;
+        }
+        #[allow(deprecated)]
+        symbols.push(SymbolInformation {
+            name: format!("#{}", tag.name()),
+            kind: SymbolKind::KEY,
+            tags: None,
+            deprecated: None,
+            location: tag_lsp_location(&tag)?,
+            container_name: container_name(tag.path()),
+        });
+    }
+    let open = TaskFilter {
+        done: Some(false),
+        ..TaskFilter::default()
+    };
+    for task in crate::services::find_tasks(context, &open)? {
+        let name = format!("[ ] {}", task.text());
+        if !name.to_lowercase().contains(&query) {
+            continue;
+        }
+        let uri = match Url::from_file_path(task.path()) {
+            Ok(uri) => uri,
+            Err(_) => return Err(crate::errors::path_cannot_convert_to_string(task.path())),
+        };
+        #[allow(deprecated)]
+        symbols.push(SymbolInformation {
+            name,
+            kind: SymbolKind::EVENT,
+            tags: None,
+            deprecated: None,
+            location: Location::new(uri, task.location().range()),
+            container_name: container_name(task.path()),
+        });
+    }
+    Ok(Some(WorkspaceSymbolResponse::Flat(symbols)))
+}
+
+fn container_name(path: &Path) -> Option<String> {
+    path.file_stem()
+        .and_then(|stem| stem.to_str())
+        .map(String::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Tag, Task};
+    use lsp_types::{PartialResultParams, WorkDoneProgressParams};
+    use std::path::PathBuf;
+
+    fn params(query: &str) -> WorkspaceSymbolParams {
+        WorkspaceSymbolParams {
+            query: String::from(query),
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        }
+    }
+
+    #[test]
+    fn test_workspace_symbols_for_tags() {
+        let context = Context::mock();
+        let tmp_dir = tempfile::Builder::new()
+            .prefix("grimoire")
+            .tempdir()
+            .unwrap();
+        let path: PathBuf = tmp_dir.path().join("note.md");
+        context
+            .db()
+            .execute(|repository| {
+                Tag::new(path.clone(), String::from("Project/LSP"), None)
+                    .upsert(&repository.tags())?;
+                Tag::new(path.clone(), String::from("inbox"), None).upsert(&repository.tags())
+            })
+            .unwrap();
+
+        let symbols = match workspace_symbols(&context, params("proj")).unwrap() {
+            Some(WorkspaceSymbolResponse::Flat(symbols)) => symbols,
+            other => panic!("expected flat symbols, got {other:?}"),
+        };
+        assert_eq!(symbols.len(), 1);
+        assert_eq!(symbols[0].name, "#Project/LSP");
+        assert_eq!(symbols[0].kind, SymbolKind::KEY);
+        assert_eq!(symbols[0].container_name, Some(String::from("note")));
+    }
+
+    #[test]
+    fn test_workspace_symbols_for_open_tasks() {
+        let context = Context::mock();
+        let tmp_dir = tempfile::Builder::new()
+            .prefix("grimoire")
+            .tempdir()
+            .unwrap();
+        let path: PathBuf = tmp_dir.path().join("todo.md");
+        let location = |line| crate::models::Location::mock(line, 0, 10);
+        context
+            .db()
+            .execute(|repository| {
+                Task::new(path.clone(), String::from("Open"), false, location(0))
+                    .upsert(&repository.tasks())?;
+                Task::new(path.clone(), String::from("Done"), true, location(1))
+                    .upsert(&repository.tasks())
+            })
+            .unwrap();
+
+        let symbols = match workspace_symbols(&context, params("[ ]")).unwrap() {
+            Some(WorkspaceSymbolResponse::Flat(symbols)) => symbols,
+            other => panic!("expected flat symbols, got {other:?}"),
+        };
+        assert_eq!(symbols.len(), 1);
+        assert_eq!(symbols[0].name, "[ ] Open");
+        assert_eq!(symbols[0].kind, SymbolKind::EVENT);
+        assert_eq!(symbols[0].location.range.end.character, 10);
+        assert_eq!(symbols[0].container_name, Some(String::from("todo")));
+    }
+}
@@ -0,0 +1,34 @@
+use crate::models::Location;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Block {
+    id: String,
+    location: Location,
+}
+
+impl Block {
+    pub fn new(id: String, location: Location) -> Self {
+        Self { id, location }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn location(&self) -> &Location {
+        &self.location
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn block_new() {
+        let block = Block::new(String::from("block-id"), Location::default());
+        assert_eq!(block.id(), "block-id");
+        assert_eq!(block.location(), &Location::default());
+    }
+}
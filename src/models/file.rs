@@ -1,49 +1,188 @@
-use crate::models::{Location, Reference};
-use crate::parsers::{HeaderParser, Parser, ReferenceParser};
+use crate::ctx::{Config, HeaderPolicy};
+use crate::models::{Block, Heading, Location, MalformedReference, Reference, Tag, Task};
+use crate::parsers::{
+    normalize_tag, CharSpan, FrontMatterParserPayload, IncrementalParser, LineIndex,
+    MarkdownLinkParserPayload,
+};
 use crate::repositories::FilesRepository;
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 use std::fs::File as FsFile;
 use std::io::{BufRead, BufReader};
-use std::path::PathBuf;
+use std::path::{Component, Path, PathBuf};
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct File {
     path: PathBuf,
     header: String,
-    header_location: Location,
+    // A note without a header is identified by its file stem, which has no location.
+    header_location: Option<Location>,
+    metadata: Map<String, Value>,
+    headings: Vec<Heading>,
+    blocks: Vec<Block>,
     references: Vec<Reference>,
+    malformed_references: Vec<MalformedReference>,
+    tags: Vec<Tag>,
+    tasks: Vec<Task>,
     persisted: bool,
 }
 
 impl File {
     pub fn parse_from_path(path: PathBuf) -> crate::Result<Self> {
-        let mut header_parser = HeaderParser::new();
-        let mut reference_parser = ReferenceParser::new();
-
-        let file = BufReader::new(FsFile::open(&path)?);
-        for line in file.lines() {
-            for c in line?.chars() {
-                header_parser.next(c);
-                reference_parser.next(c);
-            }
-            header_parser.next('\n');
-            reference_parser.next('\n')
+        Self::parse_from_path_with_config(path, &Config::default())
+    }
+
+    /// Parses a note, picking its header according to the project's header policy.
+    pub fn parse_from_path_with_config(path: PathBuf, config: &Config) -> crate::Result<Self> {
+        let reader = BufReader::new(FsFile::open(&path)?);
+        Self::parse_from_reader(path, reader, config)
+    }
+
+    /// Parses a note held in memory, such as an unsaved editor buffer, as if stored at `path`.
+    pub fn parse_from_str(path: PathBuf, text: &str, config: &Config) -> crate::Result<Self> {
+        Self::parse_from_reader(path, text.as_bytes(), config)
+    }
+
+    /// Parses a note in a single pass over `reader`, in the format its extension is configured
+    /// for. The logical `path` is never opened: it identifies the note and resolves its relative
+    /// links.
+    pub fn parse_from_reader(
+        path: PathBuf,
+        mut reader: impl BufRead,
+        config: &Config,
+    ) -> crate::Result<Self> {
+        let mut parser = IncrementalParser::with_format(config.format(&path).unwrap_or_default());
+        let mut buffer = String::new();
+        while reader.read_line(&mut buffer)? > 0 {
+            parser.push_line(&buffer);
+            buffer.clear();
         }
+        Self::from_parser(path, &parser, config)
+    }
 
-        let header_parser_payload = header_parser
-            .call()
-            .ok_or_else(|| crate::errors::markdown_header_not_found_during_parsing(&path))?;
-        let references = reference_parser
-            .call()
+    /// Builds a note from what `parser` found on each of its lines, as an editor keeps it updated.
+    pub fn from_parser(
+        path: PathBuf,
+        parser: &IncrementalParser,
+        config: &Config,
+    ) -> crate::Result<Self> {
+        // The parsers count chars on each line, which are resolved into positions in the note.
+        let index = parser.line_index();
+        let front_matter = parser.front_matter();
+        let metadata = front_matter
+            .as_ref()
+            .map(|front_matter| parse_metadata(&path, front_matter))
+            .unwrap_or_default();
+        let headings = parser
+            .headings()
             .into_iter()
-            .map(|payload| Reference::new(path.clone(), payload.header, payload.location))
+            .map(|payload| {
+                Heading::new(
+                    payload.level,
+                    payload.text,
+                    index.location(&payload.location),
+                )
+            })
+            .collect::<Vec<Heading>>();
+        let first_h1 = parser
+            .header()
+            .map(|payload| (payload.header, index.location(&payload.location)));
+        let first_h1 = first_h1.map(|(header, location)| (header, Some(location)));
+        let header = match config.header() {
+            HeaderPolicy::FrontMatterTitle => title(&metadata, front_matter, &index).or(first_h1),
+            HeaderPolicy::FirstH1 => first_h1,
+            HeaderPolicy::FirstHeading => headings
+                .first()
+                .map(|heading| {
+                    let location = Some(heading.location().clone());
+                    (heading.text().to_string(), location)
+                })
+                .or(first_h1),
+        };
+        let (header, header_location) = match header {
+            Some(header) => header,
+            None => (file_stem_header(&path)?, None),
+        };
+        let h1_count = headings
+            .iter()
+            .filter(|heading| heading.level() == 1)
+            .count();
+        if h1_count > 1 {
+            log::warn!("The file {path:?} has {h1_count} H1 headings, {header:?} identifies it");
+        }
+        let blocks = parser
+            .blocks()
+            .into_iter()
+            .map(|payload| Block::new(payload.id, index.location(&payload.location)))
+            .collect::<Vec<Block>>();
+        let markdown_links = parser.markdown_links();
+        let link_texts = markdown_links
+            .iter()
+            .map(|link| link.text_location.clone())
+            .collect::<Vec<CharSpan>>();
+        let references = parser
+            .references()
+            .into_iter()
+            // Links don't nest, so a wiki link in the text of a Markdown link is only text.
+            .filter(|payload| {
+                !link_texts
+                    .iter()
+                    .any(|text| text.contains(&payload.location))
+            })
+            .map(|payload| {
+                // Links such as [[#Section]] or [[^block]] target the current note.
+                let header = match payload.header.is_empty() {
+                    true => header.clone(),
+                    false => payload.header,
+                };
+                let location = |span: Option<CharSpan>| span.map(|span| index.location(&span));
+                Reference::new(path.clone(), header, index.location(&payload.location))
+                    .with_kind(payload.kind)
+                    .with_section(payload.section)
+                    .with_block(payload.block)
+                    .with_alias(payload.alias, location(payload.alias_location))
+                    .with_target_location(location(payload.header_location))
+            })
+            .chain(markdown_links.into_iter().filter_map(|payload| {
+                markdown_link_reference(&path, &header, payload, &index, config)
+            }))
             .collect::<Vec<Reference>>();
+        let malformed_references = parser
+            .malformed_references()
+            .into_iter()
+            .map(|payload| MalformedReference::new(payload.kind, index.location(&payload.location)))
+            .collect::<Vec<MalformedReference>>();
+        let tags = front_matter_tags(&metadata)
+            .into_iter()
+            .map(|name| Tag::new(path.clone(), name, None))
+            .chain(parser.tags().into_iter().map(|payload| {
+                let location = index.location(&payload.location);
+                Tag::new(path.clone(), payload.name, Some(location))
+            }))
+            .collect::<Vec<Tag>>();
+        let tasks = parser
+            .tasks()
+            .into_iter()
+            .map(|payload| {
+                let location = index.location(&payload.location);
+                Task::new(path.clone(), payload.text, payload.done, location)
+                    .with_due(payload.due)
+                    .with_people(payload.people)
+                    .with_checkbox_location(index.location(&payload.checkbox_location))
+            })
+            .collect::<Vec<Task>>();
 
         let parsed_markdown = Self {
             path,
-            header: header_parser_payload.header,
-            header_location: header_parser_payload.location,
+            header,
+            header_location,
+            metadata,
+            headings,
+            blocks,
             references,
+            malformed_references,
+            tags,
+            tasks,
             persisted: false,
         };
         Ok(parsed_markdown)
@@ -62,8 +201,47 @@ impl File {
         self.header.clone()
     }
 
-    pub fn header_location(&self) -> &Location {
-        &self.header_location
+    pub fn header_location(&self) -> Option<&Location> {
+        self.header_location.as_ref()
+    }
+
+    /// Whether the header was taken from the file name, the note having none of its own.
+    pub fn header_is_synthetic(&self) -> bool {
+        self.header_location.is_none()
+    }
+
+    pub fn metadata(&self) -> &Map<String, Value> {
+        &self.metadata
+    }
+
+    /// Other names the note can be linked with, from the `aliases` front matter entry.
+    pub fn aliases(&self) -> Vec<String> {
+        match self.metadata.get("aliases") {
+            Some(Value::String(alias)) => vec![alias.clone()],
+            Some(Value::Array(aliases)) => aliases
+                .iter()
+                .filter_map(|alias| alias.as_str().map(String::from))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn headings(&self) -> &Vec<Heading> {
+        &self.headings
+    }
+
+    pub fn heading(&self, text: &str) -> Option<&Heading> {
+        self.headings
+            .iter()
+            .find(|heading| heading.text() == text || heading.slug() == text)
+    }
+
+    pub fn blocks(&self) -> &Vec<Block> {
+        &self.blocks
+    }
+
+    pub fn block(&self, id: &str) -> Option<&Block> {
+        self.blocks.iter().find(|block| block.id() == id)
     }
 
     pub fn references(&self) -> &Vec<Reference> {
@@ -74,6 +252,27 @@ impl File {
         &mut self.references
     }
 
+    /// The wiki links that couldn't be parsed, such as a `[[` never closed.
+    pub fn malformed_references(&self) -> &Vec<MalformedReference> {
+        &self.malformed_references
+    }
+
+    pub fn tags(&self) -> &Vec<Tag> {
+        &self.tags
+    }
+
+    pub fn tags_mut(&mut self) -> &mut Vec<Tag> {
+        &mut self.tags
+    }
+
+    pub fn tasks(&self) -> &Vec<Task> {
+        &self.tasks
+    }
+
+    pub fn tasks_mut(&mut self) -> &mut Vec<Task> {
+        &mut self.tasks
+    }
+
     pub fn persisted(&self) -> bool {
         self.persisted
     }
@@ -85,32 +284,170 @@ impl File {
     }
 }
 
+fn parse_metadata(path: &Path, front_matter: &FrontMatterParserPayload) -> Map<String, Value> {
+    match serde_yaml::from_str::<Value>(&front_matter.yaml) {
+        Ok(Value::Object(metadata)) => metadata,
+        Ok(_) => Map::new(),
+        Err(err) => {
+            log::warn!("Ignoring invalid front matter in {:?}: {}", path, err);
+            Map::new()
+        }
+    }
+}
+
+/// Builds a reference from a Markdown link to a local note in any configured format, resolved
+/// against the linking note.
+fn markdown_link_reference(
+    path: &Path,
+    header: &str,
+    payload: MarkdownLinkParserPayload,
+    index: &LineIndex,
+    config: &Config,
+) -> Option<Reference> {
+    let location = index.location(&payload.location);
+    let destination = payload.destination;
+    if destination.contains("://") || destination.starts_with("mailto:") {
+        return None;
+    }
+    let (target, section) = match destination.split_once('#') {
+        Some((target, section)) => (target, Some(percent_decode(section))),
+        None => (destination.as_str(), None),
+    };
+    let target = percent_decode(target);
+    let alias = match payload.text.is_empty() {
+        true => (None, None),
+        false => (
+            Some(payload.text),
+            Some(index.location(&payload.text_location)),
+        ),
+    };
+
+    // Links such as [text](#heading) target the current note.
+    if target.is_empty() {
+        return Some(
+            Reference::new(path.to_path_buf(), String::from(header), location)
+                .with_kind(payload.kind)
+                .with_section(section)
+                .with_alias(alias.0, alias.1),
+        );
+    }
+    // Links to other files, such as images, aren't references.
+    config.format(Path::new(&target))?;
+    let target_path = path
+        .parent()
+        .map(|parent| normalize_path(&parent.join(&target)))
+        .unwrap_or_else(|| PathBuf::from(&target));
+    Some(
+        Reference::new(path.to_path_buf(), target, location)
+            .with_kind(payload.kind)
+            .with_section(section)
+            .with_alias(alias.0, alias.1)
+            .with_target_path(Some(target_path)),
+    )
+}
+
+/// Resolves `.` and `..` components without touching the file system, since targets may not exist.
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Front matter `tags` may be a list or a single string of space or comma separated tags.
+fn front_matter_tags(metadata: &Map<String, Value>) -> Vec<String> {
+    let tags: Vec<&str> = match metadata.get("tags") {
+        Some(Value::String(tags)) => tags.split([' ', ',']).collect(),
+        Some(Value::Array(tags)) => tags.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    };
+    tags.into_iter().filter_map(normalize_tag).collect()
+}
+
+fn file_stem_header(path: &Path) -> crate::Result<String> {
+    let stem = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .ok_or_else(|| crate::errors::markdown_header_not_found_during_parsing(path))?;
+    log::info!("The file {path:?} has no header, using its file name {stem:?}");
+    Ok(String::from(stem))
+}
+
+/// The front matter `title` takes precedence over the `#` header as the note's key. It has no
+/// location when it isn't written as is on the `title:` line, so that it isn't edited in place.
+fn title(
+    metadata: &Map<String, Value>,
+    front_matter: Option<FrontMatterParserPayload>,
+    index: &LineIndex,
+) -> Option<(String, Option<Location>)> {
+    let title = metadata.get("title")?.as_str()?.trim();
+    if title.is_empty() {
+        return None;
+    }
+    let location = front_matter
+        .and_then(|front_matter| front_matter.title_location(title))
+        .map(|span| index.location(&span));
+    Some((String::from(title), location))
+}
+
 #[cfg(test)]
-pub mod mocks {
+mod mocks {
     use super::*;
     use crate::ctx::Database;
     use tempfile::TempDir;
 
     impl File {
+        /// Parses the note in memory, the temporary directory only giving it a unique path.
         pub fn mock(data: Option<String>) -> Self {
-            let (_temp_dir, path) = Self::mock_disk_file(data);
-            File::parse_from_path(path).unwrap()
+            let (_temp_dir, path) = Database::mock_tmp_file("test.md");
+            let data = data.unwrap_or_else(Self::mock_data);
+            File::parse_from_str(path, &data, &Config::default()).unwrap()
         }
 
-        pub fn mock_disk_file(mut data: Option<String>) -> (TempDir, PathBuf) {
+        pub fn mock_disk_file(data: Option<String>) -> (TempDir, PathBuf) {
             let (tmp_dir, path) = Database::mock_tmp_file("test.md");
+            std::fs::write(path.clone(), data.unwrap_or_else(Self::mock_data)).unwrap();
+            (tmp_dir, path)
+        }
 
-            if data.is_none() {
-                data = Some(String::from(
-                    "\
-                    # I am a title
-
-                    [[Test Reference]]\
+        fn mock_data() -> String {
+            String::from(
+                "\
+                # I am a title\n\
+                \n\
+                [[Test Reference]]\
                 ",
-                ));
-            }
-            std::fs::write(path.clone(), data.unwrap()).unwrap();
-            (tmp_dir, path)
+            )
         }
     }
 }
@@ -119,15 +456,16 @@ pub mod mocks {
 mod tests {
     use super::*;
     use crate::ctx::Database;
+    use crate::models::MalformedReferenceKind;
 
     #[test]
     fn file_parse_from_path_success() {
         let data = String::from(
             "\
-            # I am a title
-
+            # I am a title\n\
+            \n\
             [[Test Reference]]\
-        ",
+            ",
         );
         let (_tmp_dir, path) = File::mock_disk_file(Some(data));
         let file = File::parse_from_path(path);
@@ -135,28 +473,79 @@ mod tests {
     }
 
     #[test]
-    fn file_parse_from_path_failure_header_not_found() {
+    fn file_parse_from_str() {
+        let path = PathBuf::from("/notes/dir/in-memory.md");
+        let data = "# In Memory\r\n\n[[Other]] [link](../sibling.md)";
+        let file = File::parse_from_str(path.clone(), data, &Config::default()).unwrap();
+        assert_eq!(file.path(), &path);
+        assert_eq!(file.header(), "In Memory");
+        assert_eq!(file.references().len(), 2);
+        assert_eq!(file.references()[0].location().start.offset, 14);
+        assert_eq!(
+            file.references()[1].target_path(),
+            Some(&PathBuf::from("/notes/sibling.md"))
+        );
+
+        let headerless = File::parse_from_str(path, "", &Config::default()).unwrap();
+        assert_eq!(headerless.header(), "in-memory");
+    }
+
+    #[test]
+    fn file_from_parser_matches_full_parse() {
+        let path = PathBuf::from("/notes/note.md");
+        let config = Config::default();
+        let versions = [
+            "---\ntitle: Front\n---\n# Title\n\n[[Link]] #tag\n- [ ] Task ^block\n",
+            "---\ntitle: Front\n---\n# Title\n\n```\n[[Link]] #tag\n- [ ] Task ^block\n",
+            "# Title\n\nSetext\n---\n[ref]: other.md\n[text][ref] [[Link|alias]]\n```\n",
+            "Setext\n===\n\n<!--\n[[Hidden]]\n-->\n[[Shown]] [[unclosed\r\n",
+            "",
+        ];
+        let mut parser = IncrementalParser::parse(versions[0]);
+        for version in versions {
+            parser.update(version);
+            let incremental = File::from_parser(path.clone(), &parser, &config).unwrap();
+            let full = File::parse_from_str(path.clone(), version, &config).unwrap();
+            assert_eq!(
+                serde_json::to_value(&incremental).unwrap(),
+                serde_json::to_value(&full).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn file_parse_from_reader() {
+        let reader = std::io::Cursor::new("text\n# Title\n#tag\n");
+        let path = PathBuf::from("note.md");
+        let file = File::parse_from_reader(path, reader, &Config::default()).unwrap();
+        assert_eq!(file.header(), "Title");
+        assert_eq!(file.header_location().unwrap().start.offset, 5);
+        assert_eq!(file.tags()[0].location().unwrap().line(), 2);
+    }
+
+    #[test]
+    fn file_parse_from_path_file_stem_fallback() {
         let data = String::from(
             "\
             [[Test Reference]]\
-        ",
+            ",
         );
         let (_tmp_dir, path) = File::mock_disk_file(Some(data));
-        let file = File::parse_from_path(path.clone());
-        assert_eq!(
-            file.unwrap_err().to_string(),
-            crate::errors::markdown_header_not_found_during_parsing(&path).to_string()
-        );
+        let file = File::parse_from_path(path).unwrap();
+        assert_eq!(file.header(), "test");
+        assert!(file.header_is_synthetic());
+        assert!(file.header_location().is_none());
+        assert_eq!(file.references()[0].header(), "Test Reference");
     }
 
     #[test]
     fn file_path() {
         let data = String::from(
             "\
-            # I am a title
-
+            # I am a title\n\
+            \n\
             [[Test Reference]]\
-        ",
+            ",
         );
         let (_tmp_dir, path) = File::mock_disk_file(Some(data));
         let file = File::parse_from_path(path.clone()).unwrap();
@@ -167,10 +556,10 @@ mod tests {
     fn file_serializable_path() {
         let data = String::from(
             "\
-            # I am a title
-
+            # I am a title\n\
+            \n\
             [[Test Reference]]\
-        ",
+            ",
         );
         let (_tmp_dir, path) = File::mock_disk_file(Some(data));
         let file = File::parse_from_path(path.clone()).unwrap();
@@ -190,10 +579,10 @@ mod tests {
     fn file_header() {
         let data = String::from(
             "\
-            # I am a title
-
+            # I am a title\n\
+            \n\
             [[Test Reference]]\
-        ",
+            ",
         );
         let (_tmp_dir, path) = File::mock_disk_file(Some(data));
         let file = File::parse_from_path(path).unwrap();
@@ -203,48 +592,276 @@ mod tests {
     #[test]
     fn file_header_location() {
         let data = String::from(
-            "
-            # I am a title
-
+            "\n\
+            # I am a title\n\
+            \n\
             [[Test Reference]]\
-        ",
+            ",
+        );
+        let (_tmp_dir, path) = File::mock_disk_file(Some(data));
+        let file = File::parse_from_path(path).unwrap();
+        let location = Location::mock(1, 0, 14);
+        assert_eq!(file.header_location().unwrap().range(), location.range());
+        assert_eq!(file.header_location().unwrap().start.offset, 1);
+    }
+
+    #[test]
+    fn file_front_matter() {
+        let data = String::from(
+            "\
+            ---\n\
+            # a comment, not a header\n\
+            title: 'From Front Matter'\n\
+            aliases: [First Alias, Second Alias]\n\
+            tags: [one]\n\
+            ---\n\
+            # I am a title\
+            ",
+        );
+        let (_tmp_dir, path) = File::mock_disk_file(Some(data));
+        let file = File::parse_from_path(path).unwrap();
+        assert_eq!(file.header(), "From Front Matter");
+        assert_eq!(file.header_location().unwrap().line(), 2);
+        assert_eq!(file.header_location().unwrap().start.utf32_column, 8);
+        assert_eq!(file.header_location().unwrap().end.utf32_column, 25);
+        assert_eq!(file.aliases(), vec!["First Alias", "Second Alias"]);
+        assert_eq!(file.metadata()["tags"], serde_json::json!(["one"]));
+    }
+
+    #[test]
+    fn file_front_matter_without_title() {
+        let data = String::from(
+            "\
+            ---\n\
+            # a comment, not a header\n\
+            aliases: Only Alias\n\
+            ---\n\
+            # I am a title\
+            ",
         );
         let (_tmp_dir, path) = File::mock_disk_file(Some(data));
         let file = File::parse_from_path(path).unwrap();
-        let location = Location {
-            in_range: false,
-            line_position: 1,
-            start_char_position: 12,
-            end_char_position: 25,
+        assert_eq!(file.header(), "I am a title");
+        assert_eq!(file.header_location().unwrap().line(), 4);
+        assert_eq!(file.aliases(), vec!["Only Alias"]);
+    }
+
+    #[test]
+    fn file_setext_header_and_unspaced_tag() {
+        let data = String::from("Setext Title\n============\n\ntext\n");
+        let (_tmp_dir, path) = File::mock_disk_file(Some(data));
+        let file = File::parse_from_path(path).unwrap();
+        assert_eq!(file.header(), "Setext Title");
+        assert_eq!(file.headings()[0].level(), 1);
+
+        // `#Title` is a tag rather than a heading, so the file stem is the header.
+        let data = String::from("#Title only\n\ntext\n");
+        let (_tmp_dir, path) = File::mock_disk_file(Some(data));
+        let file = File::parse_from_path(path.clone()).unwrap();
+        assert!(file.header_is_synthetic());
+        assert_eq!(file.header(), path.file_stem().unwrap().to_str().unwrap());
+        assert_eq!(file.tags()[0].name(), "Title");
+    }
+
+    #[test]
+    fn file_header_policies() {
+        let data = String::from(
+            "\
+            ---\n\
+            title: Front Matter\n\
+            ---\n\
+            ## Intro ##\n\
+            \n\
+            # First H1\n\
+            \n\
+            # Second H1\n\
+            ",
+        );
+        let (_tmp_dir, path) = File::mock_disk_file(Some(data));
+        let header = |header: &str| {
+            let config: Config = toml::from_str(&format!("header = {header:?}")).unwrap();
+            let file = File::parse_from_path_with_config(path.clone(), &config).unwrap();
+            file.header()
         };
-        assert_eq!(file.header_location().line_position, location.line_position);
+        assert_eq!(header("front-matter-title"), "Front Matter");
+        assert_eq!(header("first-h1"), "First H1");
+        assert_eq!(header("first-heading"), "Intro");
+    }
+
+    #[test]
+    fn file_tags() {
+        let data = String::from(
+            "\
+            ---\n\
+            tags: [from-front-matter, '#quoted']\n\
+            ---\n\
+            # I am a title\n\
+            \n\
+            Some #inline and #nested/tag, but not `#code`\
+            ",
+        );
+        let (_tmp_dir, path) = File::mock_disk_file(Some(data));
+        let mut file = File::parse_from_path(path).unwrap();
+        assert_eq!(file.tags_mut().len(), 4);
+        let names: Vec<&str> = file.tags().iter().map(|tag| tag.name()).collect();
         assert_eq!(
-            file.header_location().start_char_position,
-            location.start_char_position
+            names,
+            vec!["from-front-matter", "quoted", "inline", "nested/tag"]
+        );
+        assert!(file.tags()[0].location().is_none());
+        assert_eq!(file.tags()[2].location().unwrap().line(), 5);
+    }
+
+    #[test]
+    fn file_malformed_references() {
+        let data = String::from(
+            "\
+            # I am a title\n\
+            \n\
+            See [[Open and [[Closed]]\n\
+            `[[code` [[]]\
+            ",
+        );
+        let (_tmp_dir, path) = File::mock_disk_file(Some(data));
+        let file = File::parse_from_path(path).unwrap();
+        assert_eq!(file.references().len(), 1);
+        assert_eq!(file.references()[0].header(), "Closed");
+        let malformed = file.malformed_references();
+        assert_eq!(malformed.len(), 2);
+        assert_eq!(malformed[0].kind(), &MalformedReferenceKind::Unclosed);
+        assert_eq!(malformed[0].location().line(), 2);
+        assert_eq!(malformed[0].location().start.utf32_column, 4);
+        assert_eq!(malformed[0].location().end.utf32_column, 14);
+        assert_eq!(malformed[1].kind(), &MalformedReferenceKind::Empty);
+        assert_eq!(malformed[1].location().start.utf32_column, 9);
+    }
+
+    #[test]
+    fn file_tasks() {
+        let data = String::from(
+            "\
+            # I am a title\n\
+            \n\
+            - [ ] Review due:2024-05-01 @alice\n\
+            - [x] Ship\n\
+            \n\
+            ```\n\
+            - [ ] not a task\n\
+            ```\
+            ",
+        );
+        let (_tmp_dir, path) = File::mock_disk_file(Some(data));
+        let mut file = File::parse_from_path(path.clone()).unwrap();
+        assert_eq!(file.tasks_mut().len(), 2);
+        assert_eq!(file.tasks()[0].path(), &path);
+        assert_eq!(file.tasks()[0].text(), "Review due:2024-05-01 @alice");
+        assert_eq!(file.tasks()[0].due(), Some("2024-05-01"));
+        assert_eq!(file.tasks()[0].line(), 2);
+        assert!(file.tasks()[1].done());
+    }
+
+    #[test]
+    fn file_headings() {
+        let data = String::from(
+            "\
+            # I am a title\n\
+            \n\
+            ## First Section\n\
+            [[#First Section]]\n\
+            ### Second Section\
+            ",
+        );
+        let (_tmp_dir, path) = File::mock_disk_file(Some(data));
+        let file = File::parse_from_path(path).unwrap();
+        assert_eq!(file.headings().len(), 3);
+        let heading = file.heading("Second Section").unwrap();
+        assert_eq!(heading.level(), 3);
+        assert_eq!(heading.location().line(), 4);
+        assert!(file.heading("Third Section").is_none());
+
+        let reference = file.references().first().unwrap();
+        assert_eq!(reference.header(), file.header());
+        assert_eq!(reference.section(), Some("First Section"));
+    }
+
+    #[test]
+    fn file_blocks() {
+        let data = String::from(
+            "\
+            # I am a title\n\
+            \n\
+            A paragraph worth linking to ^quote\n\
+            [[^quote]]\
+            ",
+        );
+        let (_tmp_dir, path) = File::mock_disk_file(Some(data));
+        let file = File::parse_from_path(path).unwrap();
+        assert_eq!(file.blocks().len(), 1);
+        assert_eq!(file.block("quote").unwrap().location().line(), 2);
+        assert!(file.block("missing").is_none());
+
+        let reference = file.references().first().unwrap();
+        assert_eq!(reference.header(), file.header());
+        assert_eq!(reference.block(), Some("quote"));
+    }
+
+    #[test]
+    fn file_references_multibyte_location() {
+        let data = String::from("# Tïtle\n\n😀 [[Ünïcode]] after\n");
+        let (_tmp_dir, path) = File::mock_disk_file(Some(data));
+        let file = File::parse_from_path(path).unwrap();
+        let location = file.references().first().unwrap().location();
+        assert_eq!(location.start.offset, 15);
+        assert_eq!(
+            (location.start.utf8_column, location.end.utf8_column),
+            (5, 18)
+        );
+        assert_eq!(
+            location.range(),
+            lsp_types::Range::new(
+                lsp_types::Position::new(2, 3),
+                lsp_types::Position::new(2, 14)
+            )
         );
         assert_eq!(
-            file.header_location().end_char_position,
-            location.end_char_position
+            location.range_in(&lsp_types::PositionEncodingKind::UTF32),
+            lsp_types::Range::new(
+                lsp_types::Position::new(2, 2),
+                lsp_types::Position::new(2, 13)
+            )
         );
     }
 
     #[test]
-    fn file_references() {
+    fn file_references_alias() {
         let data = String::from(
-            "
-            # I am a title
+            "\
+            # I am a title\n\
+            \n\
+            [[Test Reference|a friendlier name]]\
+            ",
+        );
+        let (_tmp_dir, path) = File::mock_disk_file(Some(data));
+        let file = File::parse_from_path(path).unwrap();
+        let reference = file.references().first().unwrap();
+        assert_eq!(reference.header(), "Test Reference");
+        assert_eq!(reference.alias(), Some("a friendlier name"));
+        assert_eq!(reference.target_location().unwrap().start.utf32_column, 2);
+        assert_eq!(reference.alias_location().unwrap().start.utf32_column, 17);
+    }
 
-            [[Reference1]]
-        ",
+    #[test]
+    fn file_references() {
+        let data = String::from(
+            "\n\
+            # I am a title\n\
+            \n\
+            [[Reference1]]\n\
+            ",
         );
         let (_tmp_dir, path) = File::mock_disk_file(Some(data));
         let file = File::parse_from_path(path.clone()).unwrap();
-        let location = Location {
-            in_range: true,
-            line_position: 3,
-            start_char_position: 12,
-            end_char_position: 25,
-        };
+        let location = Location::mock(3, 0, 14);
         let reference = Reference::new(path, String::from("Reference1"), location);
         assert_eq!(file.references().len(), 1);
         assert_eq!(
@@ -252,27 +869,22 @@ mod tests {
             reference.header()
         );
         assert_eq!(
-            file.references().first().unwrap().location(),
-            reference.location()
+            file.references().first().unwrap().location().range(),
+            reference.location().range()
         );
     }
     #[test]
     fn file_references_mut() {
         let data = String::from(
-            "
-            # I am a title
-
-            [[Reference1]]
-        ",
+            "\n\
+            # I am a title\n\
+            \n\
+            [[Reference1]]\n\
+            ",
         );
         let (_tmp_dir, path) = File::mock_disk_file(Some(data));
         let mut file = File::parse_from_path(path.clone()).unwrap();
-        let location = Location {
-            in_range: true,
-            line_position: 3,
-            start_char_position: 12,
-            end_char_position: 25,
-        };
+        let location = Location::mock(3, 0, 14);
         let reference = Reference::new(path, String::from("Reference1"), location);
         assert_eq!(file.references_mut().len(), 1);
         assert_eq!(
@@ -280,8 +892,95 @@ mod tests {
             reference.header()
         );
         assert_eq!(
-            file.references().first().unwrap().location(),
-            reference.location()
+            file.references().first().unwrap().location().range(),
+            reference.location().range()
+        );
+    }
+
+    #[test]
+    fn file_references_ignore_code() {
+        let data = String::from(
+            "\
+            ```\n\
+            # Not a title\n\
+            [[Fenced]]\n\
+            ```\n\
+            # I am a title\n\
+            \n\
+            \x20   [[Indented]]\n\
+            \n\
+            `[[Inline]]` <!-- [[Comment]] --> [[Prose]]\
+            ",
+        );
+        let (_tmp_dir, path) = File::mock_disk_file(Some(data));
+        let file = File::parse_from_path(path).unwrap();
+        assert_eq!(file.header(), "I am a title");
+        assert_eq!(file.references().len(), 1);
+        let reference = file.references().first().unwrap();
+        assert_eq!(reference.header(), "Prose");
+        assert_eq!(reference.location().start.utf32_column, 34);
+    }
+
+    #[test]
+    fn file_markdown_links() {
+        let data = String::from(
+            "\
+            # I am a title\n\
+            \n\
+            [other](../dir/Other%20Note.md#some-heading) and [self](#i-am-a-title)\n\
+            [ref][] ![embed](./embedded.md) [site](https://example.com/a.md) [image](a.png)\n\
+            \n\
+            [ref]: sibling.md\
+            ",
+        );
+        let (_tmp_dir, path) = File::mock_disk_file(Some(data));
+        let file = File::parse_from_path(path.clone()).unwrap();
+        let parent = path.parent().unwrap();
+        let references = file.references();
+        assert_eq!(references.len(), 4);
+
+        assert_eq!(references[0].header(), "../dir/Other Note.md");
+        assert_eq!(
+            references[0].target_path(),
+            Some(&parent.parent().unwrap().join("dir").join("Other Note.md"))
+        );
+        assert_eq!(references[0].section(), Some("some-heading"));
+        assert_eq!(references[0].alias(), Some("other"));
+        assert_eq!(references[0].location().start.utf32_column, 0);
+        assert_eq!(references[0].location().end.utf32_column, 44);
+
+        assert_eq!(references[1].header(), file.header());
+        assert_eq!(references[1].target_path(), None);
+        assert_eq!(
+            file.heading(references[1].section().unwrap())
+                .unwrap()
+                .text(),
+            "I am a title"
+        );
+
+        assert_eq!(
+            references[2].target_path(),
+            Some(&parent.join("sibling.md"))
+        );
+        assert_eq!(references[2].location().line(), 3);
+        assert_eq!(references[3].kind(), &crate::models::ReferenceKind::Embed);
+        assert_eq!(
+            references[3].target_path(),
+            Some(&parent.join("embedded.md"))
+        );
+    }
+
+    #[test]
+    fn file_wiki_link_in_markdown_link_text() {
+        let data = String::from("# Title\n\n[see [[Other]]](other.md) and [[Other]]\n");
+        let (_tmp_dir, path) = File::mock_disk_file(Some(data));
+        let file = File::parse_from_path(path.clone()).unwrap();
+        let references = file.references();
+        assert_eq!(references.len(), 2);
+        assert_eq!(references[0].location().start.utf32_column, 30);
+        assert_eq!(
+            references[1].target_path(),
+            Some(&path.parent().unwrap().join("other.md"))
         );
     }
 
@@ -0,0 +1,70 @@
+use serde::{Deserialize, Serialize};
+use std::fs::Metadata;
+use std::time::SystemTime;
+
+/// What the index knew of a note's file when it last parsed it, to tell whether it changed.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct FileStamp {
+    modified: Option<SystemTime>,
+    size: u64,
+    hash: u64,
+}
+
+impl FileStamp {
+    pub fn new(metadata: &Metadata, text: &str) -> Self {
+        Self {
+            modified: metadata.modified().ok(),
+            size: metadata.len(),
+            hash: content_hash(text),
+        }
+    }
+
+    /// Whether the file is unchanged judging by its metadata alone, without reading it. A file
+    /// whose modification time can't be read is always read.
+    pub fn matches_metadata(&self, metadata: &Metadata) -> bool {
+        self.modified.is_some()
+            && self.modified == metadata.modified().ok()
+            && self.size == metadata.len()
+    }
+
+    /// Whether the file's content is unchanged, as when it is touched or saved without edits.
+    pub fn matches_content(&self, other: &FileStamp) -> bool {
+        self.hash == other.hash && self.size == other.size
+    }
+}
+
+/// FNV-1a, since hashes outlive the process in the index and std's hasher isn't stable across
+/// Rust releases.
+pub fn content_hash(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_hash() {
+        assert_eq!(content_hash(""), 0xcbf29ce484222325);
+        assert_eq!(content_hash("a"), 0xaf63dc4c8601ec8c);
+        assert_ne!(content_hash("# Note\n"), content_hash("# Note \n"));
+    }
+
+    #[test]
+    fn test_file_stamp_matches() -> crate::Result<()> {
+        let tmp_dir = tempfile::Builder::new().prefix("grimoire").tempdir()?;
+        let path = tmp_dir.path().join("note.md");
+        std::fs::write(&path, "# Note\n")?;
+        let metadata = std::fs::metadata(&path)?;
+        let stamp = FileStamp::new(&metadata, "# Note\n");
+        assert!(stamp.matches_metadata(&metadata));
+        assert!(stamp.matches_content(&FileStamp::new(&metadata, "# Note\n")));
+        assert!(!stamp.matches_content(&FileStamp::new(&metadata, "# Other\n")));
+
+        std::fs::write(&path, "# Longer note\n")?;
+        assert!(!stamp.matches_metadata(&std::fs::metadata(&path)?));
+        Ok(())
+    }
+}
@@ -0,0 +1,55 @@
+use crate::models::{slugify, Location};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Heading {
+    level: u8,
+    text: String,
+    location: Location,
+}
+
+impl Heading {
+    pub fn new(level: u8, text: String, location: Location) -> Self {
+        Self {
+            level,
+            text,
+            location,
+        }
+    }
+
+    pub fn level(&self) -> u8 {
+        self.level
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn location(&self) -> &Location {
+        &self.location
+    }
+
+    /// The anchor Markdown renderers generate for the heading, as in `[text](note.md#my-heading)`.
+    pub fn slug(&self) -> String {
+        slugify(&self.text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn heading_new() {
+        let heading = Heading::new(2, String::from("Section"), Location::default());
+        assert_eq!(heading.level(), 2);
+        assert_eq!(heading.text(), "Section");
+        assert_eq!(heading.location(), &Location::default());
+    }
+
+    #[test]
+    fn heading_slug() {
+        let heading = Heading::new(2, String::from("What's New? 2.0"), Location::default());
+        assert_eq!(heading.slug(), "whats-new-20");
+    }
+}
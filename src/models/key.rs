@@ -0,0 +1,52 @@
+use unicode_normalization::UnicodeNormalization;
+
+/// The key a header or link target is looked up by: Unicode NFC, lowercased, with runs of
+/// whitespace collapsed into single spaces.
+pub fn normalize_key(text: &str) -> String {
+    text.nfc()
+        .collect::<String>()
+        .to_lowercase()
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+/// The normalized key as a slug, so that `[[my-favorite-recipes]]` finds `My Favorite Recipes`.
+pub fn slug_key(text: &str) -> String {
+    slugify(&normalize_key(text))
+}
+
+/// The anchor Markdown renderers generate for a heading: lowercased, spaces turned into dashes
+/// and punctuation dropped.
+pub fn slugify(text: &str) -> String {
+    text.to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c),
+            c if c.is_whitespace() => Some('-'),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_key() {
+        assert_eq!(
+            normalize_key("  My   Favorite\tRecipes "),
+            "my favorite recipes"
+        );
+        // A precomposed é and an e followed by a combining acute accent are the same key.
+        assert_eq!(normalize_key("Caf\u{e9}"), normalize_key("Cafe\u{301}"));
+        assert_eq!(normalize_key("ÉCOLE"), "école");
+    }
+
+    #[test]
+    fn test_slug_key() {
+        assert_eq!(slug_key("My  Favorite Recipes!"), "my-favorite-recipes");
+        assert_eq!(slug_key("my-favorite-recipes"), "my-favorite-recipes");
+    }
+}
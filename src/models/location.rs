@@ -1,57 +1,90 @@
-use lsp_types::Position;
+use lsp_types::{Position as LspPosition, PositionEncodingKind, Range};
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
-pub struct Location {
-    pub in_range: bool,
-    pub line_position: u32,
-    pub start_char_position: i64,
-    pub end_char_position: i64,
+/// A position in a note, with its column counted in every encoding a client may negotiate.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Position {
+    pub line: u32,
+    /// The byte offset from the start of the note.
+    pub offset: usize,
+    /// The column in UTF-8 code units, that is bytes from the start of the line.
+    pub utf8_column: u32,
+    /// The column in UTF-16 code units, which LSP uses unless another encoding is negotiated.
+    pub utf16_column: u32,
+    /// The column in Unicode scalar values, that is `char`s from the start of the line.
+    pub utf32_column: u32,
 }
 
-const DEFAULT_LINE_POSITION: u32 = 0;
-const DEFAULT_CHAR_POSITION: i64 = -1;
-
-impl Location {
-    pub fn next(&mut self, c: char) {
-        if c == '\n' {
-            self.in_range = false;
-            self.line_position += 1;
-            self.start_char_position = DEFAULT_CHAR_POSITION;
-            self.end_char_position = DEFAULT_CHAR_POSITION;
+impl Position {
+    pub fn column(&self, encoding: &PositionEncodingKind) -> u32 {
+        if *encoding == PositionEncodingKind::UTF8 {
+            self.utf8_column
+        } else if *encoding == PositionEncodingKind::UTF32 {
+            self.utf32_column
         } else {
-            if !self.in_range {
-                self.start_char_position += 1;
-            }
-            self.end_char_position += 1;
+            self.utf16_column
         }
     }
 
-    pub fn in_range(&mut self) {
-        self.in_range = true;
+    pub fn to_lsp(&self, encoding: &PositionEncodingKind) -> LspPosition {
+        LspPosition::new(self.line, self.column(encoding))
     }
+}
+
+/// The span of a parsed element, from its first character to just past its last one.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Location {
+    pub start: Position,
+    pub end: Position,
+}
 
-    pub fn resume(&mut self) {
-        self.in_range = false;
-        self.start_char_position = self.end_char_position;
+impl Location {
+    pub fn new(start: Position, end: Position) -> Self {
+        Self { start, end }
+    }
+
+    /// The line the span starts on.
+    pub fn line(&self) -> u32 {
+        self.start.line
     }
 
-    pub fn contains(&self, position: &Position) -> bool {
-        position.line == self.line_position
-            && self.start_char_position >= 0
-            && position.character >= self.start_char_position as u32
-            && self.end_char_position >= 0
-            && position.character <= self.end_char_position as u32
+    /// Whether an LSP position in UTF-16 falls within the span, which may cover several lines.
+    pub fn contains(&self, position: &LspPosition) -> bool {
+        self.contains_in(position, &PositionEncodingKind::UTF16)
+    }
+
+    pub fn contains_in(&self, position: &LspPosition, encoding: &PositionEncodingKind) -> bool {
+        let position = (position.line, position.character);
+        let start = (self.start.line, self.start.column(encoding));
+        let end = (self.end.line, self.end.column(encoding));
+        start <= position && position < end
+    }
+
+    /// The span as an LSP range in UTF-16.
+    pub fn range(&self) -> Range {
+        self.range_in(&PositionEncodingKind::UTF16)
+    }
+
+    pub fn range_in(&self, encoding: &PositionEncodingKind) -> Range {
+        Range::new(self.start.to_lsp(encoding), self.end.to_lsp(encoding))
     }
 }
 
-impl Default for Location {
-    fn default() -> Self {
-        Self {
-            in_range: false,
-            line_position: DEFAULT_LINE_POSITION,
-            start_char_position: DEFAULT_CHAR_POSITION,
-            end_char_position: DEFAULT_CHAR_POSITION,
+#[cfg(test)]
+pub mod mocks {
+    use super::*;
+
+    impl Location {
+        /// A span of ASCII text on `line`, from `start` to just before `end`.
+        pub fn mock(line: u32, start: u32, end: u32) -> Self {
+            let position = |column: u32| Position {
+                line,
+                offset: column as usize,
+                utf8_column: column,
+                utf16_column: column,
+                utf32_column: column,
+            };
+            Self::new(position(start), position(end))
         }
     }
 }
@@ -60,101 +93,65 @@ impl Default for Location {
 mod tests {
     use super::*;
 
-    #[test]
-    fn location_default() {
-        let location = Location::default();
-        assert_eq!(location.line_position, DEFAULT_LINE_POSITION);
-        assert_eq!(location.start_char_position, DEFAULT_CHAR_POSITION);
-        assert_eq!(location.end_char_position, DEFAULT_CHAR_POSITION);
+    fn position(line: u32, utf8_column: u32, utf16_column: u32, utf32_column: u32) -> Position {
+        Position {
+            line,
+            offset: 0,
+            utf8_column,
+            utf16_column,
+            utf32_column,
+        }
     }
 
     #[test]
-    fn location_next_newline() {
-        let mut location = Location::default();
-        location.next('\n');
-        assert_eq!(location.line_position, 1);
-        assert_eq!(location.start_char_position, -1);
-        assert_eq!(location.end_char_position, -1);
+    fn location_default() {
+        let location = Location::default();
+        assert_eq!(location.start, Position::default());
+        assert_eq!(location.end, Position::default());
+        assert_eq!(location.line(), 0);
     }
 
     #[test]
-    fn location_next_not_newline() {
-        let mut location = Location::default();
-        location.next('a');
-        assert_eq!(location.line_position, 0);
-        assert_eq!(location.start_char_position, 0);
-        assert_eq!(location.end_char_position, 0);
+    fn location_contains() {
+        let location = Location::mock(0, 0, 3);
+        assert!(location.contains(&LspPosition::new(0, 0)));
+        assert!(location.contains(&LspPosition::new(0, 2)));
+        assert!(!location.contains(&LspPosition::new(0, 3)));
+        assert!(!location.contains(&LspPosition::new(1, 1)));
     }
 
     #[test]
-    fn location_in_range() {
-        let mut location = Location::default();
-        location.next('a');
-        location.next('b');
-        assert_eq!(location.start_char_position, 1);
-        assert_eq!(location.end_char_position, 1);
-
-        location.in_range();
-        assert_eq!(location.start_char_position, 1);
-        assert_eq!(location.end_char_position, 1);
-
-        location.next('c');
-        assert_eq!(location.start_char_position, 1);
-        assert_eq!(location.end_char_position, 2);
-
-        location.next('\n');
-        assert_eq!(location.start_char_position, -1);
-        assert_eq!(location.end_char_position, -1);
+    fn location_contains_multiple_lines() {
+        let location = Location::new(position(1, 4, 4, 4), position(3, 2, 2, 2));
+        assert!(!location.contains(&LspPosition::new(1, 3)));
+        assert!(location.contains(&LspPosition::new(1, 4)));
+        assert!(location.contains(&LspPosition::new(2, 100)));
+        assert!(location.contains(&LspPosition::new(3, 1)));
+        assert!(!location.contains(&LspPosition::new(3, 2)));
     }
 
     #[test]
-    fn location_resume() {
-        let mut location = Location::default();
-        location.next('a');
-        location.next('b');
-        assert_eq!(location.start_char_position, 1);
-        assert_eq!(location.end_char_position, 1);
-
-        location.in_range();
-        location.next('c');
-        assert_eq!(location.start_char_position, 1);
-        assert_eq!(location.end_char_position, 2);
-
-        location.resume();
-        assert_eq!(location.start_char_position, 2);
-        assert_eq!(location.end_char_position, 2);
-
-        location.next('d');
-        assert_eq!(location.start_char_position, 3);
-        assert_eq!(location.end_char_position, 3);
+    fn location_range() {
+        let range = Location::mock(2, 1, 4).range();
+        assert_eq!(range.start, LspPosition::new(2, 1));
+        assert_eq!(range.end, LspPosition::new(2, 4));
+        assert_eq!(Location::default().range(), Range::default());
     }
 
     #[test]
-    fn location_contains_true() {
-        let mut location = Location::default();
-        location.next('a');
-        location.in_range();
-        location.next('b');
-        location.next('c');
-        assert_eq!(location.start_char_position, 0);
-        assert_eq!(location.end_char_position, 2);
-
-        let assertion1 = location.contains(&Position {
-            line: 0,
-            character: 1,
-        });
-        assert!(assertion1);
-
-        let assertion2 = location.contains(&Position {
-            line: 1,
-            character: 1,
-        });
-        assert!(!assertion2);
-
-        let assertion3 = location.contains(&Position {
-            line: 0,
-            character: 10,
-        });
-        assert!(!assertion3);
+    fn location_range_in_encodings() {
+        // `é😀x` spans 2 + 4 + 1 bytes, 1 + 2 + 1 UTF-16 code units and 3 chars.
+        let location = Location::new(position(0, 0, 0, 0), position(0, 7, 4, 3));
+        assert_eq!(
+            location.range_in(&PositionEncodingKind::UTF8).end,
+            LspPosition::new(0, 7)
+        );
+        assert_eq!(location.range().end, LspPosition::new(0, 4));
+        assert_eq!(
+            location.range_in(&PositionEncodingKind::UTF32).end,
+            LspPosition::new(0, 3)
+        );
+        assert!(location.contains_in(&LspPosition::new(0, 2), &PositionEncodingKind::UTF32));
+        assert!(!location.contains_in(&LspPosition::new(0, 3), &PositionEncodingKind::UTF32));
     }
 }
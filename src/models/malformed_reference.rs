@@ -0,0 +1,52 @@
+use crate::models::Location;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum MalformedReferenceKind {
+    /// A `[[` that no `]]` closes on the same line.
+    Unclosed,
+    /// A `[[]]` or `[[|alias]]` link with no target.
+    Empty,
+}
+
+/// A wiki link that couldn't be parsed, kept so that it can be reported instead of ignored.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct MalformedReference {
+    kind: MalformedReferenceKind,
+    location: Location,
+}
+
+impl MalformedReference {
+    pub fn new(kind: MalformedReferenceKind, location: Location) -> Self {
+        Self { kind, location }
+    }
+
+    pub fn kind(&self) -> &MalformedReferenceKind {
+        &self.kind
+    }
+
+    pub fn location(&self) -> &Location {
+        &self.location
+    }
+
+    pub fn message(&self) -> &'static str {
+        match self.kind {
+            MalformedReferenceKind::Unclosed => "Link is never closed with `]]`",
+            MalformedReferenceKind::Empty => "Link has no target",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn malformed_reference_new() {
+        let malformed =
+            MalformedReference::new(MalformedReferenceKind::Unclosed, Location::default());
+        assert_eq!(malformed.kind(), &MalformedReferenceKind::Unclosed);
+        assert_eq!(malformed.location(), &Location::default());
+        assert_eq!(malformed.message(), "Link is never closed with `]]`");
+    }
+}
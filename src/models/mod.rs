@@ -1,8 +1,35 @@
 mod file;
 pub use self::file::*;
 
+mod file_stamp;
+pub use self::file_stamp::*;
+
 mod reference;
 pub use self::reference::*;
 
+mod malformed_reference;
+pub use self::malformed_reference::*;
+
 mod location;
 pub use self::location::*;
+
+mod key;
+pub use self::key::*;
+
+mod heading;
+pub use self::heading::*;
+
+mod block;
+pub use self::block::*;
+
+mod tag;
+pub use self::tag::*;
+
+mod task;
+pub use self::task::*;
+
+mod search_document;
+pub use self::search_document::*;
+
+mod search_result;
+pub use self::search_result::*;
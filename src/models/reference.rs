@@ -3,10 +3,25 @@ use crate::repositories::ReferencesRepository;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum ReferenceKind {
+    /// A `[[Note]]` link to another note.
+    Link,
+    /// A `![[Note]]` embed whose target's content is transcluded.
+    Embed,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Reference {
     path: PathBuf,
     header: String,
+    kind: ReferenceKind,
+    section: Option<String>,
+    block: Option<String>,
+    alias: Option<String>,
+    alias_location: Option<Location>,
+    target_location: Option<Location>,
+    target_path: Option<PathBuf>,
     header_location: Location,
     persisted: bool,
 }
@@ -16,11 +31,33 @@ impl Reference {
         Self {
             path,
             header,
+            kind: ReferenceKind::Link,
+            section: None,
+            block: None,
+            alias: None,
+            alias_location: None,
+            target_location: None,
+            target_path: None,
             header_location,
             persisted: false,
         }
     }
 
+    pub fn with_kind(mut self, kind: ReferenceKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    pub fn with_section(mut self, section: Option<String>) -> Self {
+        self.section = section;
+        self
+    }
+
+    /// The path of the note the reference appears in.
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
     pub fn serializable_path(&self) -> crate::Result<String> {
         Ok(String::from(self.path.to_str().ok_or_else(|| {
             crate::errors::path_cannot_convert_to_string(&self.path)
@@ -31,6 +68,65 @@ impl Reference {
         self.header.clone()
     }
 
+    pub fn with_block(mut self, block: Option<String>) -> Self {
+        self.block = block;
+        self
+    }
+
+    pub fn with_alias(mut self, alias: Option<String>, alias_location: Option<Location>) -> Self {
+        self.alias = alias;
+        self.alias_location = alias_location;
+        self
+    }
+
+    pub fn with_target_location(mut self, target_location: Option<Location>) -> Self {
+        self.target_location = target_location;
+        self
+    }
+
+    pub fn with_target_path(mut self, target_path: Option<PathBuf>) -> Self {
+        self.target_path = target_path;
+        self
+    }
+
+    pub fn kind(&self) -> &ReferenceKind {
+        &self.kind
+    }
+
+    pub fn section(&self) -> Option<&str> {
+        self.section.as_deref()
+    }
+
+    pub fn block(&self) -> Option<&str> {
+        self.block.as_deref()
+    }
+
+    pub fn alias(&self) -> Option<&str> {
+        self.alias.as_deref()
+    }
+
+    pub fn alias_location(&self) -> Option<&Location> {
+        self.alias_location.as_ref()
+    }
+
+    /// The location of the link's target header, absent when the link targets its own note.
+    pub fn target_location(&self) -> Option<&Location> {
+        self.target_location.as_ref()
+    }
+
+    /// The resolved path of the note targeted by a Markdown link, absent for wiki links.
+    pub fn target_path(&self) -> Option<&PathBuf> {
+        self.target_path.as_ref()
+    }
+
+    /// Describes the link's target for messages, as a path for Markdown links.
+    pub fn target_description(&self) -> String {
+        match &self.target_path {
+            Some(path) => format!("at path {path:?}"),
+            None => format!("with header {:?}", self.header),
+        }
+    }
+
     pub fn location(&self) -> &Location {
         &self.header_location
     }
@@ -43,7 +139,7 @@ impl Reference {
 }
 
 #[cfg(test)]
-pub mod mocks {
+mod mocks {
     use super::*;
     use tempfile::Builder;
 
@@ -82,6 +178,68 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn reference_kind() {
+        let reference = Reference::mock();
+        assert_eq!(reference.kind(), &ReferenceKind::Link);
+        let reference = reference.with_kind(ReferenceKind::Embed);
+        assert_eq!(reference.kind(), &ReferenceKind::Embed);
+    }
+
+    #[test]
+    fn reference_section() {
+        let reference = Reference::mock();
+        assert_eq!(reference.section(), None);
+        let reference = reference.with_section(Some(String::from("Section")));
+        assert_eq!(reference.section(), Some("Section"));
+    }
+
+    #[test]
+    fn reference_block() {
+        let reference = Reference::mock();
+        assert_eq!(reference.block(), None);
+        let reference = reference.with_block(Some(String::from("block-id")));
+        assert_eq!(reference.block(), Some("block-id"));
+    }
+
+    #[test]
+    fn reference_alias() {
+        let reference = Reference::mock();
+        assert_eq!(reference.alias(), None);
+        assert_eq!(reference.alias_location(), None);
+        let reference =
+            reference.with_alias(Some(String::from("shown")), Some(Location::default()));
+        assert_eq!(reference.alias(), Some("shown"));
+        assert_eq!(reference.alias_location(), Some(&Location::default()));
+    }
+
+    #[test]
+    fn reference_target_location() {
+        let reference = Reference::mock();
+        assert_eq!(reference.target_location(), None);
+        let reference = reference.with_target_location(Some(Location::default()));
+        assert_eq!(reference.target_location(), Some(&Location::default()));
+    }
+
+    #[test]
+    fn reference_target_path() {
+        let reference = Reference::mock();
+        assert_eq!(reference.target_path(), None);
+        assert_eq!(
+            reference.target_description(),
+            "with header \"A Nice Reference\""
+        );
+        let reference = reference.with_target_path(Some(PathBuf::from("/notes/other.md")));
+        assert_eq!(
+            reference.target_path(),
+            Some(&PathBuf::from("/notes/other.md"))
+        );
+        assert_eq!(
+            reference.target_description(),
+            "at path \"/notes/other.md\""
+        );
+    }
+
     #[test]
     fn reference_serializable_path() -> crate::Result<()> {
         let tmp_dir = Builder::new().prefix("grimoire").tempdir()?;
@@ -100,18 +258,7 @@ mod tests {
     #[test]
     fn reference_location() {
         let reference = Reference::mock();
-        assert_eq!(
-            reference.location().line_position,
-            Location::default().line_position
-        );
-        assert_eq!(
-            reference.location().start_char_position,
-            Location::default().start_char_position
-        );
-        assert_eq!(
-            reference.location().end_char_position,
-            Location::default().end_char_position
-        );
+        assert_eq!(reference.location(), &Location::default());
     }
 
     #[test]
@@ -0,0 +1,33 @@
+use serde::{Deserialize, Serialize};
+
+/// What the full-text index keeps of a note besides its postings: the distinct terms to remove
+/// on reindex, the line of each word to locate matches, and the lines to quote in snippets.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchDocument {
+    terms: Vec<String>,
+    token_lines: Vec<u32>,
+    lines: Vec<String>,
+}
+
+impl SearchDocument {
+    pub fn new(terms: Vec<String>, token_lines: Vec<u32>, lines: Vec<String>) -> Self {
+        Self {
+            terms,
+            token_lines,
+            lines,
+        }
+    }
+
+    pub fn terms(&self) -> &[String] {
+        &self.terms
+    }
+
+    /// The line of the word at `position`, counting words from the start of the note.
+    pub fn token_line(&self, position: u32) -> Option<u32> {
+        self.token_lines.get(position as usize).copied()
+    }
+
+    pub fn line(&self, line: u32) -> Option<&str> {
+        self.lines.get(line as usize).map(String::as_str)
+    }
+}
@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// A note matching a full-text search, with the lines its matches are on.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SearchResult {
+    path: PathBuf,
+    header: String,
+    score: f64,
+    snippets: Vec<Snippet>,
+}
+
+impl SearchResult {
+    pub fn new(path: PathBuf, header: String, score: f64, snippets: Vec<Snippet>) -> Self {
+        Self {
+            path,
+            header,
+            score,
+            snippets,
+        }
+    }
+
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    pub fn header(&self) -> &str {
+        &self.header
+    }
+
+    /// The BM25 relevance of the note, higher first.
+    pub fn score(&self) -> f64 {
+        self.score
+    }
+
+    pub fn snippets(&self) -> &[Snippet] {
+        &self.snippets
+    }
+}
+
+/// The lines of a note a match spans, from `start_line` to `end_line` inclusive.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Snippet {
+    start_line: u32,
+    end_line: u32,
+    text: String,
+}
+
+impl Snippet {
+    pub fn new(start_line: u32, end_line: u32, text: String) -> Self {
+        Self {
+            start_line,
+            end_line,
+            text,
+        }
+    }
+
+    pub fn start_line(&self) -> u32 {
+        self.start_line
+    }
+
+    pub fn end_line(&self) -> u32 {
+        self.end_line
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
@@ -0,0 +1,104 @@
+use crate::models::Location;
+use crate::repositories::TagsRepository;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Tag {
+    path: PathBuf,
+    name: String,
+    // Tags declared in front matter have no inline location.
+    location: Option<Location>,
+    persisted: bool,
+}
+
+impl Tag {
+    pub fn new(path: PathBuf, name: String, location: Option<Location>) -> Self {
+        Self {
+            path,
+            name,
+            location,
+            persisted: false,
+        }
+    }
+
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn location(&self) -> Option<&Location> {
+        self.location.as_ref()
+    }
+
+    /// Whether this tag is `name` itself or nested under it, such as `project/grimoire` for `project`.
+    pub fn matches(&self, name: &str) -> bool {
+        self.name == name
+            || self
+                .name
+                .strip_prefix(name)
+                .map(|rest| rest.starts_with('/'))
+                .unwrap_or(false)
+    }
+
+    pub fn persisted(&self) -> bool {
+        self.persisted
+    }
+
+    pub fn upsert(&mut self, repository: &TagsRepository) -> crate::Result<()> {
+        repository.upsert_tag(self)?;
+        self.persisted = true;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod mocks {
+    use super::*;
+
+    impl Tag {
+        pub fn mock() -> Self {
+            Tag::new(
+                PathBuf::from("note.md"),
+                String::from("project/grimoire"),
+                Some(Location::default()),
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ctx::Database;
+
+    #[test]
+    fn tag_new() {
+        let tag = Tag::new(PathBuf::from("note.md"), String::from("inbox"), None);
+        assert_eq!(tag.path(), &PathBuf::from("note.md"));
+        assert_eq!(tag.name(), "inbox");
+        assert!(tag.location().is_none());
+        assert!(!tag.persisted());
+    }
+
+    #[test]
+    fn tag_matches() {
+        let tag = Tag::mock();
+        assert!(tag.matches("project/grimoire"));
+        assert!(tag.matches("project"));
+        assert!(!tag.matches("proj"));
+        assert!(!tag.matches("project/grimoire/lsp"));
+    }
+
+    #[test]
+    fn tag_upsert() {
+        let db = Database::mock();
+        let mut tag = Tag::mock();
+        db.execute(|repository| tag.upsert(&repository.tags()))
+            .unwrap();
+        assert!(tag.persisted());
+    }
+}
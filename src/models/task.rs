@@ -0,0 +1,147 @@
+use crate::models::Location;
+use crate::repositories::TasksRepository;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Task {
+    path: PathBuf,
+    text: String,
+    done: bool,
+    due: Option<String>,
+    people: Vec<String>,
+    checkbox_location: Location,
+    location: Location,
+    persisted: bool,
+}
+
+impl Task {
+    pub fn new(path: PathBuf, text: String, done: bool, location: Location) -> Self {
+        Self {
+            path,
+            text,
+            done,
+            due: None,
+            people: Vec::new(),
+            checkbox_location: location.clone(),
+            location,
+            persisted: false,
+        }
+    }
+
+    pub fn with_due(mut self, due: Option<String>) -> Self {
+        self.due = due;
+        self
+    }
+
+    pub fn with_people(mut self, people: Vec<String>) -> Self {
+        self.people = people;
+        self
+    }
+
+    pub fn with_checkbox_location(mut self, checkbox_location: Location) -> Self {
+        self.checkbox_location = checkbox_location;
+        self
+    }
+
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn done(&self) -> bool {
+        self.done
+    }
+
+    /// The `due:YYYY-MM-DD` date of the task, which compares chronologically as a string.
+    pub fn due(&self) -> Option<&str> {
+        self.due.as_deref()
+    }
+
+    pub fn people(&self) -> &Vec<String> {
+        &self.people
+    }
+
+    /// The location of the `x` or space between the checkbox brackets.
+    pub fn checkbox_location(&self) -> &Location {
+        &self.checkbox_location
+    }
+
+    pub fn location(&self) -> &Location {
+        &self.location
+    }
+
+    pub fn line(&self) -> u32 {
+        self.location.line()
+    }
+
+    pub fn persisted(&self) -> bool {
+        self.persisted
+    }
+
+    pub fn upsert(&mut self, repository: &TasksRepository) -> crate::Result<()> {
+        repository.upsert_task(self)?;
+        self.persisted = true;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod mocks {
+    use super::*;
+
+    impl Task {
+        pub fn mock() -> Self {
+            Task::new(
+                PathBuf::from("note.md"),
+                String::from("Write docs due:2024-05-01 @alice"),
+                false,
+                Location::default(),
+            )
+            .with_due(Some(String::from("2024-05-01")))
+            .with_people(vec![String::from("alice")])
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ctx::Database;
+
+    #[test]
+    fn task_new() {
+        let task = Task::new(
+            PathBuf::from("note.md"),
+            String::from("Call"),
+            true,
+            Location::default(),
+        );
+        assert_eq!(task.path(), &PathBuf::from("note.md"));
+        assert_eq!(task.text(), "Call");
+        assert!(task.done());
+        assert!(task.due().is_none());
+        assert!(task.people().is_empty());
+        assert_eq!(task.checkbox_location(), task.location());
+        assert!(!task.persisted());
+    }
+
+    #[test]
+    fn task_annotations() {
+        let task = Task::mock();
+        assert_eq!(task.due(), Some("2024-05-01"));
+        assert_eq!(task.people(), &vec![String::from("alice")]);
+    }
+
+    #[test]
+    fn task_upsert() {
+        let db = Database::mock();
+        let mut task = Task::mock();
+        db.execute(|repository| task.upsert(&repository.tasks()))
+            .unwrap();
+        assert!(task.persisted());
+    }
+}
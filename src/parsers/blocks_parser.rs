@@ -0,0 +1,158 @@
+use crate::parsers::{CharSpan, LineParser, Parser};
+
+pub struct BlocksParser {
+    block: Option<BlocksParserPayload>,
+    blocks: Vec<BlocksParserPayload>,
+    closed: bool,
+    previous: Option<char>,
+    location: CharSpan,
+}
+
+impl BlocksParser {
+    pub fn new() -> Self {
+        Self {
+            block: None,
+            blocks: vec![],
+            closed: false,
+            previous: None,
+            location: CharSpan::default(),
+        }
+    }
+
+    pub fn call(mut self) -> Vec<BlocksParserPayload> {
+        self.finish_block();
+        self.blocks
+    }
+
+    fn finish_block(&mut self) {
+        if let Some(block) = self.block.take().filter(|block| !block.id.is_empty()) {
+            self.blocks.push(block);
+        }
+        self.closed = false;
+    }
+
+    fn discard_block(&mut self) {
+        self.block = None;
+        self.closed = false;
+        self.location.resume();
+    }
+}
+
+impl Default for BlocksParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LineParser for BlocksParser {
+    type Payload = Vec<BlocksParserPayload>;
+
+    fn into_payload(self) -> Self::Payload {
+        self.call()
+    }
+}
+
+impl Parser for BlocksParser {
+    fn next(&mut self, c: char) {
+        self.location.next(c);
+        let previous = self.previous.replace(c);
+        if c == '\n' {
+            self.finish_block();
+            self.previous = None;
+            return;
+        }
+
+        match &mut self.block {
+            None => {
+                if c == '^' && previous.map(char::is_whitespace).unwrap_or(true) {
+                    self.location.in_range();
+                    self.block = Some(BlocksParserPayload::from(self.location.clone()));
+                }
+            }
+            Some(block) => match c {
+                c if c.is_whitespace() => self.closed = true,
+                c if is_block_id_char(c) && !self.closed => block.push(c),
+                '^' if previous.map(char::is_whitespace).unwrap_or(false) => {
+                    self.discard_block();
+                    self.location.in_range();
+                    self.block = Some(BlocksParserPayload::from(self.location.clone()));
+                }
+                _ => self.discard_block(),
+            },
+        }
+    }
+}
+
+pub fn is_block_id_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '-'
+}
+
+#[derive(Debug, Clone)]
+pub struct BlocksParserPayload {
+    pub id: String,
+    pub location: CharSpan,
+}
+
+impl BlocksParserPayload {
+    fn from(location: CharSpan) -> Self {
+        Self {
+            id: String::new(),
+            location,
+        }
+    }
+
+    fn push(&mut self, c: char) {
+        self.id.push(c);
+        self.location.next(c);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Vec<BlocksParserPayload> {
+        let mut parser = BlocksParser::new();
+        text.chars().for_each(|c| parser.next(c));
+        parser.call()
+    }
+
+    #[test]
+    fn test_blocks_parser_new() {
+        assert!(BlocksParser::new().blocks.is_empty());
+    }
+
+    #[test]
+    fn test_blocks_parser_default() {
+        assert!(BlocksParser::default().block.is_none());
+    }
+
+    #[test]
+    fn test_blocks_parser_trailing_id() {
+        let blocks = parse("A paragraph ^para-1\n^standalone  \n");
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].id, "para-1");
+        assert_eq!(blocks[0].location.line_position, 0);
+        assert_eq!(blocks[0].location.start_char_position, 12);
+        assert_eq!(blocks[0].location.end_char_position, 18);
+        assert_eq!(blocks[1].id, "standalone");
+        assert_eq!(blocks[1].location.line_position, 1);
+    }
+
+    #[test]
+    fn test_blocks_parser_without_trailing_newline() {
+        let blocks = parse("text ^last");
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].id, "last");
+    }
+
+    #[test]
+    fn test_blocks_parser_ignores_non_terminal_ids() {
+        assert!(parse("x^2 is not a block\n^id followed by text\n^ empty\n").is_empty());
+    }
+
+    #[test]
+    fn test_blocks_parser_ignores_invalid_ids() {
+        assert!(parse("text ^not_valid\n[[Note^id]]\n").is_empty());
+    }
+}
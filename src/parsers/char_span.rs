@@ -0,0 +1,136 @@
+/// The char columns a parser is reading on the current line, with `-1` before the first one.
+///
+/// Once a parser is `in_range`, the start stays put while the end follows each char, so that
+/// the span covers the element being read. `LineIndex` resolves it into a `Location`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CharSpan {
+    pub in_range: bool,
+    pub line_position: u32,
+    pub start_char_position: i64,
+    pub end_char_position: i64,
+}
+
+const DEFAULT_LINE_POSITION: u32 = 0;
+const DEFAULT_CHAR_POSITION: i64 = -1;
+
+impl CharSpan {
+    pub fn next(&mut self, c: char) {
+        if c == '\n' {
+            self.in_range = false;
+            self.line_position += 1;
+            self.start_char_position = DEFAULT_CHAR_POSITION;
+            self.end_char_position = DEFAULT_CHAR_POSITION;
+        } else {
+            if !self.in_range {
+                self.start_char_position += 1;
+            }
+            self.end_char_position += 1;
+        }
+    }
+
+    pub fn in_range(&mut self) {
+        self.in_range = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.in_range = false;
+        self.start_char_position = self.end_char_position;
+    }
+
+    /// Moves the span `lines` further down, for a line parsed apart from those before it.
+    pub fn shift(&mut self, lines: u32) {
+        self.line_position += lines;
+    }
+
+    /// Whether `other` lies within this span, both on the same line.
+    pub fn contains(&self, other: &CharSpan) -> bool {
+        self.line_position == other.line_position
+            && self.start_char_position <= other.start_char_position
+            && other.end_char_position <= self.end_char_position
+    }
+}
+
+impl Default for CharSpan {
+    fn default() -> Self {
+        Self {
+            in_range: false,
+            line_position: DEFAULT_LINE_POSITION,
+            start_char_position: DEFAULT_CHAR_POSITION,
+            end_char_position: DEFAULT_CHAR_POSITION,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn char_span_default() {
+        let span = CharSpan::default();
+        assert_eq!(span.line_position, DEFAULT_LINE_POSITION);
+        assert_eq!(span.start_char_position, DEFAULT_CHAR_POSITION);
+        assert_eq!(span.end_char_position, DEFAULT_CHAR_POSITION);
+    }
+
+    #[test]
+    fn char_span_next_newline() {
+        let mut span = CharSpan::default();
+        span.next('\n');
+        assert_eq!(span.line_position, 1);
+        assert_eq!(span.start_char_position, -1);
+        assert_eq!(span.end_char_position, -1);
+    }
+
+    #[test]
+    fn char_span_next_not_newline() {
+        let mut span = CharSpan::default();
+        span.next('a');
+        assert_eq!(span.line_position, 0);
+        assert_eq!(span.start_char_position, 0);
+        assert_eq!(span.end_char_position, 0);
+    }
+
+    #[test]
+    fn char_span_in_range() {
+        let mut span = CharSpan::default();
+        span.next('a');
+        span.next('b');
+        assert_eq!(span.start_char_position, 1);
+        assert_eq!(span.end_char_position, 1);
+
+        span.in_range();
+        assert_eq!(span.start_char_position, 1);
+        assert_eq!(span.end_char_position, 1);
+
+        span.next('c');
+        assert_eq!(span.start_char_position, 1);
+        assert_eq!(span.end_char_position, 2);
+
+        span.next('\n');
+        assert_eq!(span.start_char_position, -1);
+        assert_eq!(span.end_char_position, -1);
+    }
+
+    #[test]
+    fn char_span_resume() {
+        let mut span = CharSpan::default();
+        span.next('a');
+        span.next('b');
+        assert_eq!(span.start_char_position, 1);
+        assert_eq!(span.end_char_position, 1);
+
+        span.in_range();
+        span.next('c');
+        assert_eq!(span.start_char_position, 1);
+        assert_eq!(span.end_char_position, 2);
+
+        span.resume();
+        assert_eq!(span.start_char_position, 2);
+        assert_eq!(span.end_char_position, 2);
+
+        span.next('d');
+        assert_eq!(span.start_char_position, 3);
+        assert_eq!(span.end_char_position, 3);
+    }
+}
@@ -0,0 +1,181 @@
+use crate::parsers::{CharSpan, Parser};
+
+const FRONT_MATTER_DELIMITER: &str = "---";
+const FRONT_MATTER_END_DELIMITER: &str = "...";
+const TITLE_KEY: &str = "title:";
+
+#[derive(Debug, PartialEq, Eq)]
+enum FrontMatterParserState {
+    Start,
+    Inside,
+    Closed,
+    Absent,
+}
+
+/// Collects the YAML front matter delimited by `---` lines at the very top of a note.
+pub struct FrontMatterParser {
+    state: FrontMatterParserState,
+    line: String,
+    line_position: u32,
+    payload: FrontMatterParserPayload,
+}
+
+impl FrontMatterParser {
+    pub fn new() -> Self {
+        Self {
+            state: FrontMatterParserState::Start,
+            line: String::new(),
+            line_position: 0,
+            payload: FrontMatterParserPayload::default(),
+        }
+    }
+
+    pub fn call(self) -> Option<FrontMatterParserPayload> {
+        match self.state {
+            FrontMatterParserState::Closed => Some(self.payload),
+            _ => None,
+        }
+    }
+
+    fn finish_line(&mut self) {
+        let line = std::mem::take(&mut self.line);
+        match self.state {
+            FrontMatterParserState::Start => {
+                self.state = match line.trim_end() == FRONT_MATTER_DELIMITER {
+                    true => FrontMatterParserState::Inside,
+                    false => FrontMatterParserState::Absent,
+                }
+            }
+            FrontMatterParserState::Inside => {
+                let trimmed = line.trim_end();
+                if trimmed == FRONT_MATTER_DELIMITER || trimmed == FRONT_MATTER_END_DELIMITER {
+                    self.state = FrontMatterParserState::Closed;
+                } else {
+                    if let Some(value) = line.strip_prefix(TITLE_KEY) {
+                        self.payload.title_line = Some((self.line_position, String::from(value)));
+                    }
+                    self.payload.yaml.push_str(&line);
+                    self.payload.yaml.push('\n');
+                }
+            }
+            FrontMatterParserState::Closed | FrontMatterParserState::Absent => {}
+        }
+        self.line_position += 1;
+    }
+}
+
+impl Default for FrontMatterParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Parser for FrontMatterParser {
+    fn next(&mut self, c: char) {
+        if self.done() {
+            return;
+        }
+        match c {
+            '\n' => self.finish_line(),
+            c => self.line.push(c),
+        }
+    }
+
+    fn done(&self) -> bool {
+        matches!(
+            self.state,
+            FrontMatterParserState::Closed | FrontMatterParserState::Absent
+        )
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct FrontMatterParserPayload {
+    pub yaml: String,
+    /// The position of the `title:` line and the raw text after the key.
+    title_line: Option<(u32, String)>,
+}
+
+impl FrontMatterParserPayload {
+    /// Locates `title`, the parsed `title:` value, on the line of its key. There is none when the
+    /// value isn't written there as is, as with a block scalar or escaped characters.
+    pub fn title_location(&self, title: &str) -> Option<CharSpan> {
+        let (line_position, value) = self.title_line.as_ref()?;
+        if title.is_empty() {
+            return None;
+        }
+        let start = value[..value.find(title)?].chars().count() + TITLE_KEY.chars().count();
+        Some(CharSpan {
+            in_range: false,
+            line_position: *line_position,
+            start_char_position: start as i64,
+            end_char_position: (start + title.chars().count() - 1) as i64,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Option<FrontMatterParserPayload> {
+        let mut parser = FrontMatterParser::new();
+        text.chars().for_each(|c| parser.next(c));
+        parser.call()
+    }
+
+    #[test]
+    fn test_front_matter_parser_new() {
+        assert_eq!(
+            FrontMatterParser::new().state,
+            FrontMatterParserState::Start
+        );
+    }
+
+    #[test]
+    fn test_front_matter_parser_default() {
+        assert!(FrontMatterParser::default().payload.yaml.is_empty());
+    }
+
+    #[test]
+    fn test_front_matter_parser_call() {
+        let payload = parse("---\ntitle: \"A Title\"\n# comment\n---\n# Header\n").unwrap();
+        assert_eq!(payload.yaml, "title: \"A Title\"\n# comment\n");
+        let location = payload.title_location("A Title").unwrap();
+        assert_eq!(location.line_position, 1);
+        assert_eq!(location.start_char_position, 8);
+        assert_eq!(location.end_char_position, 14);
+    }
+
+    #[test]
+    fn test_front_matter_parser_title_with_comment() {
+        let payload = parse("---\ntitle: 'Daily Log'  # shown in sidebar\n---\n").unwrap();
+        let location = payload.title_location("Daily Log").unwrap();
+        assert_eq!(location.start_char_position, 8);
+        assert_eq!(location.end_char_position, 16);
+    }
+
+    #[test]
+    fn test_front_matter_parser_title_block_scalar() {
+        let payload = parse("---\ntitle: >-\n  Daily Log\n---\n").unwrap();
+        assert!(payload.title_location("Daily Log").is_none());
+    }
+
+    #[test]
+    fn test_front_matter_parser_end_delimiter() {
+        let payload = parse("---\naliases: [a]\n...\n").unwrap();
+        assert_eq!(payload.yaml, "aliases: [a]\n");
+        assert!(payload.title_location("a").is_none());
+    }
+
+    #[test]
+    fn test_front_matter_parser_absent() {
+        assert!(parse("# Header\n---\ntitle: x\n---\n").is_none());
+        assert!(parse("\n---\ntitle: x\n---\n").is_none());
+    }
+
+    #[test]
+    fn test_front_matter_parser_unclosed() {
+        assert!(parse("---\ntitle: x\n").is_none());
+    }
+}
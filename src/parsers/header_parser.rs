@@ -1,10 +1,12 @@
-use crate::models::Location;
-use crate::parsers::Parser;
+use crate::parsers::{strip_closing_sequence, CharSpan, Parser, SetextTracker};
 
+/// Finds the first H1 of a note, written either as `# Title` or as `Title` underlined by `===`.
 pub struct HeaderParser {
     prefix: Option<Option<()>>,
     payload: Option<HeaderParserPayload>,
-    location: Location,
+    location: CharSpan,
+    line_start: bool,
+    setext: SetextTracker,
     done: bool,
 }
 
@@ -13,7 +15,9 @@ impl HeaderParser {
         Self {
             prefix: None,
             payload: None,
-            location: Location::default(),
+            location: CharSpan::default(),
+            line_start: true,
+            setext: SetextTracker::new(),
             done: false,
         }
     }
@@ -24,6 +28,18 @@ impl HeaderParser {
         }
         None
     }
+
+    fn end_line(&mut self) {
+        if let Some(heading) = self.setext.end_line() {
+            if heading.level == 1 {
+                self.payload = Some(HeaderParserPayload {
+                    header: heading.text,
+                    location: heading.location,
+                });
+                self.done = true;
+            }
+        }
+    }
 }
 
 impl Default for HeaderParser {
@@ -33,14 +49,28 @@ impl Default for HeaderParser {
 }
 
 impl Parser for HeaderParser {
+    fn done(&self) -> bool {
+        self.done
+    }
+
     fn next(&mut self, c: char) {
         if self.done {
             return;
         }
         self.location.next(c);
+        match c {
+            '\n' => self.end_line(),
+            c => self.setext.push(c),
+        }
+        if self.done {
+            return;
+        }
+        let line_start = self.line_start;
+        self.line_start = c == '\n' || (line_start && c.is_whitespace());
         match self.prefix {
             None => {
-                if c == '#' {
+                // Only a `#` opening its line can start a header, so `#tag`s elsewhere are ignored.
+                if c == '#' && line_start {
                     self.location.in_range();
                     self.prefix = Some(None);
                 }
@@ -76,11 +106,11 @@ impl Parser for HeaderParser {
 #[derive(Debug)]
 pub struct HeaderParserPayload {
     pub header: String,
-    pub location: Location,
+    pub location: CharSpan,
 }
 
 impl HeaderParserPayload {
-    fn from(c: char, location: Location) -> Self {
+    fn from(c: char, location: CharSpan) -> Self {
         Self {
             header: String::from(c),
             location,
@@ -92,9 +122,12 @@ impl HeaderParserPayload {
         self.location.next(c);
     }
 
-    fn trim(self) -> Self {
+    fn trim(mut self) -> Self {
+        let header = strip_closing_sequence(&self.header);
+        self.location.end_char_position -=
+            (self.header.chars().count() - header.chars().count()) as i64;
         Self {
-            header: self.header.trim_end().to_string(),
+            header,
             location: self.location,
         }
     }
@@ -232,6 +265,60 @@ mod tests {
         assert_eq!(parser.payload.as_ref().unwrap().header, String::from("abc"));
     }
 
+    #[test]
+    fn test_header_parser_ignores_tags() {
+        let mut parser = HeaderParser::new();
+        "#tag\ntext #tag # not a header\n  # Title\n"
+            .chars()
+            .for_each(|c| parser.next(c));
+        let payload = parser.call().unwrap();
+        assert_eq!(payload.header, String::from("Title"));
+        assert_eq!(payload.location.line_position, 2);
+    }
+
+    #[test]
+    fn test_header_parser_call_trims_location() {
+        let mut parser = HeaderParser::new();
+        "# abc  \n".chars().for_each(|c| parser.next(c));
+        let payload = parser.call().unwrap();
+        assert_eq!(payload.header, String::from("abc"));
+        assert_eq!(payload.location.start_char_position, 0);
+        assert_eq!(payload.location.end_char_position, 4);
+    }
+
+    #[test]
+    fn test_header_parser_closing_sequence() {
+        let mut parser = HeaderParser::new();
+        "# Title ##\n".chars().for_each(|c| parser.next(c));
+        let payload = parser.call().unwrap();
+        assert_eq!(payload.header, String::from("Title"));
+        assert_eq!(payload.location.end_char_position, 6);
+    }
+
+    #[test]
+    fn test_header_parser_setext() {
+        let mut parser = HeaderParser::new();
+        "Intro\n\nSection\n---\n\nTitle\n=====\n\n# Later\n"
+            .chars()
+            .for_each(|c| parser.next(c));
+        let payload = parser.call().unwrap();
+        assert_eq!(payload.header, String::from("Title"));
+        assert_eq!(payload.location.line_position, 5);
+        assert_eq!(payload.location.start_char_position, 0);
+        assert_eq!(payload.location.end_char_position, 4);
+    }
+
+    #[test]
+    fn test_header_parser_unspaced_tag_is_not_header() {
+        let mut parser = HeaderParser::new();
+        "\n#Title\ntext\n".chars().for_each(|c| parser.next(c));
+        assert!(parser.call().is_none());
+
+        let mut parser = HeaderParser::new();
+        "#tag only\n\n# Real\n".chars().for_each(|c| parser.next(c));
+        assert_eq!(parser.call().unwrap().header, String::from("Real"));
+    }
+
     #[test]
     fn test_header_parser_call_in_non_final_state() {
         let mut parser = HeaderParser::new();
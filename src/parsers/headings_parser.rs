@@ -0,0 +1,332 @@
+use crate::parsers::{CharSpan, LineParser, Parser};
+
+const MAX_HEADING_LEVEL: u8 = 6;
+
+#[derive(Debug, PartialEq, Eq)]
+enum HeadingsParserState {
+    LineStart,
+    Prefix(u8),
+    Payload(u8),
+    Skip,
+}
+
+pub struct HeadingsParser {
+    state: HeadingsParserState,
+    heading: Option<HeadingsParserPayload>,
+    headings: Vec<HeadingsParserPayload>,
+    setext: SetextTracker,
+    location: CharSpan,
+}
+
+impl HeadingsParser {
+    pub fn new() -> Self {
+        Self {
+            state: HeadingsParserState::LineStart,
+            heading: None,
+            headings: vec![],
+            setext: SetextTracker::new(),
+            location: CharSpan::default(),
+        }
+    }
+
+    pub fn call(mut self) -> Vec<HeadingsParserPayload> {
+        self.finish_heading();
+        self.headings
+    }
+
+    fn finish_heading(&mut self) {
+        if let Some(heading) = self.heading.take() {
+            self.headings.push(heading.trim());
+        }
+    }
+}
+
+impl Default for HeadingsParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Only ATX headings are found on a single line, Setext ones being underlined by the next.
+impl LineParser for HeadingsParser {
+    type Payload = Vec<HeadingsParserPayload>;
+
+    fn into_payload(self) -> Self::Payload {
+        self.call()
+    }
+}
+
+impl Parser for HeadingsParser {
+    fn next(&mut self, c: char) {
+        self.location.next(c);
+        if c == '\n' {
+            self.finish_heading();
+            if let Some(heading) = self.setext.end_line() {
+                self.headings.push(heading);
+            }
+            self.state = HeadingsParserState::LineStart;
+            return;
+        }
+        self.setext.push(c);
+
+        match self.state {
+            HeadingsParserState::LineStart => match c {
+                '#' => {
+                    self.location.in_range();
+                    self.state = HeadingsParserState::Prefix(1);
+                }
+                ' ' | '\t' => {}
+                _ => self.state = HeadingsParserState::Skip,
+            },
+            HeadingsParserState::Prefix(level) => match c {
+                '#' if level < MAX_HEADING_LEVEL => {
+                    self.state = HeadingsParserState::Prefix(level + 1)
+                }
+                ' ' | '\t' => self.state = HeadingsParserState::Payload(level),
+                _ => {
+                    self.location.resume();
+                    self.state = HeadingsParserState::Skip;
+                }
+            },
+            HeadingsParserState::Payload(level) => match &mut self.heading {
+                None => match c {
+                    ' ' | '\t' => {}
+                    c => {
+                        self.heading =
+                            Some(HeadingsParserPayload::from(level, c, self.location.clone()))
+                    }
+                },
+                Some(payload) => payload.push(c),
+            },
+            HeadingsParserState::Skip => {}
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HeadingsParserPayload {
+    pub level: u8,
+    pub text: String,
+    pub location: CharSpan,
+}
+
+impl HeadingsParserPayload {
+    fn from(level: u8, c: char, location: CharSpan) -> Self {
+        Self {
+            level,
+            text: String::from(c),
+            location,
+        }
+    }
+
+    fn push(&mut self, c: char) {
+        self.text.push(c);
+        self.location.next(c);
+    }
+
+    fn trim(mut self) -> Self {
+        let text = strip_closing_sequence(&self.text);
+        self.location.end_char_position -=
+            (self.text.chars().count() - text.chars().count()) as i64;
+        Self {
+            level: self.level,
+            text,
+            location: self.location,
+        }
+    }
+}
+
+/// Removes the optional closing sequence of an ATX heading, as in `## Section ##`.
+pub fn strip_closing_sequence(text: &str) -> String {
+    let text = text.trim_end();
+    let without_closing = text.trim_end_matches('#');
+    match without_closing.len() < text.len()
+        && (without_closing.is_empty() || without_closing.ends_with([' ', '\t']))
+    {
+        true => without_closing.trim_end().to_string(),
+        false => text.to_string(),
+    }
+}
+
+/// Tracks paragraph lines, which become Setext headings when underlined by `===` or `---`.
+pub struct SetextTracker {
+    line: String,
+    line_position: u32,
+    candidate: Option<HeadingsParserPayload>,
+}
+
+impl SetextTracker {
+    pub fn new() -> Self {
+        Self {
+            line: String::new(),
+            line_position: 0,
+            candidate: None,
+        }
+    }
+
+    pub fn push(&mut self, c: char) {
+        self.line.push(c);
+    }
+
+    /// The line being read, until `end_line` is called.
+    pub fn line(&self) -> &str {
+        &self.line
+    }
+
+    pub fn line_position(&self) -> u32 {
+        self.line_position
+    }
+
+    /// Ends the current line, returning the heading it underlines if any.
+    pub fn end_line(&mut self) -> Option<HeadingsParserPayload> {
+        let line = std::mem::take(&mut self.line);
+        let line_position = self.line_position;
+        self.line_position += 1;
+
+        if let Some(level) = setext_underline(&line) {
+            if let Some(mut heading) = self.candidate.take() {
+                heading.level = level;
+                return Some(heading);
+            }
+        }
+        self.candidate = setext_candidate(&line, line_position);
+        None
+    }
+}
+
+/// The level of the Setext heading `line` would underline, if it's a `===` or `---` line.
+pub fn setext_underline(line: &str) -> Option<u8> {
+    let indentation = line.chars().take_while(|c| *c == ' ').count();
+    let content = line.trim();
+    match content.chars().next() {
+        _ if indentation >= 4 => None,
+        Some('=') if content.chars().all(|c| c == '=') => Some(1),
+        Some('-') if content.chars().all(|c| c == '-') => Some(2),
+        _ => None,
+    }
+}
+
+/// The heading `line` would become if the next line underlined it, with its level left at 0.
+pub fn setext_candidate(line: &str, line_position: u32) -> Option<HeadingsParserPayload> {
+    let indentation = line.chars().take_while(|c| *c == ' ').count();
+    let content = line.trim();
+    if indentation >= 4 || !is_paragraph_line(content) {
+        return None;
+    }
+    let start = line.chars().take_while(|c| c.is_whitespace()).count();
+    let end = start + content.chars().count() - 1;
+    Some(HeadingsParserPayload {
+        level: 0,
+        text: String::from(content),
+        location: CharSpan {
+            in_range: false,
+            line_position,
+            start_char_position: start as i64,
+            end_char_position: end as i64,
+        },
+    })
+}
+
+impl Default for SetextTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn is_paragraph_line(content: &str) -> bool {
+    let atx = content.starts_with('#')
+        && matches!(
+            content.trim_start_matches('#').chars().next(),
+            None | Some(' ' | '\t')
+        );
+    let list_item = matches!(content.chars().next(), Some('-' | '*' | '+'))
+        && matches!(content.chars().nth(1), None | Some(' ' | '\t'));
+    !content.is_empty() && !atx && !list_item && !content.starts_with('>')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Vec<HeadingsParserPayload> {
+        let mut parser = HeadingsParser::new();
+        text.chars().for_each(|c| parser.next(c));
+        parser.call()
+    }
+
+    #[test]
+    fn test_headings_parser_new() {
+        assert!(HeadingsParser::new().headings.is_empty());
+    }
+
+    #[test]
+    fn test_headings_parser_default() {
+        assert_eq!(
+            HeadingsParser::default().state,
+            HeadingsParserState::LineStart
+        );
+    }
+
+    #[test]
+    fn test_headings_parser_all_levels() {
+        let headings = parse("# One\n## Two\n###### Six\n");
+        assert_eq!(headings.len(), 3);
+        assert_eq!(headings[0].level, 1);
+        assert_eq!(headings[0].text, "One");
+        assert_eq!(headings[1].level, 2);
+        assert_eq!(headings[1].text, "Two");
+        assert_eq!(headings[2].level, 6);
+        assert_eq!(headings[2].text, "Six");
+    }
+
+    #[test]
+    fn test_headings_parser_location() {
+        let headings = parse("intro\n  ## Section  \n");
+        assert_eq!(headings.len(), 1);
+        assert_eq!(headings[0].location.line_position, 1);
+        assert_eq!(headings[0].location.start_char_position, 2);
+        assert_eq!(headings[0].location.end_char_position, 11);
+    }
+
+    #[test]
+    fn test_headings_parser_without_trailing_newline() {
+        let headings = parse("# Last");
+        assert_eq!(headings.len(), 1);
+        assert_eq!(headings[0].text, "Last");
+    }
+
+    #[test]
+    fn test_headings_parser_closing_sequence() {
+        let headings = parse("## Section ##  \n# C# #\n# Issue#\n");
+        assert_eq!(headings[0].text, "Section");
+        assert_eq!(headings[0].location.end_char_position, 9);
+        assert_eq!(headings[1].text, "C#");
+        assert_eq!(headings[2].text, "Issue#");
+    }
+
+    #[test]
+    fn test_headings_parser_setext() {
+        let headings = parse("  Title  \n=====\n\nSection\n---\n- item\n---\n\n---\n");
+        assert_eq!(headings.len(), 2);
+        assert_eq!(headings[0].level, 1);
+        assert_eq!(headings[0].text, "Title");
+        assert_eq!(headings[0].location.line_position, 0);
+        assert_eq!(headings[0].location.start_char_position, 2);
+        assert_eq!(headings[0].location.end_char_position, 6);
+        assert_eq!(headings[1].level, 2);
+        assert_eq!(headings[1].text, "Section");
+        assert_eq!(headings[1].location.line_position, 3);
+    }
+
+    #[test]
+    fn test_strip_closing_sequence() {
+        assert_eq!(strip_closing_sequence("Title ###"), "Title");
+        assert_eq!(strip_closing_sequence("###"), "");
+        assert_eq!(strip_closing_sequence("Title#"), "Title#");
+    }
+
+    #[test]
+    fn test_headings_parser_ignores_non_headings() {
+        assert!(parse("#tag\n####### Seven\ntext # not a heading\n#\n").is_empty());
+    }
+}
@@ -0,0 +1,566 @@
+use crate::parsers::{
+    resolve_markdown_links, setext_candidate, setext_underline, BlocksParser, BlocksParserPayload,
+    CharSpan, FrontMatterParser, FrontMatterParserPayload, HeaderParser, HeaderParserPayload,
+    HeadingsParser, HeadingsParserPayload, LineIndex, LineParser, MalformedReferenceParserPayload,
+    MarkdownLinkParser, MarkdownLinkParserLine, MarkdownLinkParserPayload, NoteFormat, OrgFilter,
+    OrgParser, Parser, ProseFilter, ReferenceParser, ReferenceParserPayload, TagParser,
+    TagParserPayload, TaskParser, TaskParserPayload,
+};
+use std::collections::HashMap;
+
+/// Parses a note line by line, caching what each line holds so that an edit only parses again
+/// the lines it changed, along with the following ones whose state it changed.
+///
+/// The payloads of each line are located on line 0. The lines after an edit keep them as they
+/// are, and they are only shifted onto their line once the note's payloads are collected.
+#[derive(Debug, Default)]
+pub struct IncrementalParser {
+    format: NoteFormat,
+    lines: Vec<ParsedLine>,
+    // The state after the last line, which a pushed line is parsed in.
+    state: LineState,
+}
+
+impl IncrementalParser {
+    pub fn new() -> Self {
+        Self::with_format(NoteFormat::Markdown)
+    }
+
+    pub fn with_format(format: NoteFormat) -> Self {
+        Self {
+            format,
+            lines: vec![],
+            state: LineState::new(format),
+        }
+    }
+
+    pub fn parse(text: &str) -> Self {
+        Self::parse_with_format(text, NoteFormat::Markdown)
+    }
+
+    pub fn parse_with_format(text: &str, format: NoteFormat) -> Self {
+        let mut parser = Self::with_format(format);
+        text.split_inclusive('\n')
+            .for_each(|line| parser.push_line(line));
+        parser
+    }
+
+    pub fn format(&self) -> NoteFormat {
+        self.format
+    }
+
+    /// Parses the next line of the note, with its line break if it has one.
+    pub fn push_line(&mut self, line: &str) {
+        let (line, state) = ParsedLine::parse(line, &self.state);
+        self.lines.push(line);
+        self.state = state;
+    }
+
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// Parses `text` as the new version of the note, skipping the lines it starts and ends with
+    /// that didn't change. Returns how many lines were parsed.
+    pub fn update(&mut self, text: &str) -> usize {
+        let lines: Vec<&str> = text.split_inclusive('\n').collect();
+        let prefix = self
+            .lines
+            .iter()
+            .zip(&lines)
+            .take_while(|(old, new)| old.line == **new)
+            .count();
+        let suffix = self
+            .lines
+            .iter()
+            .rev()
+            .zip(lines.iter().rev())
+            .take((self.lines.len() - prefix).min(lines.len() - prefix))
+            .take_while(|(old, new)| old.line == **new)
+            .count();
+        let replacement = lines[prefix..lines.len() - suffix].concat();
+        self.edit(prefix, self.lines.len() - suffix, &replacement)
+    }
+
+    /// Replaces the lines from `start` up to `end` excluded with the lines of `text`. Returns how
+    /// many lines were parsed.
+    pub fn edit(&mut self, start: usize, end: usize, text: &str) -> usize {
+        let start = start.min(self.lines.len());
+        let end = end.clamp(start, self.lines.len());
+        let mut state = match self.lines.get(start) {
+            Some(line) => line.state.clone(),
+            None => self.state.clone(),
+        };
+        let mut lines = Vec::new();
+        for line in text.split_inclusive('\n') {
+            let (line, next) = ParsedLine::parse(line, &state);
+            lines.push(line);
+            state = next;
+        }
+        let mut parsed = lines.len();
+        let mut position = start + parsed;
+        self.lines.splice(start..end, lines);
+
+        // A following line parsed in the same state as before holds the same payloads, and so
+        // does every line after it.
+        while position < self.lines.len() && self.lines[position].state != state {
+            let (line, next) = ParsedLine::parse(&self.lines[position].line, &state);
+            self.lines[position] = line;
+            state = next;
+            position += 1;
+            parsed += 1;
+        }
+        if position == self.lines.len() {
+            self.state = state;
+        }
+        parsed
+    }
+
+    pub fn line_index(&self) -> LineIndex<'_> {
+        LineIndex::from_lines(self.lines.iter().map(|line| {
+            let text = line.text();
+            (text, line.line.len() - text.len())
+        }))
+    }
+
+    /// Front matter only opens a note, so the lines after it aren't read. Org-mode notes have
+    /// none.
+    pub fn front_matter(&self) -> Option<FrontMatterParserPayload> {
+        if self.format == NoteFormat::Org {
+            return None;
+        }
+        let mut parser = FrontMatterParser::new();
+        for line in self.lines.iter() {
+            if parser.done() {
+                break;
+            }
+            parser.next_line(line.text());
+        }
+        parser.call()
+    }
+
+    /// The header is usually found on the first lines, so the lines after it aren't read. An
+    /// Org-mode note's header is its first `* Headline`.
+    pub fn header(&self) -> Option<HeaderParserPayload> {
+        if self.format == NoteFormat::Org {
+            return self
+                .lines
+                .iter()
+                .enumerate()
+                .find_map(|(position, line)| {
+                    let heading = line.headings.iter().find(|heading| heading.level == 1)?;
+                    Some((position, heading.clone()))
+                })
+                .map(|(position, heading)| {
+                    let heading = heading.shifted(position);
+                    HeaderParserPayload {
+                        header: heading.text,
+                        location: heading.location,
+                    }
+                });
+        }
+        let mut parser = HeaderParser::new();
+        for line in self.lines.iter() {
+            if parser.done() {
+                break;
+            }
+            parser.next_line(&line.prose);
+        }
+        parser.call()
+    }
+
+    pub fn headings(&self) -> Vec<HeadingsParserPayload> {
+        let mut headings = Vec::new();
+        for (position, line) in self.lines.iter().enumerate() {
+            if let (Some(level), true) = (line.underline, line.state.setext_candidate) {
+                let previous = &self.lines[position - 1].prose;
+                if let Some(mut heading) = setext_candidate(previous, position as u32 - 1) {
+                    heading.level = level;
+                    headings.push(heading);
+                }
+            }
+            headings.extend(self.shifted(position, &line.headings));
+        }
+        headings
+    }
+
+    pub fn blocks(&self) -> Vec<BlocksParserPayload> {
+        self.collect(|line| &line.blocks)
+    }
+
+    pub fn references(&self) -> Vec<ReferenceParserPayload> {
+        self.collect(|line| &line.references)
+    }
+
+    pub fn malformed_references(&self) -> Vec<MalformedReferenceParserPayload> {
+        self.collect(|line| &line.malformed_references)
+    }
+
+    pub fn tags(&self) -> Vec<TagParserPayload> {
+        self.collect(|line| &line.tags)
+    }
+
+    pub fn tasks(&self) -> Vec<TaskParserPayload> {
+        self.collect(|line| &line.tasks)
+    }
+
+    /// Reference-style links are resolved against the definitions of every line, the first
+    /// definition of a label winning.
+    pub fn markdown_links(&self) -> Vec<MarkdownLinkParserPayload> {
+        let mut links = Vec::new();
+        let mut pending = Vec::new();
+        let mut definitions: HashMap<String, (String, CharSpan)> = HashMap::new();
+        for (position, line) in self.lines.iter().enumerate() {
+            let markdown_links = &line.markdown_links;
+            links.extend(self.shifted(position, &markdown_links.links));
+            pending.extend(
+                markdown_links
+                    .pending
+                    .iter()
+                    .map(|(label, link)| (label.clone(), link.clone().shifted(position))),
+            );
+            for (label, (destination, location)) in &markdown_links.definitions {
+                let mut location = location.clone();
+                location.shift(position as u32);
+                definitions
+                    .entry(label.clone())
+                    .or_insert((destination.clone(), location));
+            }
+        }
+        resolve_markdown_links(links, pending, &definitions)
+    }
+
+    fn collect<P: Shift + Clone>(&self, payloads: impl Fn(&ParsedLine) -> &Vec<P>) -> Vec<P> {
+        self.lines
+            .iter()
+            .enumerate()
+            .flat_map(|(position, line)| self.shifted(position, payloads(line)))
+            .collect()
+    }
+
+    fn shifted<'a, P: Shift + Clone>(
+        &self,
+        position: usize,
+        payloads: &'a [P],
+    ) -> impl Iterator<Item = P> + 'a {
+        payloads
+            .iter()
+            .map(move |payload| payload.clone().shifted(position))
+    }
+}
+
+/// The state a line is parsed in, carried over from the lines before it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct LineState {
+    filter: LineFilter,
+    // Whether the previous line becomes a Setext heading if this one is a `===` or `---` line.
+    setext_candidate: bool,
+}
+
+impl LineState {
+    fn new(format: NoteFormat) -> Self {
+        let filter = match format {
+            NoteFormat::Markdown => LineFilter::Markdown(ProseFilter::new()),
+            NoteFormat::Org => LineFilter::Org(OrgFilter::new()),
+        };
+        Self {
+            filter,
+            setext_candidate: false,
+        }
+    }
+}
+
+/// Masks what isn't prose in the format of the note.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum LineFilter {
+    Markdown(ProseFilter),
+    Org(OrgFilter),
+}
+
+impl Default for LineFilter {
+    fn default() -> Self {
+        Self::Markdown(ProseFilter::new())
+    }
+}
+
+#[derive(Debug)]
+struct ParsedLine {
+    line: String,
+    state: LineState,
+    prose: String,
+    underline: Option<u8>,
+    headings: Vec<HeadingsParserPayload>,
+    blocks: Vec<BlocksParserPayload>,
+    references: Vec<ReferenceParserPayload>,
+    malformed_references: Vec<MalformedReferenceParserPayload>,
+    tags: Vec<TagParserPayload>,
+    tasks: Vec<TaskParserPayload>,
+    markdown_links: MarkdownLinkParserLine,
+}
+
+impl ParsedLine {
+    /// Parses `line` in `state`, returning the state the next line is parsed in.
+    fn parse(line: &str, state: &LineState) -> (Self, LineState) {
+        let mut next = state.clone();
+        let text = line.strip_suffix('\n').unwrap_or(line);
+        let text = text.strip_suffix('\r').unwrap_or(text);
+        // Code and HTML are masked so that only prose yields headers and references.
+        let prose = match &mut next.filter {
+            LineFilter::Markdown(filter) => filter.filter(text),
+            LineFilter::Org(filter) => {
+                let prose = filter.filter(text);
+                return (Self::parse_org(line, state, prose), next);
+            }
+        };
+        let underline = setext_underline(&prose);
+        next.setext_candidate = match (state.setext_candidate, underline) {
+            (true, Some(_)) => false,
+            _ => setext_candidate(&prose, 0).is_some(),
+        };
+        let (references, malformed_references) = ReferenceParser::parse_line(&prose);
+
+        let parsed = Self {
+            line: String::from(line),
+            state: state.clone(),
+            underline,
+            headings: HeadingsParser::parse_line(&prose),
+            blocks: BlocksParser::parse_line(&prose),
+            references,
+            malformed_references,
+            tags: TagParser::parse_line(&prose),
+            tasks: TaskParser::parse_line(&prose),
+            markdown_links: MarkdownLinkParser::parse_line(&prose),
+            prose,
+        };
+        (parsed, next)
+    }
+
+    /// Org-mode checkboxes are written as Markdown ones, and its file links are resolved as
+    /// Markdown links are.
+    fn parse_org(line: &str, state: &LineState, prose: String) -> Self {
+        let payload = OrgParser::parse_line(&prose);
+        Self {
+            line: String::from(line),
+            state: state.clone(),
+            underline: None,
+            headings: payload.headings,
+            blocks: vec![],
+            references: payload.references,
+            malformed_references: vec![],
+            tags: payload.tags,
+            tasks: TaskParser::parse_line(&prose),
+            markdown_links: MarkdownLinkParserLine {
+                links: payload.file_links,
+                ..MarkdownLinkParserLine::default()
+            },
+            prose,
+        }
+    }
+
+    fn text(&self) -> &str {
+        let text = self.line.strip_suffix('\n').unwrap_or(&self.line);
+        text.strip_suffix('\r').unwrap_or(text)
+    }
+}
+
+/// Moves a payload parsed on line 0 onto the line it was found on.
+trait Shift {
+    fn shifted(self, position: usize) -> Self;
+}
+
+impl Shift for HeadingsParserPayload {
+    fn shifted(mut self, position: usize) -> Self {
+        self.location.shift(position as u32);
+        self
+    }
+}
+
+impl Shift for BlocksParserPayload {
+    fn shifted(mut self, position: usize) -> Self {
+        self.location.shift(position as u32);
+        self
+    }
+}
+
+impl Shift for ReferenceParserPayload {
+    fn shifted(mut self, position: usize) -> Self {
+        self.location.shift(position as u32);
+        for location in [&mut self.header_location, &mut self.alias_location]
+            .into_iter()
+            .flatten()
+        {
+            location.shift(position as u32);
+        }
+        self
+    }
+}
+
+impl Shift for MalformedReferenceParserPayload {
+    fn shifted(mut self, position: usize) -> Self {
+        self.location.shift(position as u32);
+        self
+    }
+}
+
+impl Shift for TagParserPayload {
+    fn shifted(mut self, position: usize) -> Self {
+        self.location.shift(position as u32);
+        self
+    }
+}
+
+impl Shift for TaskParserPayload {
+    fn shifted(mut self, position: usize) -> Self {
+        self.location.shift(position as u32);
+        self.checkbox_location.shift(position as u32);
+        self
+    }
+}
+
+impl Shift for MarkdownLinkParserPayload {
+    fn shifted(mut self, position: usize) -> Self {
+        self.location.shift(position as u32);
+        self.text_location.shift(position as u32);
+        self.destination_location.shift(position as u32);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(lines: usize) -> String {
+        (0..lines)
+            .map(|line| format!("Line {line} links to [[Note {line}]] #tag\n"))
+            .collect()
+    }
+
+    #[test]
+    fn test_incremental_parser_parse() {
+        let parser = IncrementalParser::parse("# Title\n\n[[Link]]\n- [ ] Task ^block");
+        assert_eq!(parser.len(), 4);
+        assert_eq!(parser.header().unwrap().header, "Title");
+        assert_eq!(parser.references()[0].location.line_position, 2);
+        assert_eq!(parser.tasks()[0].location.line_position, 3);
+        assert_eq!(parser.blocks()[0].id, "block");
+        assert!(IncrementalParser::default().is_empty());
+    }
+
+    #[test]
+    fn test_incremental_parser_update_parses_changed_lines() {
+        let mut text = note(1000);
+        let mut parser = IncrementalParser::parse(&text);
+        text = text.replace("[[Note 500]]", "[[Renamed]]");
+        assert_eq!(parser.update(&text), 1);
+        let references = parser.references();
+        assert_eq!(references.len(), 1000);
+        assert_eq!(references[500].header, "Renamed");
+        assert_eq!(references[500].location.line_position, 500);
+        assert_eq!(parser.update(&text), 0);
+    }
+
+    #[test]
+    fn test_incremental_parser_update_shifts_following_lines() {
+        let text = note(100);
+        let mut parser = IncrementalParser::parse(&text);
+        // The line that opened the note is parsed again, no longer being the first.
+        assert_eq!(parser.update(&format!("# Title\n\n{text}")), 3);
+        assert_eq!(parser.header().unwrap().header, "Title");
+        let references = parser.references();
+        assert_eq!(references[99].header, "Note 99");
+        assert_eq!(references[99].location.line_position, 101);
+        assert_eq!(parser.tags()[99].location.line_position, 101);
+        let last_line = text.lines().last().unwrap().len() + 1;
+        assert_eq!(
+            parser.line_index().position(101, 0).offset,
+            "# Title\n\n".len() + text.len() - last_line
+        );
+    }
+
+    #[test]
+    fn test_incremental_parser_edit_parses_lines_until_state_is_unchanged() {
+        let mut parser = IncrementalParser::parse(&note(10));
+        // Opening a fence masks every line after it.
+        assert_eq!(parser.edit(2, 2, "```\n"), 9);
+        assert_eq!(parser.references().len(), 2);
+        // Editing a masked line leaves the lines after it in the fence.
+        assert_eq!(parser.edit(4, 5, "[[Masked]]\n"), 1);
+        assert_eq!(parser.references().len(), 2);
+        // Closing the fence unmasks the lines after it.
+        assert_eq!(parser.edit(5, 5, "```\n"), 7);
+        assert_eq!(parser.references().len(), 2 + 6);
+        assert_eq!(parser.references()[2].location.line_position, 6);
+    }
+
+    #[test]
+    fn test_incremental_parser_setext_headings() {
+        let mut parser = IncrementalParser::parse("Title\n\ntext\n");
+        assert!(parser.headings().is_empty());
+        assert_eq!(parser.edit(1, 2, "===\n"), 2);
+        let headings = parser.headings();
+        assert_eq!(headings.len(), 1);
+        assert_eq!(headings[0].text, "Title");
+        assert_eq!(headings[0].level, 1);
+        assert_eq!(headings[0].location.line_position, 0);
+        assert_eq!(parser.header().unwrap().header, "Title");
+
+        parser.edit(0, 1, "Renamed\n");
+        assert_eq!(parser.headings()[0].text, "Renamed");
+    }
+
+    #[test]
+    fn test_incremental_parser_markdown_links() {
+        let mut parser = IncrementalParser::parse("[text][label]\n\n[inline](a.md)\n");
+        assert_eq!(parser.markdown_links().len(), 1);
+        parser.push_line("[label]: b.md\n");
+        let links = parser.markdown_links();
+        assert_eq!(links.len(), 2);
+        assert_eq!(links[0].destination, "b.md");
+        assert_eq!(links[0].destination_location.line_position, 3);
+        assert_eq!(links[1].location.line_position, 2);
+    }
+
+    #[test]
+    fn test_incremental_parser_front_matter() {
+        let mut parser = IncrementalParser::parse("---\ntitle: First\n---\n# Header\n");
+        assert_eq!(parser.front_matter().unwrap().yaml, "title: First\n");
+        parser.update("---\ntitle: Second\n---\n# Header\n");
+        let front_matter = parser.front_matter().unwrap();
+        assert_eq!(front_matter.yaml, "title: Second\n");
+        assert_eq!(
+            front_matter.title_location("Second").unwrap().line_position,
+            1
+        );
+        // Front matter lines are masked, so they yield no header.
+        assert_eq!(parser.header().unwrap().header, "Header");
+    }
+
+    #[test]
+    fn test_incremental_parser_org() {
+        let text = "#+TITLE: ignored\n* Meeting :work:\n[[Person]]\n#+begin_src\n[[Code]]\n#+end_src\n- [ ] Call [[file:x.org]]\n";
+        let mut parser = IncrementalParser::parse_with_format(text, NoteFormat::Org);
+        assert_eq!(parser.format(), NoteFormat::Org);
+        assert!(parser.front_matter().is_none());
+        let header = parser.header().unwrap();
+        assert_eq!(header.header, "Meeting");
+        assert_eq!(header.location.line_position, 1);
+        assert_eq!(parser.tags()[0].name, "work");
+        assert_eq!(parser.references().len(), 1);
+        assert_eq!(parser.references()[0].location.line_position, 2);
+        assert_eq!(parser.tasks()[0].location.line_position, 6);
+        assert_eq!(parser.markdown_links()[0].destination, "x.org");
+
+        // Unclosing the block unmasks its link.
+        assert_eq!(parser.edit(5, 6, ""), 1);
+        assert_eq!(parser.references().len(), 1);
+        assert_eq!(parser.edit(3, 4, ""), 2);
+        assert_eq!(parser.references().len(), 2);
+        assert_eq!(parser.references()[1].header, "Code");
+        assert_eq!(parser.references()[1].location.line_position, 3);
+    }
+}
@@ -0,0 +1,151 @@
+use crate::models::{Location, Position};
+use crate::parsers::CharSpan;
+
+/// Resolves the char columns tracked by the parsers into positions within a note.
+pub struct LineIndex<'a> {
+    lines: Vec<(usize, &'a str)>,
+    length: usize,
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn new(text: &'a str) -> Self {
+        Self::from_lines(text.split_inclusive('\n').map(|line| {
+            let content = line.strip_suffix('\n').unwrap_or(line);
+            let content = content.strip_suffix('\r').unwrap_or(content);
+            (content, line.len() - content.len())
+        }))
+    }
+
+    /// Indexes lines given without their line break, along with the length of that break.
+    pub fn from_lines(lines: impl IntoIterator<Item = (&'a str, usize)>) -> Self {
+        let mut offset = 0;
+        let lines = lines
+            .into_iter()
+            .map(|(line, line_break)| {
+                let start = offset;
+                offset += line.len() + line_break;
+                (start, line)
+            })
+            .collect();
+        Self {
+            lines,
+            length: offset,
+        }
+    }
+
+    /// The position `char_column` chars into `line`, clamped to the end of the line.
+    pub fn position(&self, line: u32, char_column: usize) -> Position {
+        let (offset, text) = match self.lines.get(line as usize) {
+            Some((offset, text)) => (*offset, *text),
+            None => (self.length, ""),
+        };
+        if text.is_ascii() {
+            let column = char_column.min(text.len());
+            return Position {
+                line,
+                offset: offset + column,
+                utf8_column: column as u32,
+                utf16_column: column as u32,
+                utf32_column: column as u32,
+            };
+        }
+        let mut position = Position {
+            line,
+            offset,
+            ..Position::default()
+        };
+        for c in text.chars().take(char_column) {
+            position.offset += c.len_utf8();
+            position.utf8_column += c.len_utf8() as u32;
+            position.utf16_column += c.len_utf16() as u32;
+            position.utf32_column += 1;
+        }
+        position
+    }
+
+    /// The position at `utf16_column`, as LSP clients count columns, clamped to the end of the
+    /// line. A column in the middle of a surrogate pair is moved back to the start of its char.
+    pub fn position_utf16(&self, line: u32, utf16_column: u32) -> Position {
+        let text = self
+            .lines
+            .get(line as usize)
+            .map(|(_, text)| *text)
+            .unwrap_or_default();
+        let mut utf16 = 0;
+        let char_column = text
+            .chars()
+            .take_while(|c| {
+                utf16 += c.len_utf16() as u32;
+                utf16 <= utf16_column
+            })
+            .count();
+        self.position(line, char_column)
+    }
+
+    /// The location from the first char of `span` to just past its last one.
+    pub fn location(&self, span: &CharSpan) -> Location {
+        let start = span.start_char_position.max(0);
+        let end = (span.end_char_position + 1).max(start);
+        Location::new(
+            self.position(span.line_position, start as usize),
+            self.position(span.line_position, end as usize),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_index_position() {
+        let index = LineIndex::new("# Tïtle\r\né😀 [[x]]\n");
+        let position = index.position(1, 3);
+        assert_eq!(position.line, 1);
+        assert_eq!(position.offset, 10 + 7);
+        assert_eq!(position.utf8_column, 7);
+        assert_eq!(position.utf16_column, 4);
+        assert_eq!(position.utf32_column, 3);
+
+        let clamped = index.position(0, 100);
+        assert_eq!(clamped.utf32_column, 7);
+        assert_eq!(clamped.offset, 8);
+
+        let past_end = index.position(2, 0);
+        assert_eq!(past_end.offset, 10 + 13);
+        assert_eq!(past_end.utf8_column, 0);
+    }
+
+    #[test]
+    fn test_line_index_position_utf16() {
+        let index = LineIndex::new("# Tïtle\r\né😀 [[x]]\n");
+        let position = index.position_utf16(1, 4);
+        assert_eq!(position.utf32_column, 3);
+        assert_eq!(position.utf8_column, 7);
+
+        assert_eq!(index.position_utf16(1, 2).utf16_column, 1);
+        assert_eq!(index.position_utf16(0, 100).utf16_column, 7);
+        assert_eq!(index.position_utf16(5, 3).utf16_column, 0);
+    }
+
+    #[test]
+    fn test_line_index_location() {
+        let index = LineIndex::new("é😀 [[x]]\n");
+        let span = CharSpan {
+            in_range: false,
+            line_position: 0,
+            start_char_position: 3,
+            end_char_position: 7,
+        };
+        let location = index.location(&span);
+        assert_eq!(location.start.utf32_column, 3);
+        assert_eq!(location.start.utf16_column, 4);
+        assert_eq!(location.end.utf32_column, 8);
+        assert_eq!(location.end.utf8_column, 12);
+        assert_eq!(location.end.offset, 12);
+
+        let empty = index.location(&CharSpan::default());
+        assert_eq!(empty.start, empty.end);
+        assert_eq!(empty.start.utf32_column, 0);
+    }
+}
@@ -0,0 +1,357 @@
+use crate::models::ReferenceKind;
+use crate::parsers::{CharSpan, LineParser, Parser};
+use std::collections::HashMap;
+
+/// Parses inline `[text](destination)` and reference-style `[text][label]` Markdown links.
+///
+/// Reference-style links are only kept when their `[label]: destination` definition is found,
+/// which may come after the link itself.
+pub struct MarkdownLinkParser {
+    line: Vec<char>,
+    line_position: u32,
+    links: Vec<MarkdownLinkParserPayload>,
+    pending: Vec<(String, MarkdownLinkParserPayload)>,
+    definitions: HashMap<String, (String, CharSpan)>,
+}
+
+impl MarkdownLinkParser {
+    pub fn new() -> Self {
+        Self {
+            line: Vec::new(),
+            line_position: 0,
+            links: vec![],
+            pending: vec![],
+            definitions: HashMap::new(),
+        }
+    }
+
+    pub fn call(mut self) -> Vec<MarkdownLinkParserPayload> {
+        self.finish_line();
+        resolve_markdown_links(self.links, self.pending, &self.definitions)
+    }
+
+    fn finish_line(&mut self) {
+        let line = std::mem::take(&mut self.line);
+        if !self.parse_definition(&line) {
+            self.parse_links(&line);
+        }
+        self.line_position += 1;
+    }
+
+    fn parse_definition(&mut self, line: &[char]) -> bool {
+        let start = line.iter().take_while(|c| **c == ' ').count();
+        if start > 3 || line.get(start) != Some(&'[') {
+            return false;
+        }
+        let end = match closing_bracket(line, start) {
+            Some(end) if line.get(end + 1) == Some(&':') => end,
+            _ => return false,
+        };
+        let label = normalize_label(&line[start + 1..end]);
+        if label.is_empty() {
+            return false;
+        }
+        let destination_start = end + 2 + count_whitespace(&line[end + 2..]);
+        let (destination, location) = match self.destination(line, destination_start) {
+            Some(destination) => destination,
+            None => return false,
+        };
+        // The first definition of a label wins, as in CommonMark.
+        self.definitions
+            .entry(label)
+            .or_insert((destination, location));
+        true
+    }
+
+    fn parse_links(&mut self, line: &[char]) {
+        let mut i = 0;
+        while i < line.len() {
+            match line[i] {
+                '\\' => i += 2,
+                '[' if line.get(i + 1) == Some(&'[') => {
+                    i = find_wiki_link_end(line, i + 2).unwrap_or(i + 2);
+                }
+                '[' => match closing_bracket(line, i) {
+                    Some(end) => i = self.parse_link(line, i, end),
+                    None => i += 1,
+                },
+                _ => i += 1,
+            }
+        }
+    }
+
+    /// Parses the link whose text spans from `start` to `end`, returning where to resume.
+    fn parse_link(&mut self, line: &[char], start: usize, end: usize) -> usize {
+        let embed = start > 0 && line[start - 1] == '!';
+        let text: String = line[start + 1..end].iter().collect();
+        let link_start = if embed { start - 1 } else { start };
+        let mut link = MarkdownLinkParserPayload {
+            text: text.trim().to_string(),
+            text_location: self.location(start + 1, end.saturating_sub(1).max(start + 1)),
+            destination: String::new(),
+            destination_location: CharSpan::default(),
+            kind: match embed {
+                true => ReferenceKind::Embed,
+                false => ReferenceKind::Link,
+            },
+            location: CharSpan::default(),
+        };
+
+        match line.get(end + 1) {
+            Some('(') => {
+                let close = match closing_parenthesis(line, end + 1) {
+                    Some(close) => close,
+                    None => return end + 1,
+                };
+                let destination_start = end + 2 + count_whitespace(&line[end + 2..close]);
+                if let Some((destination, location)) =
+                    self.destination(&line[..close], destination_start)
+                {
+                    link.destination = destination;
+                    link.destination_location = location;
+                    link.location = self.location(link_start, close);
+                    self.links.push(link);
+                }
+                close + 1
+            }
+            Some('[') => {
+                let close = match closing_bracket(line, end + 1) {
+                    Some(close) => close,
+                    None => return end + 1,
+                };
+                let label = match normalize_label(&line[end + 2..close]) {
+                    label if label.is_empty() => normalize_label(&line[start + 1..end]),
+                    label => label,
+                };
+                link.location = self.location(link_start, close);
+                self.pending.push((label, link));
+                close + 1
+            }
+            _ => {
+                link.location = self.location(link_start, end);
+                self.pending
+                    .push((normalize_label(&line[start + 1..end]), link));
+                end + 1
+            }
+        }
+    }
+
+    /// Reads a destination, either `<enclosed in brackets>` or up to the next whitespace.
+    fn destination(&self, line: &[char], start: usize) -> Option<(String, CharSpan)> {
+        let (start, end) = match line.get(start) {
+            Some('<') => {
+                let close = start + 1 + line[start + 1..].iter().position(|c| *c == '>')?;
+                (start + 1, close)
+            }
+            Some(_) => {
+                let length = line[start..]
+                    .iter()
+                    .take_while(|c| !c.is_whitespace())
+                    .count();
+                (start, start + length)
+            }
+            None => return None,
+        };
+        if start >= end {
+            return None;
+        }
+        let destination: String = line[start..end].iter().collect();
+        Some((destination, self.location(start, end - 1)))
+    }
+
+    fn location(&self, start: usize, end: usize) -> CharSpan {
+        CharSpan {
+            in_range: false,
+            line_position: self.line_position,
+            start_char_position: start as i64,
+            end_char_position: end as i64,
+        }
+    }
+}
+
+impl Default for MarkdownLinkParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LineParser for MarkdownLinkParser {
+    type Payload = MarkdownLinkParserLine;
+
+    fn into_payload(mut self) -> Self::Payload {
+        self.finish_line();
+        MarkdownLinkParserLine {
+            links: self.links,
+            pending: self.pending,
+            definitions: self.definitions,
+        }
+    }
+}
+
+/// The links of a line, with its reference-style links left to resolve against the definitions
+/// of the whole note.
+#[derive(Debug, Clone, Default)]
+pub struct MarkdownLinkParserLine {
+    pub links: Vec<MarkdownLinkParserPayload>,
+    pub pending: Vec<(String, MarkdownLinkParserPayload)>,
+    pub definitions: HashMap<String, (String, CharSpan)>,
+}
+
+/// Keeps the reference-style links whose label is defined, sorted along with the inline ones.
+pub fn resolve_markdown_links(
+    mut links: Vec<MarkdownLinkParserPayload>,
+    pending: Vec<(String, MarkdownLinkParserPayload)>,
+    definitions: &HashMap<String, (String, CharSpan)>,
+) -> Vec<MarkdownLinkParserPayload> {
+    for (label, mut link) in pending {
+        if let Some((destination, location)) = definitions.get(&label) {
+            link.destination = destination.clone();
+            link.destination_location = location.clone();
+            links.push(link);
+        }
+    }
+    links.sort_by_key(|link| {
+        (
+            link.location.line_position,
+            link.location.start_char_position,
+        )
+    });
+    links
+}
+
+impl Parser for MarkdownLinkParser {
+    fn next(&mut self, c: char) {
+        match c {
+            '\n' => self.finish_line(),
+            c => self.line.push(c),
+        }
+    }
+}
+
+fn closing_bracket(line: &[char], open: usize) -> Option<usize> {
+    closing(line, open, '[', ']')
+}
+
+fn closing_parenthesis(line: &[char], open: usize) -> Option<usize> {
+    closing(line, open, '(', ')')
+}
+
+fn closing(line: &[char], open: usize, opening: char, closing: char) -> Option<usize> {
+    let mut depth = 0;
+    let mut i = open;
+    while i < line.len() {
+        match line[i] {
+            '\\' => i += 1,
+            c if c == opening => depth += 1,
+            c if c == closing => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+fn find_wiki_link_end(line: &[char], start: usize) -> Option<usize> {
+    (start..line.len().saturating_sub(1))
+        .find(|i| line[*i] == ']' && line[*i + 1] == ']')
+        .map(|i| i + 2)
+}
+
+fn count_whitespace(chars: &[char]) -> usize {
+    chars.iter().take_while(|c| c.is_whitespace()).count()
+}
+
+/// Labels match case-insensitively, with runs of whitespace collapsed.
+fn normalize_label(label: &[char]) -> String {
+    label
+        .iter()
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+#[derive(Debug, Clone)]
+pub struct MarkdownLinkParserPayload {
+    pub text: String,
+    pub text_location: CharSpan,
+    pub destination: String,
+    pub destination_location: CharSpan,
+    pub kind: ReferenceKind,
+    pub location: CharSpan,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Vec<MarkdownLinkParserPayload> {
+        let mut parser = MarkdownLinkParser::new();
+        text.chars().for_each(|c| parser.next(c));
+        parser.call()
+    }
+
+    #[test]
+    fn test_markdown_link_parser_new() {
+        assert!(MarkdownLinkParser::new().links.is_empty());
+    }
+
+    #[test]
+    fn test_markdown_link_parser_default() {
+        assert!(MarkdownLinkParser::default().definitions.is_empty());
+    }
+
+    #[test]
+    fn test_markdown_link_parser_inline() {
+        let links = parse("See [the note](../dir/note.md#heading \"Title\") now");
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].text, "the note");
+        assert_eq!(links[0].text_location.start_char_position, 5);
+        assert_eq!(links[0].text_location.end_char_position, 12);
+        assert_eq!(links[0].destination, "../dir/note.md#heading");
+        assert_eq!(links[0].destination_location.start_char_position, 15);
+        assert_eq!(links[0].destination_location.end_char_position, 36);
+        assert_eq!(links[0].location.start_char_position, 4);
+        assert_eq!(links[0].location.end_char_position, 45);
+        assert_eq!(links[0].kind, ReferenceKind::Link);
+    }
+
+    #[test]
+    fn test_markdown_link_parser_angle_brackets_and_embeds() {
+        let links = parse("![image](<my note.md>)");
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].destination, "my note.md");
+        assert_eq!(links[0].kind, ReferenceKind::Embed);
+        assert_eq!(links[0].location.start_char_position, 0);
+        assert_eq!(links[0].location.end_char_position, 21);
+    }
+
+    #[test]
+    fn test_markdown_link_parser_reference_style() {
+        let links = parse(
+            "[full][Label] and [collapsed][] and [shortcut] and [undefined]\n\n\
+             [label]: note.md\n[Collapsed]: <other.md>\n  [shortcut]: third.md 'title'\n",
+        );
+        assert_eq!(links.len(), 3);
+        assert_eq!(links[0].destination, "note.md");
+        assert_eq!(links[0].destination_location.line_position, 2);
+        assert_eq!(links[0].destination_location.start_char_position, 9);
+        assert_eq!(links[0].location.start_char_position, 0);
+        assert_eq!(links[0].location.end_char_position, 12);
+        assert_eq!(links[1].destination, "other.md");
+        assert_eq!(links[1].text, "collapsed");
+        assert_eq!(links[2].destination, "third.md");
+        assert_eq!(links[2].location.end_char_position, 45);
+    }
+
+    #[test]
+    fn test_markdown_link_parser_ignores_wiki_links() {
+        assert!(parse("[[Note]] and [[Note|alias]](not.md) and [ ] task\n").is_empty());
+    }
+}
@@ -1,8 +1,47 @@
 mod parser;
 pub use self::parser::*;
 
+mod char_span;
+pub use self::char_span::*;
+
+mod line_index;
+pub use self::line_index::*;
+
+mod front_matter_parser;
+pub use self::front_matter_parser::*;
+
 mod header_parser;
 pub use self::header_parser::*;
 
+mod blocks_parser;
+pub use self::blocks_parser::*;
+
+mod headings_parser;
+pub use self::headings_parser::*;
+
+mod markdown_link_parser;
+pub use self::markdown_link_parser::*;
+
 mod reference_parser;
 pub use self::reference_parser::*;
+
+mod tag_parser;
+pub use self::tag_parser::*;
+
+mod task_parser;
+pub use self::task_parser::*;
+
+mod prose_filter;
+pub use self::prose_filter::*;
+
+mod note_format;
+pub use self::note_format::*;
+
+mod org_parser;
+pub use self::org_parser::*;
+
+mod incremental_parser;
+pub use self::incremental_parser::*;
+
+mod tokenizer;
+pub use self::tokenizer::*;
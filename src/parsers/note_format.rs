@@ -0,0 +1,7 @@
+/// The markup a note is written in, which decides how its lines are parsed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum NoteFormat {
+    #[default]
+    Markdown,
+    Org,
+}
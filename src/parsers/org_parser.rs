@@ -0,0 +1,458 @@
+use crate::models::ReferenceKind;
+use crate::parsers::{
+    CharSpan, HeadingsParserPayload, LineParser, MarkdownLinkParserPayload, Parser,
+    ReferenceParserPayload, TagParserPayload,
+};
+
+const MASK: char = ' ';
+const FILE_LINK_PREFIX: &str = "file:";
+const SEARCH_OPTION_DELIMITER: &str = "::";
+const HEADLINE_KEYWORDS: [&str; 2] = ["TODO", "DONE"];
+const BLOCK_START: &str = "#+begin_";
+const BLOCK_END: &str = "#+end_";
+// Quote, verse and center blocks hold prose, so their links are kept.
+const MASKED_BLOCKS: [&str; 4] = ["src", "example", "export", "comment"];
+
+/// Parses the headlines and links of Org-mode notes.
+///
+/// - A headline is a line starting with stars followed by a space, its level being the number
+///   of stars. A leading `TODO` or `DONE` keyword and `[#A]` priority cookie aren't part of its
+///   text, and its trailing `:tag:other:` group is read as tags.
+/// - A link reads `[[target]]` or `[[target][description]]`, the description being its alias.
+/// - `[[Heading]]` targets the note with that header, and `[[*Heading]]` a heading of the
+///   current note.
+/// - `[[file:note.org]]` targets a file relative to the current note, and
+///   `[[file:note.org::*Heading]]` a heading within it.
+/// - Links of any other type, such as `https:` or `id:`, aren't notes and are skipped.
+pub struct OrgParser {
+    line: Vec<char>,
+    line_position: u32,
+    headings: Vec<HeadingsParserPayload>,
+    tags: Vec<TagParserPayload>,
+    references: Vec<ReferenceParserPayload>,
+    file_links: Vec<MarkdownLinkParserPayload>,
+}
+
+impl OrgParser {
+    pub fn new() -> Self {
+        Self {
+            line: Vec::new(),
+            line_position: 0,
+            headings: vec![],
+            tags: vec![],
+            references: vec![],
+            file_links: vec![],
+        }
+    }
+
+    pub fn call(mut self) -> OrgParserPayload {
+        self.finish_line();
+        OrgParserPayload {
+            headings: self.headings,
+            tags: self.tags,
+            references: self.references,
+            file_links: self.file_links,
+        }
+    }
+
+    fn finish_line(&mut self) {
+        let line = std::mem::take(&mut self.line);
+        self.parse_headline(&line);
+        self.parse_links(&line);
+        self.line_position += 1;
+    }
+
+    fn parse_headline(&mut self, line: &[char]) {
+        let stars = line.iter().take_while(|c| **c == '*').count();
+        if stars == 0 || line.get(stars) != Some(&' ') {
+            return;
+        }
+        let mut start = stars + count_whitespace(&line[stars..]);
+        let mut end = line.len();
+        while end > start && line[end - 1].is_whitespace() {
+            end -= 1;
+        }
+
+        let tags_start = line[start..end]
+            .iter()
+            .rposition(|c| c.is_whitespace())
+            .map(|i| start + i + 1)
+            .unwrap_or(start);
+        if let Some(tags) = self.headline_tags(line, tags_start, end) {
+            self.tags.extend(tags);
+            end = tags_start;
+            while end > start && line[end - 1].is_whitespace() {
+                end -= 1;
+            }
+        }
+
+        for keyword in HEADLINE_KEYWORDS {
+            let keyword: Vec<char> = keyword.chars().collect();
+            let after = start + keyword.len();
+            if line[start..end].starts_with(&keyword) && (after == end || line[after] == ' ') {
+                start = after + count_whitespace(&line[after..end]);
+                break;
+            }
+        }
+        if let ['[', '#', priority, ']', rest @ ..] = &line[start..end] {
+            if priority.is_ascii_alphanumeric() && rest.first().is_none_or(|c| *c == ' ') {
+                start += 4;
+                start += count_whitespace(&line[start..end]);
+            }
+        }
+        if start >= end {
+            return;
+        }
+        self.headings.push(HeadingsParserPayload {
+            level: stars.min(u8::MAX as usize) as u8,
+            text: line[start..end].iter().collect(),
+            location: self.location(start, end - 1),
+        });
+    }
+
+    /// Reads the `:tag:other:` group spanning from `start` to `end` excluded, if it is one.
+    fn headline_tags(
+        &self,
+        line: &[char],
+        start: usize,
+        end: usize,
+    ) -> Option<Vec<TagParserPayload>> {
+        let group = &line[start..end];
+        let valid = group.len() > 2
+            && group[0] == ':'
+            && group[group.len() - 1] == ':'
+            && group
+                .iter()
+                .all(|c| *c == ':' || c.is_alphanumeric() || matches!(c, '_' | '@' | '#' | '%'));
+        if !valid {
+            return None;
+        }
+        let mut tags = Vec::new();
+        let mut tag_start = start + 1;
+        for i in start + 1..end {
+            if line[i] != ':' {
+                continue;
+            }
+            if i > tag_start {
+                tags.push(TagParserPayload {
+                    name: line[tag_start..i].iter().collect(),
+                    location: self.location(tag_start, i - 1),
+                });
+            }
+            tag_start = i + 1;
+        }
+        Some(tags)
+    }
+
+    fn parse_links(&mut self, line: &[char]) {
+        let mut i = 0;
+        while i + 1 < line.len() {
+            if line[i] == '[' && line[i + 1] == '[' {
+                i = self.parse_link(line, i).unwrap_or(i + 2);
+            } else {
+                i += 1;
+            }
+        }
+    }
+
+    /// Parses the link opening at `start`, returning where to resume if it is closed.
+    fn parse_link(&mut self, line: &[char], start: usize) -> Option<usize> {
+        let target_start = start + 2;
+        let target_end = target_start + line[target_start..].iter().position(|c| *c == ']')?;
+        // Another link opening before this one closes means this one is never closed.
+        if line[target_start..target_end].contains(&'[') {
+            return None;
+        }
+        let (description, end) = match line.get(target_end + 1) {
+            Some(']') => (None, target_end + 1),
+            Some('[') => {
+                let description_start = target_end + 2;
+                let close = (description_start..line.len().saturating_sub(1))
+                    .find(|i| line[*i] == ']' && line[*i + 1] == ']')?;
+                (Some((description_start, close)), close + 1)
+            }
+            _ => return None,
+        };
+        let target: String = line[target_start..target_end].iter().collect();
+        let location = self.location(start, end);
+        let (alias, alias_location) = match description {
+            Some((description_start, close)) => match self.trim(line, description_start, close) {
+                Some((alias, location)) => (Some(alias), Some(location)),
+                None => (None, None),
+            },
+            None => (None, None),
+        };
+
+        if let Some(file) = target.strip_prefix(FILE_LINK_PREFIX) {
+            let (file, search) = match file.split_once(SEARCH_OPTION_DELIMITER) {
+                Some((file, search)) => (file, Some(search)),
+                None => (file, None),
+            };
+            if file.is_empty() {
+                return Some(end + 1);
+            }
+            // Markdown links carry their section after a `#`, which the index reads alike.
+            let destination = match search.and_then(|search| search.strip_prefix('*')) {
+                Some(heading) => format!("{file}#{heading}"),
+                None => String::from(file),
+            };
+            let file_start = target_start + FILE_LINK_PREFIX.len();
+            self.file_links.push(MarkdownLinkParserPayload {
+                text: alias.unwrap_or_default(),
+                text_location: alias_location.unwrap_or_default(),
+                destination,
+                destination_location: self
+                    .location(file_start, file_start + file.chars().count() - 1),
+                kind: ReferenceKind::Link,
+                location,
+            });
+            return Some(end + 1);
+        }
+        if has_link_type(&target) || target.starts_with(['#', '(']) {
+            return Some(end + 1);
+        }
+
+        let mut reference = match target.strip_prefix('*') {
+            Some(_) => {
+                let section = self.trim(line, target_start + 1, target_end);
+                let mut reference = ReferenceParserPayload::link(String::new(), None, location);
+                reference.section = section.map(|(section, _)| section);
+                reference
+            }
+            None => match self.trim(line, target_start, target_end) {
+                Some((header, header_location)) => {
+                    ReferenceParserPayload::link(header, Some(header_location), location)
+                }
+                None => return Some(end + 1),
+            },
+        };
+        reference.alias = alias;
+        reference.alias_location = alias_location;
+        self.references.push(reference);
+        Some(end + 1)
+    }
+
+    /// The text from `start` to `end` excluded without its surrounding whitespace, if any is left.
+    fn trim(&self, line: &[char], start: usize, end: usize) -> Option<(String, CharSpan)> {
+        let leading = count_whitespace(&line[start..end]);
+        let trailing = line[start..end]
+            .iter()
+            .rev()
+            .take_while(|c| c.is_whitespace())
+            .count();
+        if start + leading >= end - trailing {
+            return None;
+        }
+        let (start, end) = (start + leading, end - trailing);
+        Some((
+            line[start..end].iter().collect(),
+            self.location(start, end - 1),
+        ))
+    }
+
+    fn location(&self, start: usize, end: usize) -> CharSpan {
+        CharSpan {
+            in_range: false,
+            line_position: self.line_position,
+            start_char_position: start as i64,
+            end_char_position: end as i64,
+        }
+    }
+}
+
+impl Default for OrgParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LineParser for OrgParser {
+    type Payload = OrgParserPayload;
+
+    fn into_payload(self) -> Self::Payload {
+        self.call()
+    }
+}
+
+impl Parser for OrgParser {
+    fn next(&mut self, c: char) {
+        match c {
+            '\n' => self.finish_line(),
+            c => self.line.push(c),
+        }
+    }
+}
+
+/// What an Org-mode note holds, its file links being resolved as Markdown links are.
+#[derive(Debug, Clone, Default)]
+pub struct OrgParserPayload {
+    pub headings: Vec<HeadingsParserPayload>,
+    pub tags: Vec<TagParserPayload>,
+    pub references: Vec<ReferenceParserPayload>,
+    pub file_links: Vec<MarkdownLinkParserPayload>,
+}
+
+/// Masks the Org-mode lines that aren't prose: comment lines, and the lines of source, example,
+/// export and comment blocks.
+///
+/// Like the `ProseFilter`, masked characters are replaced by spaces to keep locations accurate.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct OrgFilter {
+    block: Option<String>,
+}
+
+impl OrgFilter {
+    pub fn new() -> Self {
+        Self { block: None }
+    }
+
+    pub fn filter(&mut self, line: &str) -> String {
+        let trimmed = line.trim_start().to_lowercase();
+        match &self.block {
+            Some(block) => {
+                if trimmed.strip_prefix(BLOCK_END).map(str::trim_end) == Some(block.as_str()) {
+                    self.block = None;
+                }
+                mask(line)
+            }
+            None => {
+                let block = trimmed
+                    .strip_prefix(BLOCK_START)
+                    .and_then(|rest| rest.split_whitespace().next())
+                    .filter(|block| MASKED_BLOCKS.contains(block));
+                if let Some(block) = block {
+                    self.block = Some(String::from(block));
+                    return mask(line);
+                }
+                match trimmed == "#" || trimmed.starts_with("# ") {
+                    true => mask(line),
+                    false => String::from(line),
+                }
+            }
+        }
+    }
+}
+
+/// Whether `target` starts with a link type such as `https:`, made of lowercase letters and
+/// directly followed by the link, which tells it apart from a header such as `Meeting: notes`.
+fn has_link_type(target: &str) -> bool {
+    match target.split_once(':') {
+        Some((link_type, rest)) => {
+            !link_type.is_empty()
+                && link_type
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c == '+')
+                && !rest.starts_with(char::is_whitespace)
+        }
+        None => false,
+    }
+}
+
+fn count_whitespace(chars: &[char]) -> usize {
+    chars.iter().take_while(|c| c.is_whitespace()).count()
+}
+
+fn mask(line: &str) -> String {
+    line.chars().map(|_| MASK).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> OrgParserPayload {
+        let mut parser = OrgParser::new();
+        text.chars().for_each(|c| parser.next(c));
+        parser.call()
+    }
+
+    #[test]
+    fn test_org_parser_default() {
+        assert!(OrgParser::default().headings.is_empty());
+    }
+
+    #[test]
+    fn test_org_parser_headlines() {
+        let payload = parse("* Title\ntext *bold*\n** TODO Sub heading   :work:urgent:\n*not\n");
+        assert_eq!(payload.headings.len(), 2);
+        assert_eq!(payload.headings[0].level, 1);
+        assert_eq!(payload.headings[0].text, "Title");
+        assert_eq!(payload.headings[0].location.start_char_position, 2);
+        assert_eq!(payload.headings[0].location.end_char_position, 6);
+        assert_eq!(payload.headings[1].level, 2);
+        assert_eq!(payload.headings[1].text, "Sub heading");
+        assert_eq!(payload.headings[1].location.line_position, 2);
+        assert_eq!(payload.headings[1].location.start_char_position, 8);
+        assert_eq!(payload.tags.len(), 2);
+        assert_eq!(payload.tags[0].name, "work");
+        assert_eq!(payload.tags[0].location.start_char_position, 23);
+        assert_eq!(payload.tags[1].name, "urgent");
+        assert_eq!(payload.tags[1].location.end_char_position, 33);
+    }
+
+    #[test]
+    fn test_org_parser_headline_priority() {
+        let payload = parse("* TODO [#A] Title\n** [#1] Other :work:\n*** [#B]\n* [#Ab] Kept\n");
+        let texts: Vec<&str> = payload
+            .headings
+            .iter()
+            .map(|heading| heading.text.as_str())
+            .collect();
+        assert_eq!(texts, vec!["Title", "Other", "[#Ab] Kept"]);
+        assert_eq!(payload.headings[0].location.start_char_position, 12);
+    }
+
+    #[test]
+    fn test_org_parser_links() {
+        let payload = parse(
+            "See [[Other Note]] and [[Other Note][the other]] or [[*Section]]\n\
+             [[file:../dir/x.org]] [[file:y.org::*Part][y]] [[https://example.com][site]]\n",
+        );
+        assert_eq!(payload.references.len(), 3);
+        assert_eq!(payload.references[0].header, "Other Note");
+        assert_eq!(payload.references[0].location.start_char_position, 4);
+        assert_eq!(payload.references[0].location.end_char_position, 17);
+        let header_location = payload.references[0].header_location.clone().unwrap();
+        assert_eq!(header_location.start_char_position, 6);
+        assert_eq!(header_location.end_char_position, 15);
+        assert_eq!(payload.references[1].alias.as_deref(), Some("the other"));
+        assert_eq!(payload.references[1].location.end_char_position, 47);
+        assert_eq!(payload.references[2].header, "");
+        assert_eq!(payload.references[2].section.as_deref(), Some("Section"));
+
+        assert_eq!(payload.file_links.len(), 2);
+        assert_eq!(payload.file_links[0].destination, "../dir/x.org");
+        assert_eq!(payload.file_links[0].destination_location.line_position, 1);
+        assert_eq!(
+            payload.file_links[0]
+                .destination_location
+                .start_char_position,
+            7
+        );
+        assert_eq!(
+            payload.file_links[0].destination_location.end_char_position,
+            18
+        );
+        assert_eq!(payload.file_links[1].destination, "y.org#Part");
+        assert_eq!(payload.file_links[1].text, "y");
+    }
+
+    #[test]
+    fn test_org_parser_skips_unclosed_links_and_link_types() {
+        let payload = parse("[[Open and [[id:1234]] [[Meeting: notes]]\n");
+        assert_eq!(payload.references.len(), 1);
+        assert_eq!(payload.references[0].header, "Meeting: notes");
+    }
+
+    #[test]
+    fn test_org_filter() {
+        let mut filter = OrgFilter::new();
+        assert_eq!(filter.filter("[[Note]]"), "[[Note]]");
+        assert_eq!(filter.filter("#+BEGIN_SRC rust"), " ".repeat(16));
+        assert_eq!(filter.filter("[[Code]]"), " ".repeat(8));
+        assert_eq!(filter.filter("#+end_src"), " ".repeat(9));
+        assert_eq!(filter.filter("# [[Comment]]"), " ".repeat(13));
+        assert_eq!(filter.filter("#+BEGIN_QUOTE"), "#+BEGIN_QUOTE");
+        assert_eq!(filter.filter("[[Quoted]]"), "[[Quoted]]");
+    }
+}
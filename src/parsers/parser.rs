@@ -1,3 +1,30 @@
 pub trait Parser {
     fn next(&mut self, c: char);
+
+    /// Feeds `line` followed by the line break ending it.
+    fn next_line(&mut self, line: &str) {
+        line.chars().for_each(|c| self.next(c));
+        self.next('\n');
+    }
+
+    /// Whether the parser ignores whatever comes next, so that feeding it can stop early.
+    fn done(&self) -> bool {
+        false
+    }
+}
+
+/// A parser whose payloads on a line only depend on that line.
+///
+/// Their payloads can be cached per line, so that an edit only parses the lines it changed again.
+pub trait LineParser: Parser + Default {
+    type Payload;
+
+    fn into_payload(self) -> Self::Payload;
+
+    /// Parses `line` on its own, locating its payload on line 0.
+    fn parse_line(line: &str) -> Self::Payload {
+        let mut parser = Self::default();
+        parser.next_line(line);
+        parser.into_payload()
+    }
 }
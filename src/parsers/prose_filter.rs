@@ -0,0 +1,475 @@
+const MASK: char = ' ';
+const FRONT_MATTER_DELIMITER: &str = "---";
+const FRONT_MATTER_END_DELIMITER: &str = "...";
+const INDENTED_CODE_WIDTH: usize = 4;
+const HTML_COMMENT_START: &str = "<!--";
+const HTML_COMMENT_END: &str = "-->";
+const HTML_RAW_TAGS: [&str; 4] = ["pre", "script", "style", "textarea"];
+const HTML_BLOCK_TAGS: [&str; 40] = [
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "body",
+    "caption",
+    "center",
+    "col",
+    "colgroup",
+    "dd",
+    "details",
+    "dialog",
+    "dir",
+    "div",
+    "dl",
+    "dt",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "iframe",
+    "li",
+    "main",
+    "nav",
+    "ol",
+    "p",
+    "section",
+    "summary",
+    "table",
+    "td",
+    "ul",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ProseFilterState {
+    Start,
+    FrontMatter,
+    Prose,
+    FencedCode { fence: char, length: usize },
+    IndentedCode,
+    HtmlComment,
+    HtmlBlock,
+    HtmlRawBlock { tag: String },
+}
+
+/// Masks the parts of Markdown lines that aren't prose, such as code and HTML.
+///
+/// Masked characters are replaced by spaces rather than removed, so that the
+/// locations reported by the parsers fed with the filtered lines stay accurate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProseFilter {
+    state: ProseFilterState,
+    interruptible: bool,
+    in_list: bool,
+}
+
+impl ProseFilter {
+    pub fn new() -> Self {
+        Self {
+            state: ProseFilterState::Start,
+            interruptible: true,
+            in_list: false,
+        }
+    }
+
+    pub fn filter(&mut self, line: &str) -> String {
+        let blank = line.trim().is_empty();
+        let (indentation, content) = indentation(line);
+
+        match self.state.clone() {
+            ProseFilterState::Start if line.trim_end() == FRONT_MATTER_DELIMITER => {
+                self.state = ProseFilterState::FrontMatter;
+                mask(line)
+            }
+            ProseFilterState::FrontMatter => {
+                let trimmed = line.trim_end();
+                if trimmed == FRONT_MATTER_DELIMITER || trimmed == FRONT_MATTER_END_DELIMITER {
+                    self.state = ProseFilterState::Prose;
+                    self.interruptible = true;
+                }
+                mask(line)
+            }
+            ProseFilterState::FencedCode { fence, length } => {
+                if indentation < INDENTED_CODE_WIDTH && closes_fence(content, fence, length) {
+                    self.state = ProseFilterState::Prose;
+                    self.interruptible = true;
+                }
+                mask(line)
+            }
+            ProseFilterState::HtmlComment => match line.find(HTML_COMMENT_END) {
+                Some(end) => {
+                    self.state = ProseFilterState::Prose;
+                    let end = end + HTML_COMMENT_END.len();
+                    let mut filtered = mask(&line[..end]);
+                    filtered.push_str(&self.filter_inline(&line[end..]));
+                    filtered
+                }
+                None => mask(line),
+            },
+            ProseFilterState::HtmlBlock => {
+                if blank {
+                    self.state = ProseFilterState::Prose;
+                    self.interruptible = true;
+                }
+                mask(line)
+            }
+            ProseFilterState::HtmlRawBlock { tag } => {
+                if line.to_ascii_lowercase().contains(&format!("</{tag}>")) {
+                    self.state = ProseFilterState::Prose;
+                    self.interruptible = true;
+                }
+                mask(line)
+            }
+            ProseFilterState::IndentedCode if blank || indentation >= INDENTED_CODE_WIDTH => {
+                mask(line)
+            }
+            ProseFilterState::Start | ProseFilterState::IndentedCode | ProseFilterState::Prose => {
+                self.state = ProseFilterState::Prose;
+                self.filter_block_start(line, blank, indentation, content)
+            }
+        }
+    }
+
+    fn filter_block_start(
+        &mut self,
+        line: &str,
+        blank: bool,
+        indentation: usize,
+        content: &str,
+    ) -> String {
+        if blank {
+            self.interruptible = true;
+            return String::from(line);
+        }
+
+        if indentation >= INDENTED_CODE_WIDTH {
+            if self.interruptible && !self.in_list {
+                self.state = ProseFilterState::IndentedCode;
+                return mask(line);
+            }
+            self.interruptible = false;
+            return self.filter_inline(line);
+        }
+
+        if let Some((fence, length)) = opens_fence(content) {
+            self.state = ProseFilterState::FencedCode { fence, length };
+            return mask(line);
+        }
+
+        if let Some(state) = opens_html_block(content) {
+            let filtered = match state {
+                ProseFilterState::HtmlComment => self.filter_inline(line),
+                ProseFilterState::HtmlRawBlock { ref tag }
+                    if line.to_ascii_lowercase().contains(&format!("</{tag}>")) =>
+                {
+                    mask(line)
+                }
+                state => {
+                    self.state = state;
+                    mask(line)
+                }
+            };
+            self.interruptible = true;
+            return filtered;
+        }
+
+        self.in_list = starts_list_item(content) || (self.in_list && !self.interruptible);
+        self.interruptible = content.starts_with('#');
+        self.filter_inline(line)
+    }
+
+    fn filter_inline(&mut self, line: &str) -> String {
+        let chars: Vec<char> = line.chars().collect();
+        let mut filtered = String::with_capacity(line.len());
+        let mut i = 0;
+        while i < chars.len() {
+            match chars[i] {
+                '\\' if i + 1 < chars.len() => {
+                    filtered.push(chars[i]);
+                    filtered.push(chars[i + 1]);
+                    i += 2;
+                }
+                '`' => {
+                    let length = run_length(&chars, i, '`');
+                    match closing_backticks(&chars, i + length, length) {
+                        Some(end) => {
+                            (i..end).for_each(|_| filtered.push(MASK));
+                            i = end;
+                        }
+                        None => {
+                            (i..i + length).for_each(|j| filtered.push(chars[j]));
+                            i += length;
+                        }
+                    }
+                }
+                '<' if starts_with(&chars, i, HTML_COMMENT_START) => {
+                    match find(&chars, i + HTML_COMMENT_START.len(), HTML_COMMENT_END) {
+                        Some(end) => {
+                            let end = end + HTML_COMMENT_END.len();
+                            (i..end).for_each(|_| filtered.push(MASK));
+                            i = end;
+                        }
+                        None => {
+                            (i..chars.len()).for_each(|_| filtered.push(MASK));
+                            self.state = ProseFilterState::HtmlComment;
+                            i = chars.len();
+                        }
+                    }
+                }
+                c => {
+                    filtered.push(c);
+                    i += 1;
+                }
+            }
+        }
+        filtered
+    }
+}
+
+impl Default for ProseFilter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn mask(line: &str) -> String {
+    line.chars().map(|_| MASK).collect()
+}
+
+fn indentation(line: &str) -> (usize, &str) {
+    let mut width = 0;
+    for (i, c) in line.char_indices() {
+        match c {
+            ' ' => width += 1,
+            '\t' => width += INDENTED_CODE_WIDTH - width % INDENTED_CODE_WIDTH,
+            _ => return (width, &line[i..]),
+        }
+    }
+    (width, "")
+}
+
+fn opens_fence(content: &str) -> Option<(char, usize)> {
+    let fence = content.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let length = content.chars().take_while(|c| *c == fence).count();
+    if length < 3 {
+        return None;
+    }
+    let info = &content[length..];
+    if fence == '`' && info.contains('`') {
+        return None;
+    }
+    Some((fence, length))
+}
+
+fn closes_fence(content: &str, fence: char, length: usize) -> bool {
+    let closing = content.chars().take_while(|c| *c == fence).count();
+    closing >= length && content[closing..].trim().is_empty()
+}
+
+fn opens_html_block(content: &str) -> Option<ProseFilterState> {
+    if content.starts_with(HTML_COMMENT_START) {
+        return Some(ProseFilterState::HtmlComment);
+    }
+    let tag_start = content.strip_prefix('<')?;
+    let tag_start = tag_start.strip_prefix('/').unwrap_or(tag_start);
+    let tag: String = tag_start
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_ascii_lowercase();
+    let after_tag = tag_start[tag.len()..].chars().next();
+    if !matches!(after_tag, None | Some(' ' | '\t' | '>' | '/')) {
+        return None;
+    }
+    if HTML_RAW_TAGS.contains(&tag.as_str()) {
+        return Some(ProseFilterState::HtmlRawBlock { tag });
+    }
+    if HTML_BLOCK_TAGS.contains(&tag.as_str()) {
+        return Some(ProseFilterState::HtmlBlock);
+    }
+    None
+}
+
+fn starts_list_item(content: &str) -> bool {
+    let marker_end = match content.chars().next() {
+        Some('-' | '*' | '+') => 1,
+        Some(c) if c.is_ascii_digit() => {
+            let digits = content.chars().take_while(char::is_ascii_digit).count();
+            match content[digits..].chars().next() {
+                Some('.' | ')') => digits + 1,
+                _ => return false,
+            }
+        }
+        _ => return false,
+    };
+    matches!(
+        content[marker_end..].chars().next(),
+        None | Some(' ' | '\t')
+    )
+}
+
+fn run_length(chars: &[char], start: usize, c: char) -> usize {
+    chars[start..]
+        .iter()
+        .take_while(|other| **other == c)
+        .count()
+}
+
+fn closing_backticks(chars: &[char], start: usize, length: usize) -> Option<usize> {
+    let mut i = start;
+    while i < chars.len() {
+        if chars[i] == '`' {
+            let run = run_length(chars, i, '`');
+            if run == length {
+                return Some(i + run);
+            }
+            i += run;
+        } else {
+            i += 1;
+        }
+    }
+    None
+}
+
+fn starts_with(chars: &[char], start: usize, pattern: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    chars.len() >= start + pattern.len() && chars[start..start + pattern.len()] == pattern[..]
+}
+
+fn find(chars: &[char], start: usize, pattern: &str) -> Option<usize> {
+    (start..chars.len()).find(|i| starts_with(chars, *i, pattern))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(text: &str) -> Vec<String> {
+        let mut filter = ProseFilter::new();
+        text.lines().map(|line| filter.filter(line)).collect()
+    }
+
+    #[test]
+    fn test_prose_filter_new() {
+        assert_eq!(ProseFilter::new().state, ProseFilterState::Start);
+    }
+
+    #[test]
+    fn test_prose_filter_default() {
+        assert!(ProseFilter::default().interruptible);
+    }
+
+    #[test]
+    fn test_prose_filter_keeps_prose() {
+        assert_eq!(
+            filter("# Title\n\nSome [[Link]] text"),
+            vec!["# Title", "", "Some [[Link]] text"]
+        );
+    }
+
+    #[test]
+    fn test_prose_filter_front_matter() {
+        assert_eq!(
+            filter("---\n# comment\n---\n# Title\n---\n[[a]]"),
+            vec!["   ", "         ", "   ", "# Title", "---", "[[a]]"]
+        );
+    }
+
+    #[test]
+    fn test_prose_filter_fenced_code() {
+        assert_eq!(
+            filter("```sh\n# comment\n[[x]]\n```\nafter\n~~~~\n~~~\n~~~~"),
+            vec![
+                "     ",
+                "         ",
+                "     ",
+                "   ",
+                "after",
+                "    ",
+                "   ",
+                "    "
+            ]
+        );
+    }
+
+    #[test]
+    fn test_prose_filter_unclosed_fence_masks_the_rest() {
+        assert_eq!(filter("```\n# a\n[[b]]"), vec!["   ", "   ", "     "]);
+    }
+
+    #[test]
+    fn test_prose_filter_indented_code() {
+        assert_eq!(
+            filter("para\n    continued [[a]]\n\n    # code\n\n\tcode\nafter"),
+            vec![
+                "para",
+                "    continued [[a]]",
+                "",
+                "          ",
+                "",
+                "     ",
+                "after"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_prose_filter_indented_list_continuation() {
+        assert_eq!(
+            filter("- item\n\n    more [[a]]"),
+            vec!["- item", "", "    more [[a]]"]
+        );
+    }
+
+    #[test]
+    fn test_prose_filter_inline_code() {
+        assert_eq!(
+            filter("a `[[x]]` b ``c ` d`` [[y]] \\`[[z]]`"),
+            vec!["a         b           [[y]] \\`[[z]]`"]
+        );
+    }
+
+    #[test]
+    fn test_prose_filter_unmatched_backticks() {
+        assert_eq!(filter("a ``b` [[y]]"), vec!["a ``b` [[y]]"]);
+    }
+
+    #[test]
+    fn test_prose_filter_html_comments() {
+        assert_eq!(
+            filter("a <!-- [[x]] --> b\n<!--\n# hidden\n--> [[y]]"),
+            vec!["a                b", "    ", "        ", "    [[y]]"]
+        );
+    }
+
+    #[test]
+    fn test_prose_filter_html_blocks() {
+        assert_eq!(
+            filter("<div>\n# x\n\n# y\n<script>\n\n[[z]]\n</script>\n[[w]]"),
+            vec![
+                "     ",
+                "   ",
+                "",
+                "# y",
+                "        ",
+                "",
+                "     ",
+                "         ",
+                "[[w]]"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_prose_filter_inline_html_is_prose() {
+        assert_eq!(filter("<span>[[a]]</span>"), vec!["<span>[[a]]</span>"]);
+    }
+}
@@ -1,12 +1,30 @@
-use crate::models::Location;
-use crate::parsers::Parser;
+use crate::models::{MalformedReferenceKind, ReferenceKind};
+use crate::parsers::{CharSpan, LineParser, Parser};
 
+/// Parses `[[Note#Section^block|alias]]` wiki links and `![[Note]]` embeds.
+///
+/// - A link opens with an unescaped `[[`. In a longer run such as `[[[Note]]]`, it opens at the
+///   last two brackets.
+/// - It closes at the first unescaped `]]` on the same line, so a single `]` is part of its text.
+///   An unescaped `[[` inside an open link starts a new link instead.
+/// - A backslash escapes the next character, which then loses its special meaning. `\|` still
+///   separates the alias, as it must be escaped inside Markdown tables.
+/// - The text splits at the first `|` into target and alias, the target at the first `^` into
+///   heading and block, and the heading at the first `#` into header and section.
+///
+/// Links left open at the end of their line and links without a target are reported as
+/// malformed rather than dropped.
 pub struct ReferenceParser {
     prefix: Option<Option<()>>,
     reference: Option<ReferenceParserPayload>,
     references: Vec<ReferenceParserPayload>,
+    malformed: Vec<MalformedReferenceParserPayload>,
     suffix: Option<Option<()>>,
-    location: Location,
+    kind: ReferenceKind,
+    previous: Option<char>,
+    previous_opening: bool,
+    escaped: bool,
+    location: CharSpan,
     done: bool,
 }
 
@@ -16,14 +34,127 @@ impl ReferenceParser {
             prefix: None,
             reference: None,
             references: vec![],
+            malformed: vec![],
             suffix: None,
-            location: Location::default(),
+            kind: ReferenceKind::Link,
+            previous: None,
+            previous_opening: false,
+            escaped: false,
+            location: CharSpan::default(),
             done: false,
         }
     }
 
     pub fn call(self) -> Vec<ReferenceParserPayload> {
-        self.references
+        self.call_with_malformed().0
+    }
+
+    /// Returns the parsed references along with the malformed ones.
+    pub fn call_with_malformed(
+        mut self,
+    ) -> (
+        Vec<ReferenceParserPayload>,
+        Vec<MalformedReferenceParserPayload>,
+    ) {
+        if self.prefix == Some(Some(())) {
+            self.unclosed_at_line_end();
+        }
+        (self.references, self.malformed)
+    }
+
+    fn capture(&mut self, c: char, escaped: bool, previous_opening: bool) {
+        let opening = c == '[' && !escaped;
+        let closing = c == ']' && !escaped;
+        if self.suffix.is_some() {
+            if closing {
+                self.done = true;
+                return;
+            }
+            // A single `]` is part of the text.
+            self.suffix = None;
+            if let Some(payload) = &mut self.reference {
+                payload.push(']');
+            }
+        }
+
+        match &mut self.reference {
+            None => match c {
+                '[' if opening && previous_opening => {
+                    self.location.start_char_position += 1;
+                    self.kind = ReferenceKind::Link;
+                }
+                ' ' => {}
+                c => {
+                    let mut payload =
+                        ReferenceParserPayload::new(self.location.clone(), self.kind.clone());
+                    match closing {
+                        true => self.suffix = Some(None),
+                        false => payload.push(c),
+                    }
+                    self.reference = Some(payload);
+                }
+            },
+            Some(_) if opening && previous_opening => self.restart(),
+            Some(_) if closing => self.suffix = Some(None),
+            Some(payload) => payload.push(c),
+        }
+    }
+
+    /// Reports the open link as unclosed and opens a new one at the `[[` just read.
+    fn restart(&mut self) {
+        let column = self.location.end_char_position;
+        let mut text = self.reference.take().map(|p| p.header).unwrap_or_default();
+        // The first `[` of the new link, and its `!` for an embed, end the unclosed one.
+        text.pop();
+        let embed = text.ends_with('!');
+        if embed {
+            text.pop();
+        }
+        self.unclosed(column - 2 - embed as i64 - trailing_whitespace(&text));
+        self.kind = match embed {
+            true => ReferenceKind::Embed,
+            false => ReferenceKind::Link,
+        };
+        self.location.start_char_position = column - 1;
+        self.suffix = None;
+    }
+
+    fn unclosed_at_line_end(&mut self) {
+        let trailing = match &self.reference {
+            Some(payload) => trailing_whitespace(&payload.header),
+            None => 0,
+        };
+        self.unclosed(self.location.end_char_position - trailing);
+    }
+
+    fn unclosed(&mut self, end_char_position: i64) {
+        let mut location = self.location.clone();
+        location.in_range = false;
+        location.end_char_position = end_char_position;
+        if self.kind == ReferenceKind::Embed {
+            location.start_char_position -= 1;
+        }
+        self.malformed.push(MalformedReferenceParserPayload {
+            kind: MalformedReferenceKind::Unclosed,
+            location,
+        });
+    }
+
+    fn finish(&mut self) {
+        let mut payload = self.reference.take().unwrap();
+        payload.location = self.location.clone();
+        let payload = payload.split();
+        match payload.header.is_empty() && payload.section.is_none() && payload.block.is_none() {
+            true => self.malformed.push(MalformedReferenceParserPayload {
+                kind: MalformedReferenceKind::Empty,
+                location: payload.location,
+            }),
+            false => self.references.push(payload),
+        }
+        self.prefix = None;
+        self.suffix = None;
+        self.location.resume();
+        self.done = false;
     }
 }
 
@@ -33,86 +164,211 @@ impl Default for ReferenceParser {
     }
 }
 
+impl LineParser for ReferenceParser {
+    type Payload = (
+        Vec<ReferenceParserPayload>,
+        Vec<MalformedReferenceParserPayload>,
+    );
+
+    fn into_payload(self) -> Self::Payload {
+        self.call_with_malformed()
+    }
+}
+
 impl Parser for ReferenceParser {
     fn next(&mut self, c: char) {
-        self.location.next(c);
-        if c == '[' {
-            self.location.in_range();
+        let previous = self.previous.replace(c);
+        let escaped = self.escaped;
+        self.escaped = c == '\\' && !escaped;
+        let previous_opening = std::mem::replace(&mut self.previous_opening, c == '[' && !escaped);
+        if c == '\n' {
+            if self.prefix == Some(Some(())) {
+                self.unclosed_at_line_end();
+            }
+            self.prefix = None;
+            self.suffix = None;
+            self.reference = None;
+            self.location.next(c);
+            return;
         }
+        self.location.next(c);
 
         match self.prefix {
             None => match c {
-                '[' => self.prefix = Some(None),
+                '[' if !escaped => {
+                    self.location.in_range();
+                    self.kind = match previous {
+                        Some('!') => ReferenceKind::Embed,
+                        _ => ReferenceKind::Link,
+                    };
+                    self.prefix = Some(None)
+                }
+                _ => self.location.resume(),
+            },
+            Some(None) => match c {
+                '[' if !escaped => self.prefix = Some(Some(())),
                 _ => {
                     self.location.resume();
-                    return;
+                    self.prefix = None;
                 }
             },
-            Some(prefix) => match prefix {
-                None => match c {
-                    '[' => self.prefix = Some(Some(())),
-                    _ => {
-                        self.location.resume();
-                        self.prefix = None;
-                    }
-                },
-                Some(_) => match &mut self.reference {
-                    None => match c {
-                        ' ' => return,
-                        '\n' => self.prefix = None,
-                        c => {
-                            self.reference =
-                                Some(ReferenceParserPayload::from(c, self.location.clone()))
-                        }
-                    },
-                    Some(payload) => match c {
-                        '\n' => {
-                            self.prefix = None;
-                            self.suffix = None;
-                            self.reference = None;
-                        }
-                        ']' => {
-                            payload.location.next(c);
-                            match self.suffix {
-                                None => self.suffix = Some(None),
-                                Some(_) => self.done = true,
-                            }
-                        }
-                        c => {
-                            payload.push(c);
-                            payload.location.next(c);
-                        }
-                    },
-                },
-            },
+            Some(Some(_)) => self.capture(c, escaped, previous_opening),
         }
         if self.done {
-            self.prefix = None;
-            self.suffix = None;
-            self.references.push(self.reference.take().unwrap());
-            self.location.resume();
-            self.done = false
+            self.finish();
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ReferenceParserPayload {
     pub header: String,
-    pub location: Location,
+    pub header_location: Option<CharSpan>,
+    pub section: Option<String>,
+    pub block: Option<String>,
+    pub alias: Option<String>,
+    pub alias_location: Option<CharSpan>,
+    pub kind: ReferenceKind,
+    pub location: CharSpan,
+    text_start: i64,
 }
 
 impl ReferenceParserPayload {
-    fn from(c: char, location: Location) -> Self {
+    fn new(location: CharSpan, kind: ReferenceKind) -> Self {
         Self {
-            header: String::from(c),
+            header: String::new(),
+            header_location: None,
+            section: None,
+            block: None,
+            alias: None,
+            alias_location: None,
+            kind,
+            text_start: location.end_char_position,
             location,
         }
     }
 
+    /// A link to `header` written in another syntax than wiki links, such as Org-mode's.
+    pub fn link(header: String, header_location: Option<CharSpan>, location: CharSpan) -> Self {
+        Self {
+            header,
+            header_location,
+            ..Self::new(location, ReferenceKind::Link)
+        }
+    }
+
     fn push(&mut self, c: char) {
         self.header.push(c);
     }
+
+    fn split(mut self) -> Self {
+        if self.kind == ReferenceKind::Embed {
+            // Embeds span their leading `!` as well.
+            self.location.start_char_position -= 1;
+        }
+        let line_position = self.location.line_position;
+        let text = Span::new(self.text_start, &self.header);
+        let (target, alias) = text.split('|');
+        let (target, block) = target.split('^');
+        let (header, section) = target.split('#');
+
+        let (header, header_location) = match header.trim(line_position) {
+            Some((header, location)) => (header, Some(location)),
+            None => (String::new(), None),
+        };
+        let (alias, alias_location) = match alias.and_then(|alias| alias.trim(line_position)) {
+            Some((alias, location)) => (Some(alias), Some(location)),
+            None => (None, None),
+        };
+        Self {
+            header,
+            header_location,
+            section: section.and_then(|s| s.trim(line_position)).map(|(s, _)| s),
+            block: block.and_then(|b| b.trim(line_position)).map(|(b, _)| b),
+            alias,
+            alias_location,
+            kind: self.kind,
+            location: self.location,
+            text_start: self.text_start,
+        }
+    }
+}
+
+/// A wiki link that couldn't be parsed, spanning from its `[[` to where parsing gave up.
+#[derive(Debug, Clone)]
+pub struct MalformedReferenceParserPayload {
+    pub kind: MalformedReferenceKind,
+    pub location: CharSpan,
+}
+
+/// A slice of a reference's text along with the column of its first character.
+#[derive(Clone, Copy)]
+struct Span<'a> {
+    start: i64,
+    text: &'a str,
+}
+
+impl<'a> Span<'a> {
+    fn new(start: i64, text: &'a str) -> Self {
+        Self { start, text }
+    }
+
+    /// Splits at the first unescaped `delimiter`, or at an escaped `|` as in Markdown tables.
+    fn split(self, delimiter: char) -> (Self, Option<Self>) {
+        let mut escaped = false;
+        for (i, c) in self.text.char_indices() {
+            if c == delimiter && (!escaped || delimiter == '|') {
+                let before = &self.text[..i];
+                let offset = before.chars().count() as i64 + 1;
+                let before = match escaped {
+                    true => &before[..before.len() - 1],
+                    false => before,
+                };
+                return (
+                    Self::new(self.start, before),
+                    Some(Self::new(self.start + offset, &self.text[i + 1..])),
+                );
+            }
+            escaped = c == '\\' && !escaped;
+        }
+        (self, None)
+    }
+
+    fn trim(self, line_position: u32) -> Option<(String, CharSpan)> {
+        let trimmed = self.text.trim();
+        if trimmed.is_empty() {
+            return None;
+        }
+        let leading = (self.text.chars().count() - self.text.trim_start().chars().count()) as i64;
+        let start_char_position = self.start + leading;
+        let location = CharSpan {
+            in_range: false,
+            line_position,
+            start_char_position,
+            end_char_position: start_char_position + trimmed.chars().count() as i64 - 1,
+        };
+        Some((unescape(trimmed), location))
+    }
+}
+
+fn trailing_whitespace(text: &str) -> i64 {
+    (text.chars().count() - text.trim_end().chars().count()) as i64
+}
+
+/// Drops the backslashes escaping punctuation, as in `[[Note \] 2]]`.
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match chars.peek() {
+            Some(next) if c == '\\' && next.is_ascii_punctuation() => {
+                unescaped.push(*next);
+                chars.next();
+            }
+            _ => unescaped.push(c),
+        }
+    }
+    unescaped
 }
 
 #[cfg(test)]
@@ -310,6 +566,182 @@ mod tests {
         assert!(parser.suffix.is_none());
     }
 
+    #[test]
+    fn test_reference_parser_section() {
+        let mut parser = ReferenceParser::new();
+        "[[Note#Section]] [[Other]] [[#Local]] [[Empty#]]"
+            .chars()
+            .for_each(|c| parser.next(c));
+        let references = parser.call();
+        assert_eq!(references.len(), 4);
+        assert_eq!(references[0].header, "Note");
+        assert_eq!(references[0].section, Some(String::from("Section")));
+        assert_eq!(references[1].header, "Other");
+        assert_eq!(references[1].section, None);
+        assert_eq!(references[2].header, "");
+        assert_eq!(references[2].section, Some(String::from("Local")));
+        assert_eq!(references[3].header, "Empty");
+        assert_eq!(references[3].section, None);
+    }
+
+    #[test]
+    fn test_reference_parser_block() {
+        let mut parser = ReferenceParser::new();
+        "[[Note^block-id]] [[^local]] [[Note#^heading-style]]"
+            .chars()
+            .for_each(|c| parser.next(c));
+        let references = parser.call();
+        assert_eq!(references.len(), 3);
+        assert_eq!(references[0].header, "Note");
+        assert_eq!(references[0].section, None);
+        assert_eq!(references[0].block, Some(String::from("block-id")));
+        assert_eq!(references[1].header, "");
+        assert_eq!(references[1].block, Some(String::from("local")));
+        assert_eq!(references[2].header, "Note");
+        assert_eq!(references[2].section, None);
+        assert_eq!(references[2].block, Some(String::from("heading-style")));
+    }
+
+    #[test]
+    fn test_reference_parser_alias() {
+        let mut parser = ReferenceParser::new();
+        "- [[ Note#Section | shown text ]] [[Plain]]"
+            .chars()
+            .for_each(|c| parser.next(c));
+        let references = parser.call();
+        assert_eq!(references.len(), 2);
+
+        let reference = &references[0];
+        assert_eq!(reference.header, "Note");
+        assert_eq!(reference.section, Some(String::from("Section")));
+        assert_eq!(reference.alias, Some(String::from("shown text")));
+        let header_location = reference.header_location.as_ref().unwrap();
+        assert_eq!(header_location.start_char_position, 5);
+        assert_eq!(header_location.end_char_position, 8);
+        let alias_location = reference.alias_location.as_ref().unwrap();
+        assert_eq!(alias_location.start_char_position, 20);
+        assert_eq!(alias_location.end_char_position, 29);
+        assert_eq!(reference.location.start_char_position, 2);
+        assert_eq!(reference.location.end_char_position, 32);
+
+        assert_eq!(references[1].alias, None);
+        assert_eq!(references[1].alias_location, None);
+    }
+
+    #[test]
+    fn test_reference_parser_implicit_header_location() {
+        let mut parser = ReferenceParser::new();
+        "[[#Section|alias]]".chars().for_each(|c| parser.next(c));
+        let references = parser.call();
+        assert_eq!(references[0].header, "");
+        assert!(references[0].header_location.is_none());
+        assert_eq!(references[0].alias, Some(String::from("alias")));
+    }
+
+    #[test]
+    fn test_reference_parser_embed() {
+        let mut parser = ReferenceParser::new();
+        "![[Note#Section]] [[Link]] !not [[Other]]"
+            .chars()
+            .for_each(|c| parser.next(c));
+        let references = parser.call();
+        assert_eq!(references.len(), 3);
+        assert_eq!(references[0].kind, ReferenceKind::Embed);
+        assert_eq!(references[0].header, "Note");
+        assert_eq!(references[0].section, Some(String::from("Section")));
+        assert_eq!(references[0].location.start_char_position, 0);
+        assert_eq!(references[0].location.end_char_position, 16);
+        assert_eq!(references[1].kind, ReferenceKind::Link);
+        assert_eq!(references[1].location.start_char_position, 18);
+        assert_eq!(references[2].kind, ReferenceKind::Link);
+    }
+
+    fn parse(
+        text: &str,
+    ) -> (
+        Vec<ReferenceParserPayload>,
+        Vec<MalformedReferenceParserPayload>,
+    ) {
+        let mut parser = ReferenceParser::new();
+        text.chars().for_each(|c| parser.next(c));
+        parser.call_with_malformed()
+    }
+
+    #[test]
+    fn test_reference_parser_escapes() {
+        let (references, malformed) =
+            parse("\\[[not a link]] [[Note \\]] 2\\#3]] [[Table\\|alias]] [[A\\^b#c]]\n");
+        assert!(malformed.is_empty());
+        assert_eq!(references.len(), 3);
+        assert_eq!(references[0].header, "Note ]] 2#3");
+        assert_eq!(references[0].section, None);
+        let header_location = references[0].header_location.as_ref().unwrap();
+        assert_eq!(header_location.start_char_position, 18);
+        assert_eq!(header_location.end_char_position, 30);
+        assert_eq!(references[1].header, "Table");
+        assert_eq!(references[1].alias, Some(String::from("alias")));
+        assert_eq!(
+            references[1]
+                .alias_location
+                .as_ref()
+                .unwrap()
+                .start_char_position,
+            43
+        );
+        assert_eq!(references[2].header, "A^b");
+        assert_eq!(references[2].section, Some(String::from("c")));
+    }
+
+    #[test]
+    fn test_reference_parser_nested_brackets() {
+        let (references, malformed) = parse("[[[Note]]] [[a]b]] [[x [y] z]]\n");
+        assert!(malformed.is_empty());
+        assert_eq!(references.len(), 3);
+        assert_eq!(references[0].header, "Note");
+        assert_eq!(references[0].location.start_char_position, 1);
+        assert_eq!(references[0].location.end_char_position, 8);
+        assert_eq!(references[1].header, "a]b");
+        assert_eq!(references[1].location.start_char_position, 11);
+        assert_eq!(references[1].location.end_char_position, 17);
+        assert_eq!(references[2].header, "x [y] z");
+    }
+
+    #[test]
+    fn test_reference_parser_unclosed() {
+        let (references, malformed) = parse("a [[Open  \n![[Outer [[Inner]]\n[[Last");
+        assert_eq!(references.len(), 1);
+        assert_eq!(references[0].header, "Inner");
+        assert_eq!(references[0].kind, ReferenceKind::Link);
+        assert_eq!(references[0].location.line_position, 1);
+        assert_eq!(references[0].location.start_char_position, 9);
+        assert_eq!(malformed.len(), 3);
+        assert!(malformed
+            .iter()
+            .all(|malformed| malformed.kind == MalformedReferenceKind::Unclosed));
+        assert_eq!(malformed[0].location.line_position, 0);
+        assert_eq!(malformed[0].location.start_char_position, 2);
+        assert_eq!(malformed[0].location.end_char_position, 7);
+        assert_eq!(malformed[1].location.line_position, 1);
+        assert_eq!(malformed[1].location.start_char_position, 0);
+        assert_eq!(malformed[1].location.end_char_position, 7);
+        assert_eq!(malformed[2].location.line_position, 2);
+        assert_eq!(malformed[2].location.end_char_position, 5);
+    }
+
+    #[test]
+    fn test_reference_parser_empty() {
+        let (references, malformed) = parse("[[]] [[ ]] [[|alias]] [[#Section]]\n");
+        assert_eq!(references.len(), 1);
+        assert_eq!(malformed.len(), 3);
+        assert!(malformed
+            .iter()
+            .all(|malformed| malformed.kind == MalformedReferenceKind::Empty));
+        assert_eq!(malformed[0].location.start_char_position, 0);
+        assert_eq!(malformed[0].location.end_char_position, 3);
+        assert_eq!(malformed[2].location.start_char_position, 11);
+        assert_eq!(malformed[2].location.end_char_position, 20);
+    }
+
     #[test]
     fn test_reference_parser_call_in_non_final_state() {
         let mut parser = ReferenceParser::new();
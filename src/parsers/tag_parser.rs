@@ -0,0 +1,168 @@
+use crate::parsers::{CharSpan, LineParser, Parser};
+
+pub struct TagParser {
+    tag: Option<TagParserPayload>,
+    tags: Vec<TagParserPayload>,
+    previous: Option<char>,
+    location: CharSpan,
+}
+
+impl TagParser {
+    pub fn new() -> Self {
+        Self {
+            tag: None,
+            tags: vec![],
+            previous: None,
+            location: CharSpan::default(),
+        }
+    }
+
+    pub fn call(mut self) -> Vec<TagParserPayload> {
+        self.finish_tag();
+        self.tags
+    }
+
+    fn finish_tag(&mut self) {
+        if let Some(tag) = self.tag.take().map(TagParserPayload::trim) {
+            // Like `#1`, tags made only of digits are more likely to be issue numbers.
+            if tag.name.chars().any(|c| !c.is_ascii_digit() && c != '/') {
+                self.tags.push(tag);
+            }
+        }
+        self.location.resume();
+    }
+}
+
+impl Default for TagParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LineParser for TagParser {
+    type Payload = Vec<TagParserPayload>;
+
+    fn into_payload(self) -> Self::Payload {
+        self.call()
+    }
+}
+
+impl Parser for TagParser {
+    fn next(&mut self, c: char) {
+        self.location.next(c);
+        let previous = self.previous.replace(c);
+        if c == '\n' {
+            self.finish_tag();
+            self.previous = None;
+            return;
+        }
+
+        match &mut self.tag {
+            Some(tag) if is_tag_char(c) => tag.push(c),
+            Some(_) => self.finish_tag(),
+            None => {
+                if c == '#' && previous.map(char::is_whitespace).unwrap_or(true) {
+                    self.location.in_range();
+                    self.tag = Some(TagParserPayload::from(self.location.clone()));
+                }
+            }
+        }
+    }
+}
+
+pub fn is_tag_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | '/')
+}
+
+/// Normalizes a tag written in front matter, where the leading `#` is optional.
+pub fn normalize_tag(tag: &str) -> Option<String> {
+    let tag = tag.trim();
+    let tag = tag.strip_prefix('#').unwrap_or(tag).trim_end_matches('/');
+    match !tag.is_empty() && tag.chars().all(is_tag_char) {
+        true => Some(String::from(tag)),
+        false => None,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TagParserPayload {
+    pub name: String,
+    pub location: CharSpan,
+}
+
+impl TagParserPayload {
+    fn from(location: CharSpan) -> Self {
+        Self {
+            name: String::new(),
+            location,
+        }
+    }
+
+    fn push(&mut self, c: char) {
+        self.name.push(c);
+        self.location.next(c);
+    }
+
+    fn trim(mut self) -> Self {
+        let name = self.name.trim_end_matches('/').to_string();
+        self.location.end_char_position -=
+            (self.name.chars().count() - name.chars().count()) as i64;
+        Self {
+            name,
+            location: self.location,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Vec<TagParserPayload> {
+        let mut parser = TagParser::new();
+        text.chars().for_each(|c| parser.next(c));
+        parser.call()
+    }
+
+    #[test]
+    fn test_tag_parser_new() {
+        assert!(TagParser::new().tags.is_empty());
+    }
+
+    #[test]
+    fn test_tag_parser_default() {
+        assert!(TagParser::default().tag.is_none());
+    }
+
+    #[test]
+    fn test_tag_parser_tags() {
+        let tags = parse("#inbox and #project/grimoire-lsp, done\n  #last/");
+        assert_eq!(tags.len(), 3);
+        assert_eq!(tags[0].name, "inbox");
+        assert_eq!(tags[0].location.start_char_position, 0);
+        assert_eq!(tags[0].location.end_char_position, 5);
+        assert_eq!(tags[1].name, "project/grimoire-lsp");
+        assert_eq!(tags[1].location.start_char_position, 11);
+        assert_eq!(tags[1].location.end_char_position, 31);
+        assert_eq!(tags[2].name, "last");
+        assert_eq!(tags[2].location.line_position, 1);
+        assert_eq!(tags[2].location.start_char_position, 2);
+        assert_eq!(tags[2].location.end_char_position, 6);
+    }
+
+    #[test]
+    fn test_tag_parser_ignores_headings_and_anchors() {
+        assert!(parse("# Heading\n## Section\n[[Note#Section]]\nissue #12\na#b\n").is_empty());
+    }
+
+    #[test]
+    fn test_normalize_tag() {
+        assert_eq!(
+            normalize_tag(" #nested/tag/ "),
+            Some(String::from("nested/tag"))
+        );
+        assert_eq!(normalize_tag("plain"), Some(String::from("plain")));
+        assert_eq!(normalize_tag("not a tag"), None);
+        assert_eq!(normalize_tag("#"), None);
+    }
+}
@@ -0,0 +1,234 @@
+use crate::parsers::{CharSpan, LineParser, Parser};
+
+/// Parses `- [ ]` and `- [x]` list items, along with their `due:YYYY-MM-DD` and `@person`
+/// annotations.
+pub struct TaskParser {
+    line: String,
+    line_position: u32,
+    tasks: Vec<TaskParserPayload>,
+}
+
+impl TaskParser {
+    pub fn new() -> Self {
+        Self {
+            line: String::new(),
+            line_position: 0,
+            tasks: vec![],
+        }
+    }
+
+    pub fn call(mut self) -> Vec<TaskParserPayload> {
+        self.finish_line();
+        self.tasks
+    }
+
+    fn finish_line(&mut self) {
+        let line = std::mem::take(&mut self.line);
+        if let Some(task) = parse_task_line(&line, self.line_position) {
+            self.tasks.push(task);
+        }
+        self.line_position += 1;
+    }
+}
+
+impl Default for TaskParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LineParser for TaskParser {
+    type Payload = Vec<TaskParserPayload>;
+
+    fn into_payload(self) -> Self::Payload {
+        self.call()
+    }
+}
+
+impl Parser for TaskParser {
+    fn next(&mut self, c: char) {
+        match c {
+            '\n' => self.finish_line(),
+            c => self.line.push(c),
+        }
+    }
+}
+
+/// Reads the task written on `line`, if it is a list item starting with a checkbox.
+pub fn parse_task_line(line: &str, line_position: u32) -> Option<TaskParserPayload> {
+    let chars: Vec<char> = line.chars().collect();
+    let start = chars.iter().take_while(|c| c.is_whitespace()).count();
+    let marker_end = list_marker_end(&chars, start)?;
+    let spaces = chars[marker_end..]
+        .iter()
+        .take_while(|c| **c == ' ' || **c == '\t')
+        .count();
+    let open = marker_end + spaces;
+    if spaces == 0 || chars.get(open) != Some(&'[') || chars.get(open + 2) != Some(&']') {
+        return None;
+    }
+    let done = match chars.get(open + 1)? {
+        ' ' => false,
+        'x' | 'X' => true,
+        _ => return None,
+    };
+    if !matches!(chars.get(open + 3), None | Some(' ' | '\t')) {
+        return None;
+    }
+
+    let text: String = chars[open + 3..].iter().collect();
+    let text = text.trim();
+    let end = start + line.trim().chars().count() - 1;
+    Some(TaskParserPayload {
+        text: String::from(text),
+        done,
+        due: text
+            .split_whitespace()
+            .filter_map(|word| word.strip_prefix("due:"))
+            .find(|date| is_date(date))
+            .map(String::from),
+        people: text
+            .split_whitespace()
+            .filter_map(|word| word.strip_prefix('@'))
+            .map(|name| name.trim_end_matches(|c: char| !c.is_alphanumeric()))
+            .filter(|name| !name.is_empty())
+            .map(String::from)
+            .collect(),
+        checkbox_location: location(line_position, open + 1, open + 1),
+        location: location(line_position, start, end),
+    })
+}
+
+/// Whether `date` is written as `YYYY-MM-DD` and names a day of the calendar, leap days included.
+pub fn is_date(date: &str) -> bool {
+    let parts: Vec<&str> = date.split('-').collect();
+    let number = |part: &str, length: usize| match part.len() == length
+        && part.chars().all(|c| c.is_ascii_digit())
+    {
+        true => part.parse::<u32>().ok(),
+        false => None,
+    };
+    let (year, month, day) = match parts[..] {
+        [year, month, day] => match (number(year, 4), number(month, 2), number(day, 2)) {
+            (Some(year), Some(month), Some(day)) => (year, month, day),
+            _ => return false,
+        },
+        _ => return false,
+    };
+    let leap_year = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days_in_month = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap_year => 29,
+        2 => 28,
+        _ => return false,
+    };
+    (1..=days_in_month).contains(&day)
+}
+
+/// Finds the end of a `-`, `*`, `+`, `1.` or `1)` list marker starting at `start`.
+fn list_marker_end(chars: &[char], start: usize) -> Option<usize> {
+    match chars.get(start)? {
+        '-' | '*' | '+' => Some(start + 1),
+        c if c.is_ascii_digit() => {
+            let digits = chars[start..]
+                .iter()
+                .take_while(|c| c.is_ascii_digit())
+                .count();
+            match chars.get(start + digits)? {
+                '.' | ')' if digits <= 9 => Some(start + digits + 1),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn location(line_position: u32, start: usize, end: usize) -> CharSpan {
+    CharSpan {
+        in_range: false,
+        line_position,
+        start_char_position: start as i64,
+        end_char_position: end as i64,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TaskParserPayload {
+    pub text: String,
+    pub done: bool,
+    pub due: Option<String>,
+    pub people: Vec<String>,
+    pub checkbox_location: CharSpan,
+    pub location: CharSpan,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Vec<TaskParserPayload> {
+        let mut parser = TaskParser::new();
+        text.chars().for_each(|c| parser.next(c));
+        parser.call()
+    }
+
+    #[test]
+    fn test_task_parser_new() {
+        assert!(TaskParser::new().tasks.is_empty());
+    }
+
+    #[test]
+    fn test_task_parser_default() {
+        assert_eq!(TaskParser::default().line_position, 0);
+    }
+
+    #[test]
+    fn test_task_parser_tasks() {
+        let tasks = parse(
+            "# Title\n\n- [ ] Write docs due:2024-05-01 @alice, @bob.\n  * [x] Done\n1. [X] Numbered\n",
+        );
+        assert_eq!(tasks.len(), 3);
+        assert_eq!(tasks[0].text, "Write docs due:2024-05-01 @alice, @bob.");
+        assert!(!tasks[0].done);
+        assert_eq!(tasks[0].due, Some(String::from("2024-05-01")));
+        assert_eq!(tasks[0].people, vec!["alice", "bob"]);
+        assert_eq!(tasks[0].checkbox_location.line_position, 2);
+        assert_eq!(tasks[0].checkbox_location.start_char_position, 3);
+        assert_eq!(tasks[0].location.start_char_position, 0);
+        assert_eq!(tasks[0].location.end_char_position, 44);
+        assert!(tasks[1].done);
+        assert_eq!(tasks[1].checkbox_location.start_char_position, 5);
+        assert_eq!(tasks[1].location.start_char_position, 2);
+        assert!(tasks[1].due.is_none());
+        assert!(tasks[2].done);
+        assert_eq!(tasks[2].text, "Numbered");
+    }
+
+    #[test]
+    fn test_task_parser_ignores_non_tasks() {
+        let tasks = parse("[ ] no marker\n- [] empty\n- [y] other\n-[ ] no space\n- [ ]x\n");
+        assert!(tasks.is_empty());
+    }
+
+    #[test]
+    fn test_task_parser_invalid_due_date() {
+        let tasks = parse("- [ ] Task due:2024-13-01 due:tomorrow\n");
+        assert!(tasks[0].due.is_none());
+    }
+
+    #[test]
+    fn test_is_date() {
+        assert!(is_date("2024-02-29"));
+        assert!(!is_date("2024-2-29"));
+        assert!(!is_date("2024-00-10"));
+        assert!(!is_date("2024-01-32"));
+        assert!(!is_date("2024-01-01-01"));
+        assert!(!is_date("2024-02-30"));
+        assert!(!is_date("2023-02-29"));
+        assert!(!is_date("1900-02-29"));
+        assert!(is_date("2000-02-29"));
+        assert!(!is_date("2024-04-31"));
+        assert!(is_date("2024-12-31"));
+    }
+}
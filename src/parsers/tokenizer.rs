@@ -0,0 +1,61 @@
+use unicode_normalization::UnicodeNormalization;
+
+/// A word of a note as the full-text index stores it, with the line it appears on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub term: String,
+    pub line: u32,
+}
+
+/// Splits a note into words, in order: runs of letters and digits, in Unicode NFC and
+/// lowercased so that searches ignore case and normalization.
+pub fn tokenize(text: &str) -> Vec<Token> {
+    text.lines()
+        .enumerate()
+        .flat_map(|(line, text)| {
+            terms(text).into_iter().map(move |term| Token {
+                term,
+                line: line as u32,
+            })
+        })
+        .collect()
+}
+
+/// The words of a single line or query, normalized as `tokenize` does.
+pub fn terms(text: &str) -> Vec<String> {
+    text.nfc()
+        .collect::<String>()
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|term| !term.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_terms() {
+        assert_eq!(
+            terms("# My [[Favorite]] re-cipes, 2024!"),
+            vec!["my", "favorite", "re", "cipes", "2024"]
+        );
+        assert_eq!(terms("Caf\u{65}\u{301} ÉCOLE"), vec!["caf\u{e9}", "école"]);
+        assert!(terms(" -- ").is_empty());
+    }
+
+    #[test]
+    fn test_tokenize() {
+        let tokens = tokenize("# Title\n\nsome words\n");
+        let expected: Vec<(&str, u32)> = vec![("title", 0), ("some", 2), ("words", 2)];
+        assert_eq!(
+            tokens
+                .iter()
+                .map(|token| (token.term.as_str(), token.line))
+                .collect::<Vec<_>>(),
+            expected
+        );
+    }
+}
@@ -1,31 +1,207 @@
-use crate::models::File;
+use crate::ctx::LinkMatching;
+use crate::ctx::{file_names, LinkResolver};
+use crate::models::{normalize_key, slug_key, File, Reference};
+use std::path::{Path, PathBuf};
 
+/// Stores notes by path, with indexes from the names links use to the path of their note.
 pub struct FilesRepository<'a> {
     table: &'a crate::ctx::DatabaseTable,
+    headers_table: &'a crate::ctx::DatabaseTable,
+    aliases_table: &'a crate::ctx::DatabaseTable,
+    // The normalized keys and slugs of headers and aliases, for links that aren't matched exactly.
+    keys_table: &'a crate::ctx::DatabaseTable,
+    slugs_table: &'a crate::ctx::DatabaseTable,
+    // The paths of notes keyed by the slugs of their file stem and file name, which end the
+    // path-qualified links naming them.
+    stems_table: &'a crate::ctx::DatabaseTable,
 }
 
 impl<'a> FilesRepository<'a> {
-    pub fn new(table: &'a crate::ctx::DatabaseTable) -> Self {
-        Self { table }
+    pub fn new(
+        table: &'a crate::ctx::DatabaseTable,
+        headers_table: &'a crate::ctx::DatabaseTable,
+        aliases_table: &'a crate::ctx::DatabaseTable,
+        keys_table: &'a crate::ctx::DatabaseTable,
+        slugs_table: &'a crate::ctx::DatabaseTable,
+        stems_table: &'a crate::ctx::DatabaseTable,
+    ) -> Self {
+        Self {
+            table,
+            headers_table,
+            aliases_table,
+            keys_table,
+            slugs_table,
+            stems_table,
+        }
     }
 
+    /// Notes may share a header, in which case links to it find the first one indexed and
+    /// path-qualified links such as `[[folder/Note]]` tell them apart.
     pub fn create_file(&self, file: &File) -> crate::Result<()> {
-        let header = &file.header();
-        let key = serde_json::to_vec(header)?;
-        let value = serde_json::to_vec(file)?;
+        let key = serde_json::to_vec(file.path())?;
+        if self.table.contains_key(&key)? {
+            return Err(crate::errors::file_already_indexed(file.path()));
+        }
+        self.table.insert(&key, serde_json::to_vec(file)?)?;
+        for (table, name) in self.names(file) {
+            self.insert_name(table, &name, &key, file)?;
+        }
+        for stem_key in stem_keys(file.path())? {
+            self.stems_table.insert(stem_key, [])?;
+        }
+        Ok(())
+    }
 
-        if let Some(old_file) = self.find(header)? {
-            return Err(crate::errors::file_with_duplicate_header_created(
-                &old_file, file,
-            ));
+    /// Forgets the note at `path`. The names it was found by point to another note sharing them,
+    /// if any.
+    pub fn delete_file(&self, path: &Path) -> crate::Result<()> {
+        let key = serde_json::to_vec(path)?;
+        let file = match self.find_by_key(&key)? {
+            Some(file) => file,
+            None => return Ok(()),
+        };
+        self.table.remove(&key)?;
+        for stem_key in stem_keys(path)? {
+            self.stems_table.remove(stem_key)?;
+        }
+
+        let mut orphans = Vec::new();
+        for (table, name) in self.names(&file) {
+            let name_key = serde_json::to_vec(&name)?;
+            if table
+                .get(&name_key)?
+                .is_some_and(|existing| existing == key)
+            {
+                table.remove(&name_key)?;
+                orphans.push((table, name));
+            }
+        }
+        if orphans.is_empty() {
+            return Ok(());
+        }
+        for entry_result in self.table.iter() {
+            let (other_key, value) = entry_result?;
+            let other: File = serde_json::from_slice(&value)?;
+            for (table, name) in self.names(&other) {
+                let adopted = orphans.iter().position(|(orphan_table, orphan)| {
+                    std::ptr::eq(*orphan_table, table) && *orphan == name
+                });
+                if let Some(index) = adopted {
+                    table.insert(serde_json::to_vec(&name)?, &other_key)?;
+                    orphans.swap_remove(index);
+                }
+            }
         }
-        self.table.insert(&key, value)?;
         Ok(())
     }
 
-    pub fn find(&self, header: &str) -> crate::Result<Option<File>> {
-        let key = serde_json::to_vec(&header)?;
-        if let Some(value) = self.table.get(&key)? {
+    /// The names a note is indexed by, with the table indexing each.
+    fn names(&self, file: &File) -> Vec<(&'a crate::ctx::DatabaseTable, String)> {
+        let header = file.header();
+        let mut names = vec![(self.headers_table, header.clone())];
+        names.extend(
+            file.aliases()
+                .into_iter()
+                .map(|alias| (self.aliases_table, alias)),
+        );
+        for name in std::iter::once(header).chain(file.aliases()) {
+            names.push((self.keys_table, normalize_key(&name)));
+            names.push((self.slugs_table, slug_key(&name)));
+        }
+        names
+    }
+
+    /// Names shared by several notes keep pointing to the first note indexed.
+    fn insert_name(
+        &self,
+        table: &crate::ctx::DatabaseTable,
+        name: &str,
+        key: &[u8],
+        file: &File,
+    ) -> crate::Result<()> {
+        let name_key = serde_json::to_vec(name)?;
+        match table.get(&name_key)? {
+            Some(existing) if existing != key => {
+                let existing: PathBuf = serde_json::from_slice(&existing)?;
+                log::warn!(
+                    "The notes {existing:?} and {:?} are both linked to as {name:?}, a link such as [[folder/Note]] tells them apart",
+                    file.path()
+                );
+            }
+            Some(_) => {}
+            None => {
+                table.insert(name_key, key)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Finds a note by its header, falling back to the aliases declared in front matter. Unless
+    /// links are matched strictly, both are then looked up by normalized key and by slug. A
+    /// target such as `folder/Note` is first looked up by path, and by name if no path matches.
+    pub fn find(&self, target: &str, resolver: &LinkResolver) -> crate::Result<Option<File>> {
+        if target.contains('/') {
+            if let Some(file) = self.find_by_target_path(target, resolver)? {
+                return Ok(Some(file));
+            }
+        }
+        let name_key = serde_json::to_vec(&target)?;
+        let mut lookups = vec![
+            (self.headers_table, name_key.clone()),
+            (self.aliases_table, name_key),
+        ];
+        if resolver.matching() != LinkMatching::Strict {
+            lookups.push((self.keys_table, serde_json::to_vec(&normalize_key(target))?));
+            lookups.push((self.slugs_table, serde_json::to_vec(&slug_key(target))?));
+        }
+        for (table, name_key) in lookups {
+            if let Some(key) = table.get(name_key)? {
+                return self.find_by_key(&key);
+            }
+        }
+        Ok(None)
+    }
+
+    /// Finds the first note, by path, that the path-qualified `target` names. Only the notes
+    /// whose file stem or file name slugs like the last segment of `target` are considered.
+    fn find_by_target_path(
+        &self,
+        target: &str,
+        resolver: &LinkResolver,
+    ) -> crate::Result<Option<File>> {
+        let last_segment = target.rsplit('/').next().unwrap_or_default();
+        let prefix = serde_json::to_vec(&slug_key(last_segment))?;
+        let mut paths: Vec<PathBuf> = Vec::new();
+        for entry_result in self.stems_table.scan_prefix(&prefix) {
+            let (key, _) = entry_result?;
+            paths.push(serde_json::from_slice(&key[prefix.len()..])?);
+        }
+        paths.sort();
+        paths.dedup();
+        match paths.iter().find(|path| resolver.names_path(target, path)) {
+            Some(path) => self.find_by_path(path),
+            None => Ok(None),
+        }
+    }
+
+    pub fn find_by_path(&self, path: &Path) -> crate::Result<Option<File>> {
+        self.find_by_key(&serde_json::to_vec(path)?)
+    }
+
+    /// Finds the note a reference links to, by path for Markdown links and by name otherwise.
+    pub fn find_target(
+        &self,
+        reference: &Reference,
+        resolver: &LinkResolver,
+    ) -> crate::Result<Option<File>> {
+        match reference.target_path() {
+            Some(path) => self.find_by_path(path),
+            None => self.find(&reference.header(), resolver),
+        }
+    }
+
+    fn find_by_key(&self, key: &[u8]) -> crate::Result<Option<File>> {
+        if let Some(value) = self.table.get(key)? {
             let value: &[u8] = &value;
             let file: File = serde_json::from_slice(value)?;
             return Ok(Some(file));
@@ -46,11 +222,29 @@ impl<'a> FilesRepository<'a> {
     }
 }
 
+/// Keys are the slug of the file stem or file name then the path, each JSON-encoded.
+fn stem_keys(path: &Path) -> crate::Result<Vec<Vec<u8>>> {
+    let mut keys = Vec::new();
+    for name in file_names(path) {
+        let mut key = serde_json::to_vec(&slug_key(&name))?;
+        key.extend(serde_json::to_vec(path)?);
+        keys.push(key);
+    }
+    keys.sort();
+    keys.dedup();
+    Ok(keys)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ctx::Config;
     use crate::repositories::RepositoryBuilder;
 
+    fn resolver(matching: LinkMatching) -> LinkResolver {
+        LinkResolver::new(PathBuf::from("/"), matching)
+    }
+
     #[test]
     fn test_files_repositority_create_file_success() {
         let repository_builder = RepositoryBuilder::mock();
@@ -60,7 +254,7 @@ mod tests {
     }
 
     #[test]
-    fn test_files_repositority_create_file_failure_duplicate_header() {
+    fn test_files_repositority_create_file_failure_duplicate_path() {
         let repository_builder = RepositoryBuilder::mock();
         let repository = repository_builder.files();
         let file = File::mock(None);
@@ -73,9 +267,191 @@ mod tests {
         let repository_builder = RepositoryBuilder::mock();
         let repository = repository_builder.files();
         let file = File::mock(None);
-        assert!(repository.find(&file.header()).unwrap().is_none());
+        let strict = resolver(LinkMatching::Strict);
+        assert!(repository.find(&file.header(), &strict).unwrap().is_none());
         assert!(repository.create_file(&file).is_ok());
-        assert!(repository.find(&file.header()).unwrap().is_some());
+        assert!(repository.find(&file.header(), &strict).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_files_repositority_find_normalized() {
+        let repository_builder = RepositoryBuilder::mock();
+        let repository = repository_builder.files();
+        let file = File::mock(Some(String::from(
+            "---\naliases: [Cafe\u{301} Notes]\n---\n# My Favorite Recipes\n",
+        )));
+        assert!(repository.create_file(&file).is_ok());
+
+        let normalized = resolver(LinkMatching::Normalized);
+        for target in [
+            "my favorite recipes",
+            "MY  FAVORITE\tRECIPES",
+            "my-favorite-recipes",
+            "caf\u{e9} notes",
+        ] {
+            let found = repository.find(target, &normalized).unwrap().unwrap();
+            // The original header is kept for display.
+            assert_eq!(found.header(), "My Favorite Recipes");
+        }
+        assert!(repository
+            .find("my recipes", &normalized)
+            .unwrap()
+            .is_none());
+        let strict = resolver(LinkMatching::Strict);
+        assert!(repository
+            .find("my favorite recipes", &strict)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_files_repositority_find_by_alias() {
+        let repository_builder = RepositoryBuilder::mock();
+        let repository = repository_builder.files();
+        let file = File::mock(Some(String::from(
+            "---\naliases: [Other Name, Third Name]\n---\n# I am a title\n",
+        )));
+        assert!(repository
+            .find("Other Name", &resolver(LinkMatching::Strict))
+            .unwrap()
+            .is_none());
+        assert!(repository.create_file(&file).is_ok());
+        assert_eq!(
+            repository
+                .find("Third Name", &resolver(LinkMatching::Strict))
+                .unwrap()
+                .unwrap()
+                .header(),
+            file.header()
+        );
+    }
+
+    #[test]
+    fn test_files_repositority_find_by_path() {
+        let repository_builder = RepositoryBuilder::mock();
+        let repository = repository_builder.files();
+        let file = File::mock(None);
+        assert!(repository.find_by_path(file.path()).unwrap().is_none());
+        assert!(repository.create_file(&file).is_ok());
+        assert_eq!(
+            repository
+                .find_by_path(file.path())
+                .unwrap()
+                .unwrap()
+                .header(),
+            file.header()
+        );
+    }
+
+    #[test]
+    fn test_files_repositority_find_target() {
+        let repository_builder = RepositoryBuilder::mock();
+        let repository = repository_builder.files();
+        let file = File::mock(None);
+        assert!(repository.create_file(&file).is_ok());
+
+        let by_header = Reference::new(file.path().clone(), file.header(), Default::default());
+        assert!(repository
+            .find_target(&by_header, &resolver(LinkMatching::Strict))
+            .unwrap()
+            .is_some());
+        let by_path = Reference::new(
+            file.path().clone(),
+            String::from("x.md"),
+            Default::default(),
+        )
+        .with_target_path(Some(file.path().clone()));
+        assert!(repository
+            .find_target(&by_path, &resolver(LinkMatching::Strict))
+            .unwrap()
+            .is_some());
+        let missing = by_header.with_target_path(Some(file.path().with_extension("txt")));
+        assert!(repository
+            .find_target(&missing, &resolver(LinkMatching::Strict))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_files_repositority_delete_file() {
+        let repository_builder = RepositoryBuilder::mock();
+        let repository = repository_builder.files();
+        let config = Config::default();
+        let first =
+            File::parse_from_str(PathBuf::from("/vault/a.md"), "# Meeting\n", &config).unwrap();
+        let second = File::parse_from_str(
+            PathBuf::from("/vault/b.md"),
+            "---\naliases: [Standup]\n---\n# Meeting\n",
+            &config,
+        )
+        .unwrap();
+        repository.create_file(&first).unwrap();
+        repository.create_file(&second).unwrap();
+
+        let normalized = resolver(LinkMatching::Normalized);
+        let find = |target: &str| {
+            repository
+                .find(target, &normalized)
+                .unwrap()
+                .map(|file| file.path().clone())
+        };
+        assert_eq!(find("Meeting"), Some(PathBuf::from("/vault/a.md")));
+        repository.delete_file(first.path()).unwrap();
+        assert!(repository.find_by_path(first.path()).unwrap().is_none());
+        assert_eq!(find("meeting"), Some(PathBuf::from("/vault/b.md")));
+        assert_eq!(find("Meeting"), Some(PathBuf::from("/vault/b.md")));
+
+        repository.delete_file(second.path()).unwrap();
+        assert_eq!(find("Standup"), None);
+        assert!(repository.find_all().unwrap().is_empty());
+        assert!(repository.delete_file(second.path()).is_ok());
+    }
+
+    #[test]
+    fn test_files_repositority_find_path_qualified() {
+        let repository_builder = RepositoryBuilder::mock();
+        let repository = repository_builder.files();
+        let config = Config::default();
+        for folder in ["work", "home"] {
+            let path = PathBuf::from(format!("/vault/{folder}/meeting.md"));
+            let file = File::parse_from_str(path, "# Meeting\n", &config).unwrap();
+            assert!(repository.create_file(&file).is_ok());
+        }
+
+        let resolver = LinkResolver::new(PathBuf::from("/vault"), LinkMatching::Normalized);
+        let find = |target: &str| {
+            repository
+                .find(target, &resolver)
+                .unwrap()
+                .map(|file| file.path().clone())
+        };
+        assert_eq!(
+            find("Meeting"),
+            Some(PathBuf::from("/vault/work/meeting.md"))
+        );
+        assert_eq!(
+            find("work/Meeting"),
+            Some(PathBuf::from("/vault/work/meeting.md"))
+        );
+        assert_eq!(
+            find("/home/meeting.md"),
+            Some(PathBuf::from("/vault/home/meeting.md"))
+        );
+        assert_eq!(find("other/meeting"), None);
+
+        // A header slugging like the path doesn't shadow the note at that path.
+        let decoy =
+            File::parse_from_str(PathBuf::from("/vault/decoy.md"), "# HomeMeeting\n", &config)
+                .unwrap();
+        assert!(repository.create_file(&decoy).is_ok());
+        assert_eq!(
+            find("home/meeting"),
+            Some(PathBuf::from("/vault/home/meeting.md"))
+        );
+        repository
+            .delete_file(Path::new("/vault/home/meeting.md"))
+            .unwrap();
+        assert_eq!(find("home/meeting"), Some(PathBuf::from("/vault/decoy.md")));
     }
 
     #[test]
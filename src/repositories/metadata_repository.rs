@@ -0,0 +1,70 @@
+use crate::ctx::DatabaseTable;
+
+const SCHEMA_VERSION_KEY: &[u8] = b"schema_version";
+const CONFIG_HASH_KEY: &[u8] = b"config_hash";
+
+/// Stores facts about the index itself rather than about notes.
+pub struct MetadataRepository<'a> {
+    table: &'a DatabaseTable,
+}
+
+impl<'a> MetadataRepository<'a> {
+    pub fn new(table: &'a DatabaseTable) -> Self {
+        Self { table }
+    }
+
+    /// The version of the encodings the index was written with, absent from an index that is
+    /// empty or predates versions. A version that can't be decoded counts as absent.
+    pub fn schema_version(&self) -> crate::Result<Option<u32>> {
+        Ok(self
+            .table
+            .get(SCHEMA_VERSION_KEY)?
+            .and_then(|value| serde_json::from_slice(&value).ok()))
+    }
+
+    pub fn set_schema_version(&self, version: u32) -> crate::Result<()> {
+        self.table
+            .insert(SCHEMA_VERSION_KEY, serde_json::to_vec(&version)?)?;
+        Ok(())
+    }
+
+    /// The hash of the settings the notes were indexed with, absent from an index that is empty
+    /// or predates it.
+    pub fn config_hash(&self) -> crate::Result<Option<u64>> {
+        Ok(self
+            .table
+            .get(CONFIG_HASH_KEY)?
+            .and_then(|value| serde_json::from_slice(&value).ok()))
+    }
+
+    pub fn set_config_hash(&self, hash: u64) -> crate::Result<()> {
+        self.table
+            .insert(CONFIG_HASH_KEY, serde_json::to_vec(&hash)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::repositories::RepositoryBuilder;
+
+    #[test]
+    fn test_metadata_repository_schema_version() -> crate::Result<()> {
+        let repository_builder = RepositoryBuilder::mock();
+        let repository = repository_builder.metadata();
+        assert_eq!(repository.schema_version()?, None);
+        repository.set_schema_version(3)?;
+        assert_eq!(repository.schema_version()?, Some(3));
+        Ok(())
+    }
+
+    #[test]
+    fn test_metadata_repository_config_hash() -> crate::Result<()> {
+        let repository_builder = RepositoryBuilder::mock();
+        let repository = repository_builder.metadata();
+        assert_eq!(repository.config_hash()?, None);
+        repository.set_config_hash(u64::MAX)?;
+        assert_eq!(repository.config_hash()?, Some(u64::MAX));
+        Ok(())
+    }
+}
@@ -4,5 +4,20 @@ pub use self::files_repository::*;
 mod references_repository;
 pub use self::references_repository::*;
 
+mod tags_repository;
+pub use self::tags_repository::*;
+
+mod tasks_repository;
+pub use self::tasks_repository::*;
+
+mod stamps_repository;
+pub use self::stamps_repository::*;
+
+mod search_repository;
+pub use self::search_repository::*;
+
+mod metadata_repository;
+pub use self::metadata_repository::*;
+
 mod repository_builder;
 pub use self::repository_builder::*;
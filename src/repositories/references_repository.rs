@@ -1,27 +1,138 @@
-use crate::ctx::DatabaseTable;
-use crate::models::Reference;
+use crate::ctx::{file_names, DatabaseTable, LinkResolver};
+use crate::models::{slug_key, File, Reference};
+use std::path::Path;
 
+/// Stores every reference keyed by its target, its source note and its location, so the links
+/// to a note are found with a prefix scan. The `by_source` tree maps each source note to the
+/// keys of its references, so a note's outgoing links are replaced together on reindex.
 pub struct ReferencesRepository<'a> {
     table: &'a DatabaseTable,
+    by_source_table: &'a DatabaseTable,
 }
 
 impl<'a> ReferencesRepository<'a> {
-    pub fn new(table: &'a DatabaseTable) -> Self {
-        Self { table }
+    pub fn new(table: &'a DatabaseTable, by_source_table: &'a DatabaseTable) -> Self {
+        Self {
+            table,
+            by_source_table,
+        }
     }
 
     pub fn upsert_reference(&self, reference: &Reference) -> crate::Result<()> {
-        let key = serde_json::to_vec(&reference.header())?;
-        let value = serde_json::to_vec(reference)?;
-        self.table.insert(&key, value)?;
+        let key = reference_key(reference)?;
+        self.table.insert(&key, serde_json::to_vec(reference)?)?;
+        self.by_source_table.insert(source_key(reference)?, key)?;
         Ok(())
     }
+
+    /// Replaces the outgoing links of the note at `path` with `references`.
+    pub fn replace_references(&self, path: &Path, references: &[Reference]) -> crate::Result<()> {
+        for entry_result in self.by_source_table.scan_prefix(serde_json::to_vec(path)?) {
+            let (source_key, key) = entry_result?;
+            self.table.remove(key)?;
+            self.by_source_table.remove(source_key)?;
+        }
+        for reference in references {
+            self.upsert_reference(reference)?;
+        }
+        Ok(())
+    }
+
+    /// The references whose target is `name`, or a path ending with it, compared by slug.
+    /// Callers narrow them down to the links that resolve to a given note.
+    pub fn find_by_target(&self, name: &str) -> crate::Result<Vec<Reference>> {
+        self.scan(serde_json::to_vec(&slug_key(name))?)
+    }
+
+    /// The references whose Markdown link points at `path`.
+    pub fn find_by_target_path(&self, path: &Path) -> crate::Result<Vec<Reference>> {
+        self.scan(serde_json::to_vec(&path_key(path))?)
+    }
+
+    /// The outgoing links of the note at `path`, in the order they appear.
+    pub fn find_by_source(&self, path: &Path) -> crate::Result<Vec<Reference>> {
+        let mut references = Vec::new();
+        for entry_result in self.by_source_table.scan_prefix(serde_json::to_vec(path)?) {
+            let (_, key) = entry_result?;
+            if let Some(value) = self.table.get(key)? {
+                references.push(serde_json::from_slice(&value)?);
+            }
+        }
+        Ok(references)
+    }
+
+    /// The links to `file` from any note: by its header or an alias, by a path such as
+    /// `folder/Note`, or by a Markdown link to its path.
+    pub fn find_linking_to(
+        &self,
+        file: &File,
+        resolver: &LinkResolver,
+    ) -> crate::Result<Vec<Reference>> {
+        let mut names: Vec<String> = std::iter::once(file.header())
+            .chain(file.aliases())
+            .chain(file_names(file.path()))
+            .map(|name| slug_key(&name))
+            .collect();
+        names.sort();
+        names.dedup();
+
+        let mut references = self.find_by_target_path(file.path())?;
+        for name in names {
+            references.extend(self.find_by_target(&name)?);
+        }
+        references.retain(|reference| resolver.links_to(reference, file));
+        references.sort_by(|a, b| {
+            (a.path(), a.location().start.offset).cmp(&(b.path(), b.location().start.offset))
+        });
+        Ok(references)
+    }
+
+    fn scan(&self, prefix: Vec<u8>) -> crate::Result<Vec<Reference>> {
+        let mut references = Vec::new();
+        for entry_result in self.table.scan_prefix(prefix) {
+            let (_, value) = entry_result?;
+            references.push(serde_json::from_slice(&value)?);
+        }
+        Ok(references)
+    }
+}
+
+/// Keys start with the target, JSON-encoded so that a string never prefixes another one, then
+/// the source path and the offset of the link in it.
+fn reference_key(reference: &Reference) -> crate::Result<Vec<u8>> {
+    let target = match reference.target_path() {
+        Some(path) => path_key(path),
+        // `[[folder/Note]]` is stored with the links to `Note`, the segment it ends with.
+        None => {
+            let header = reference.header();
+            slug_key(header.rsplit('/').next().unwrap_or_default())
+        }
+    };
+    let mut key = serde_json::to_vec(&target)?;
+    key.extend(source_key(reference)?);
+    Ok(key)
+}
+
+fn source_key(reference: &Reference) -> crate::Result<Vec<u8>> {
+    let mut key = serde_json::to_vec(reference.path())?;
+    key.extend((reference.location().start.offset as u64).to_be_bytes());
+    Ok(key)
+}
+
+fn path_key(path: &Path) -> String {
+    format!("path:{}", path.to_string_lossy())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ctx::{Config, LinkMatching};
     use crate::repositories::RepositoryBuilder;
+    use std::path::PathBuf;
+
+    fn parse(path: &str, text: &str) -> File {
+        File::parse_from_str(PathBuf::from(path), text, &Config::default()).unwrap()
+    }
 
     #[test]
     fn test_references_repositority_create_file_success() {
@@ -30,5 +141,103 @@ mod tests {
         let reference = Reference::mock();
         assert!(repository.upsert_reference(&reference).is_ok());
         assert!(repository.upsert_reference(&reference).is_ok());
+        assert_eq!(
+            repository
+                .find_by_target(&reference.header())
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_references_repository_keeps_every_reference() {
+        let repository_builder = RepositoryBuilder::mock();
+        let repository = repository_builder.references();
+        let first = parse("/vault/a.md", "# A\n[[Target]] and [[target]]\n");
+        let second = parse("/vault/b.md", "# B\n[[Target]] [[Other]]\n");
+        repository
+            .replace_references(first.path(), first.references())
+            .unwrap();
+        repository
+            .replace_references(second.path(), second.references())
+            .unwrap();
+
+        let sources: Vec<PathBuf> = repository
+            .find_by_target("Target")
+            .unwrap()
+            .iter()
+            .map(|reference| reference.path().clone())
+            .collect();
+        assert_eq!(
+            sources,
+            vec![
+                PathBuf::from("/vault/a.md"),
+                PathBuf::from("/vault/a.md"),
+                PathBuf::from("/vault/b.md")
+            ]
+        );
+        assert_eq!(repository.find_by_target("Other").unwrap().len(), 1);
+        assert_eq!(repository.find_by_source(second.path()).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_references_repository_replace_references() {
+        let repository_builder = RepositoryBuilder::mock();
+        let repository = repository_builder.references();
+        let before = parse("/vault/a.md", "# A\n[[Target]] [[Other]]\n");
+        repository
+            .replace_references(before.path(), before.references())
+            .unwrap();
+
+        let after = parse("/vault/a.md", "# A\n[[Other]]\n[[Third]]\n");
+        repository
+            .replace_references(after.path(), after.references())
+            .unwrap();
+        assert!(repository.find_by_target("Target").unwrap().is_empty());
+        assert_eq!(repository.find_by_target("Other").unwrap().len(), 1);
+        let headers: Vec<String> = repository
+            .find_by_source(after.path())
+            .unwrap()
+            .iter()
+            .map(|reference| reference.header())
+            .collect();
+        assert_eq!(headers, vec!["Other", "Third"]);
+    }
+
+    #[test]
+    fn test_references_repository_find_linking_to() {
+        let repository_builder = RepositoryBuilder::mock();
+        let repository = repository_builder.references();
+        let target = parse(
+            "/vault/work/meeting.md",
+            "---\naliases: [Standup]\n---\n# Meeting\n",
+        );
+        let source = parse(
+            "/vault/source.md",
+            "# Source\n[[meeting]] [[Standup]] [[work/meeting]] [[home/meeting]] [m](work/meeting.md)\n",
+        );
+        repository
+            .replace_references(source.path(), source.references())
+            .unwrap();
+
+        let resolver = LinkResolver::new(PathBuf::from("/vault"), LinkMatching::Normalized);
+        let mut headers: Vec<String> = repository
+            .find_linking_to(&target, &resolver)
+            .unwrap()
+            .iter()
+            .map(|reference| reference.header())
+            .collect();
+        headers.sort();
+        assert_eq!(
+            headers,
+            vec!["Standup", "meeting", "work/meeting", "work/meeting.md"]
+        );
+
+        let strict = LinkResolver::new(PathBuf::from("/vault"), LinkMatching::Strict);
+        assert_eq!(
+            repository.find_linking_to(&target, &strict).unwrap().len(),
+            3
+        );
     }
 }
@@ -1,52 +1,150 @@
-use crate::ctx::DatabaseTable;
-use crate::repositories::{FilesRepository, ReferencesRepository};
+use crate::ctx::{DatabaseTable, Storage};
+use crate::repositories::{
+    FilesRepository, MetadataRepository, ReferencesRepository, SearchRepository, StampsRepository,
+    TagsRepository, TasksRepository,
+};
+use std::rc::Rc;
 
 #[derive(Debug)]
 pub struct RepositoryBuilder {
+    storage: Rc<dyn Storage>,
     files_table: DatabaseTable,
+    aliases_table: DatabaseTable,
+    headers_table: DatabaseTable,
+    keys_table: DatabaseTable,
+    slugs_table: DatabaseTable,
+    stems_table: DatabaseTable,
     references_table: DatabaseTable,
+    references_by_source_table: DatabaseTable,
+    tags_table: DatabaseTable,
+    tasks_table: DatabaseTable,
+    stamps_table: DatabaseTable,
+    search_terms_table: DatabaseTable,
+    search_lengths_table: DatabaseTable,
+    search_documents_table: DatabaseTable,
+    metadata_table: DatabaseTable,
 }
 
 impl RepositoryBuilder {
-    pub fn new(conn: &crate::ctx::Connection) -> crate::Result<Self> {
-        let files_table = conn.open_tree(b"files")?;
-        let references_table = conn.open_tree(b"references")?;
+    pub fn new(storage: Rc<dyn Storage>) -> Self {
+        let table = |name| DatabaseTable::new(storage.clone(), name);
+        let files_table = table("files");
+        let aliases_table = table("aliases");
+        let headers_table = table("headers");
+        let keys_table = table("keys");
+        let slugs_table = table("slugs");
+        let stems_table = table("stems");
+        let references_table = table("references");
+        let references_by_source_table = table("references_by_source");
+        let tags_table = table("tags");
+        let tasks_table = table("tasks");
+        let stamps_table = table("stamps");
+        let search_terms_table = table("search_terms");
+        let search_lengths_table = table("search_lengths");
+        let search_documents_table = table("search_documents");
+        let metadata_table = table("metadata");
 
-        Ok(Self {
+        Self {
+            storage,
             files_table,
+            aliases_table,
+            headers_table,
+            keys_table,
+            slugs_table,
+            stems_table,
             references_table,
-        })
+            references_by_source_table,
+            tags_table,
+            tasks_table,
+            stamps_table,
+            search_terms_table,
+            search_lengths_table,
+            search_documents_table,
+            metadata_table,
+        }
     }
 
+    /// Clears what is indexed about notes, keeping the metadata about the index itself.
     pub fn clear(&self) -> crate::Result<()> {
-        self.files_table.clear()?;
-        self.references_table.clear()?;
+        for table in self.tables() {
+            if !std::ptr::eq(table, &self.metadata_table) {
+                table.clear()?;
+            }
+        }
         Ok(())
     }
     pub fn flush(&self) -> crate::Result<()> {
-        self.files_table.flush()?;
-        self.references_table.flush()?;
-        Ok(())
+        self.storage.flush()
+    }
+
+    pub(crate) fn tables(&self) -> [&DatabaseTable; 15] {
+        [
+            &self.files_table,
+            &self.aliases_table,
+            &self.headers_table,
+            &self.keys_table,
+            &self.slugs_table,
+            &self.stems_table,
+            &self.references_table,
+            &self.references_by_source_table,
+            &self.tags_table,
+            &self.tasks_table,
+            &self.stamps_table,
+            &self.search_terms_table,
+            &self.search_lengths_table,
+            &self.search_documents_table,
+            &self.metadata_table,
+        ]
+    }
+
+    pub fn files(&self) -> FilesRepository<'_> {
+        FilesRepository::new(
+            &self.files_table,
+            &self.headers_table,
+            &self.aliases_table,
+            &self.keys_table,
+            &self.slugs_table,
+            &self.stems_table,
+        )
     }
 
-    pub fn files(&self) -> FilesRepository {
-        FilesRepository::new(&self.files_table)
+    pub fn references(&self) -> ReferencesRepository<'_> {
+        ReferencesRepository::new(&self.references_table, &self.references_by_source_table)
     }
 
-    pub fn references(&self) -> ReferencesRepository {
-        ReferencesRepository::new(&self.references_table)
+    pub fn tags(&self) -> TagsRepository<'_> {
+        TagsRepository::new(&self.tags_table)
+    }
+
+    pub fn tasks(&self) -> TasksRepository<'_> {
+        TasksRepository::new(&self.tasks_table)
+    }
+
+    pub fn stamps(&self) -> StampsRepository<'_> {
+        StampsRepository::new(&self.stamps_table)
+    }
+
+    pub fn search(&self) -> SearchRepository<'_> {
+        SearchRepository::new(
+            &self.search_terms_table,
+            &self.search_lengths_table,
+            &self.search_documents_table,
+        )
+    }
+
+    pub fn metadata(&self) -> MetadataRepository<'_> {
+        MetadataRepository::new(&self.metadata_table)
     }
 }
 
 #[cfg(test)]
 pub mod mocks {
     use super::*;
-    use crate::ctx::Database;
+    use crate::ctx::MemoryStorage;
 
     impl RepositoryBuilder {
         pub fn mock() -> Self {
-            let conn = Database::mock_connection();
-            RepositoryBuilder::new(&conn).unwrap()
+            RepositoryBuilder::new(Rc::new(MemoryStorage::new()))
         }
     }
 }
@@ -54,13 +152,13 @@ pub mod mocks {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::ctx::Database;
+    use crate::ctx::{Database, SledStorage};
 
     #[test]
     fn test_repository_builder_new() {
-        let conn = Database::mock_connection();
-        let repository_builder = RepositoryBuilder::new(&conn);
-        assert!(repository_builder.is_ok())
+        let storage = Rc::new(SledStorage::new(Database::mock_connection()));
+        let repository_builder = RepositoryBuilder::new(storage);
+        assert!(repository_builder.files().find_all().unwrap().is_empty());
     }
 
     #[test]
@@ -84,4 +182,24 @@ mod tests {
     fn test_repository_builder_references() {
         RepositoryBuilder::mock().references();
     }
+
+    #[test]
+    fn test_repository_builder_tags() {
+        RepositoryBuilder::mock().tags();
+    }
+
+    #[test]
+    fn test_repository_builder_tasks() {
+        RepositoryBuilder::mock().tasks();
+    }
+
+    #[test]
+    fn test_repository_builder_stamps() {
+        RepositoryBuilder::mock().stamps();
+    }
+
+    #[test]
+    fn test_repository_builder_metadata() {
+        RepositoryBuilder::mock().metadata();
+    }
 }
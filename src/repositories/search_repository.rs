@@ -0,0 +1,194 @@
+use crate::ctx::DatabaseTable;
+use crate::models::SearchDocument;
+use crate::parsers::tokenize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// The postings of a term in one note: the positions of its occurrences, counted in words.
+pub type Postings = Vec<u32>;
+
+/// Stores the full-text index of note bodies: the positions of each term in each note keyed by
+/// term then path, so the notes containing a term or a term prefix are found with a prefix scan.
+pub struct SearchRepository<'a> {
+    terms_table: &'a DatabaseTable,
+    // The number of words of each note, read on every search to rank by BM25.
+    lengths_table: &'a DatabaseTable,
+    documents_table: &'a DatabaseTable,
+}
+
+impl<'a> SearchRepository<'a> {
+    pub fn new(
+        terms_table: &'a DatabaseTable,
+        lengths_table: &'a DatabaseTable,
+        documents_table: &'a DatabaseTable,
+    ) -> Self {
+        Self {
+            terms_table,
+            lengths_table,
+            documents_table,
+        }
+    }
+
+    /// Indexes the words of the note at `path`, replacing what was indexed of it before.
+    pub fn index_document(&self, path: &Path, text: &str) -> crate::Result<()> {
+        self.remove_document(path)?;
+
+        let tokens = tokenize(text);
+        let mut postings: BTreeMap<&str, Postings> = BTreeMap::new();
+        for (position, token) in tokens.iter().enumerate() {
+            postings
+                .entry(token.term.as_str())
+                .or_default()
+                .push(position as u32);
+        }
+        for (term, positions) in postings.iter() {
+            self.terms_table
+                .insert(term_key(term, path)?, serde_json::to_vec(positions)?)?;
+        }
+
+        let path_key = serde_json::to_vec(path)?;
+        self.lengths_table
+            .insert(&path_key, serde_json::to_vec(&(tokens.len() as u32))?)?;
+        let document = SearchDocument::new(
+            postings.keys().map(|term| String::from(*term)).collect(),
+            tokens.iter().map(|token| token.line).collect(),
+            text.lines().map(String::from).collect(),
+        );
+        self.documents_table
+            .insert(path_key, serde_json::to_vec(&document)?)?;
+        Ok(())
+    }
+
+    pub fn remove_document(&self, path: &Path) -> crate::Result<()> {
+        let document = match self.find_document(path)? {
+            Some(document) => document,
+            None => return Ok(()),
+        };
+        for term in document.terms() {
+            self.terms_table.remove(term_key(term, path)?)?;
+        }
+        let path_key = serde_json::to_vec(path)?;
+        self.lengths_table.remove(&path_key)?;
+        self.documents_table.remove(path_key)?;
+        Ok(())
+    }
+
+    /// The notes containing `term`, with the positions it occurs at.
+    pub fn find_postings(&self, term: &str) -> crate::Result<Vec<(PathBuf, Postings)>> {
+        Ok(self
+            .scan(serde_json::to_vec(term)?)?
+            .into_iter()
+            .filter(|(found, _, _)| found == term)
+            .map(|(_, path, postings)| (path, postings))
+            .collect())
+    }
+
+    /// The terms starting with `prefix`, with the notes containing each and its positions.
+    pub fn find_postings_with_prefix(
+        &self,
+        prefix: &str,
+    ) -> crate::Result<Vec<(String, PathBuf, Postings)>> {
+        // An encoded string without its closing quote prefixes the encodings of its extensions.
+        let mut key = serde_json::to_vec(prefix)?;
+        key.pop();
+        self.scan(key)
+    }
+
+    pub fn find_document(&self, path: &Path) -> crate::Result<Option<SearchDocument>> {
+        match self.documents_table.get(serde_json::to_vec(path)?)? {
+            Some(value) => Ok(Some(serde_json::from_slice(&value)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// The number of words of every indexed note.
+    pub fn lengths(&self) -> crate::Result<Vec<(PathBuf, u32)>> {
+        let mut lengths = Vec::new();
+        for entry_result in self.lengths_table.iter() {
+            let (key, value) = entry_result?;
+            lengths.push((
+                serde_json::from_slice(&key)?,
+                serde_json::from_slice(&value)?,
+            ));
+        }
+        Ok(lengths)
+    }
+
+    fn scan(&self, prefix: Vec<u8>) -> crate::Result<Vec<(String, PathBuf, Postings)>> {
+        let mut postings = Vec::new();
+        for entry_result in self.terms_table.scan_prefix(prefix) {
+            let (key, value) = entry_result?;
+            let mut parts = serde_json::Deserializer::from_slice(&key).into_iter::<String>();
+            let (term, path) = match (parts.next(), parts.next()) {
+                (Some(term), Some(path)) => (term?, PathBuf::from(path?)),
+                _ => continue,
+            };
+            postings.push((term, path, serde_json::from_slice(&value)?));
+        }
+        Ok(postings)
+    }
+}
+
+/// Keys are the term then the path, each JSON-encoded so that neither can run into the other.
+fn term_key(term: &str, path: &Path) -> crate::Result<Vec<u8>> {
+    let mut key = serde_json::to_vec(term)?;
+    key.extend(serde_json::to_vec(path)?);
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repositories::RepositoryBuilder;
+
+    #[test]
+    fn test_search_repository_index_document() -> crate::Result<()> {
+        let repository_builder = RepositoryBuilder::mock();
+        let repository = repository_builder.search();
+        let first = Path::new("/vault/first.md");
+        let second = Path::new("/vault/second.md");
+        repository.index_document(first, "# Recipes\nrecipe for bread\nbread again\n")?;
+        repository.index_document(second, "# Bread\n")?;
+
+        assert_eq!(
+            repository.find_postings("bread")?,
+            vec![
+                (first.to_path_buf(), vec![3, 4]),
+                (second.to_path_buf(), vec![0])
+            ]
+        );
+        assert!(repository.find_postings("rec")?.is_empty());
+        let terms: Vec<String> = repository
+            .find_postings_with_prefix("rec")?
+            .into_iter()
+            .map(|(term, _, _)| term)
+            .collect();
+        assert_eq!(terms, vec!["recipe", "recipes"]);
+
+        let document = repository.find_document(first)?.unwrap();
+        assert_eq!(document.token_line(4), Some(2));
+        assert_eq!(document.line(2), Some("bread again"));
+        assert_eq!(
+            repository.lengths()?,
+            vec![(first.to_path_buf(), 6), (second.to_path_buf(), 1)]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_repository_reindex_and_remove_document() -> crate::Result<()> {
+        let repository_builder = RepositoryBuilder::mock();
+        let repository = repository_builder.search();
+        let path = Path::new("/vault/note.md");
+        repository.index_document(path, "old words\n")?;
+        repository.index_document(path, "new words\n")?;
+        assert!(repository.find_postings("old")?.is_empty());
+        assert_eq!(repository.find_postings("new")?.len(), 1);
+
+        repository.remove_document(path)?;
+        assert!(repository.find_postings("words")?.is_empty());
+        assert!(repository.find_document(path)?.is_none());
+        assert!(repository.lengths()?.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,65 @@
+use crate::ctx::DatabaseTable;
+use crate::models::FileStamp;
+use std::path::{Path, PathBuf};
+
+/// Stores the stamp of each indexed note by path, so a reindex only parses the notes that changed.
+pub struct StampsRepository<'a> {
+    table: &'a DatabaseTable,
+}
+
+impl<'a> StampsRepository<'a> {
+    pub fn new(table: &'a DatabaseTable) -> Self {
+        Self { table }
+    }
+
+    pub fn upsert_stamp(&self, path: &Path, stamp: &FileStamp) -> crate::Result<()> {
+        self.table
+            .insert(serde_json::to_vec(path)?, serde_json::to_vec(stamp)?)?;
+        Ok(())
+    }
+
+    pub fn find(&self, path: &Path) -> crate::Result<Option<FileStamp>> {
+        match self.table.get(serde_json::to_vec(path)?)? {
+            Some(value) => Ok(Some(serde_json::from_slice(&value)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn delete(&self, path: &Path) -> crate::Result<()> {
+        self.table.remove(serde_json::to_vec(path)?)?;
+        Ok(())
+    }
+
+    pub fn paths(&self) -> crate::Result<Vec<PathBuf>> {
+        let mut paths = Vec::new();
+        for entry_result in self.table.iter() {
+            let (key, _) = entry_result?;
+            paths.push(serde_json::from_slice(&key)?);
+        }
+        Ok(paths)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repositories::RepositoryBuilder;
+
+    #[test]
+    fn test_stamps_repository_upsert_find_delete() -> crate::Result<()> {
+        let tmp_dir = tempfile::Builder::new().prefix("grimoire").tempdir()?;
+        let path = tmp_dir.path().join("note.md");
+        std::fs::write(&path, "# Note\n")?;
+        let stamp = FileStamp::new(&std::fs::metadata(&path)?, "# Note\n");
+
+        let repository_builder = RepositoryBuilder::mock();
+        let repository = repository_builder.stamps();
+        assert!(repository.find(&path)?.is_none());
+        repository.upsert_stamp(&path, &stamp)?;
+        assert_eq!(repository.find(&path)?, Some(stamp));
+        assert_eq!(repository.paths()?, vec![path.clone()]);
+        repository.delete(&path)?;
+        assert!(repository.paths()?.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,127 @@
+use crate::ctx::DatabaseTable;
+use crate::models::Tag;
+use std::path::Path;
+
+/// Stores the occurrences of tags keyed by the file they are written in and their name, so a
+/// file's tags are replaced with a prefix scan on reindex.
+pub struct TagsRepository<'a> {
+    table: &'a DatabaseTable,
+}
+
+impl<'a> TagsRepository<'a> {
+    pub fn new(table: &'a DatabaseTable) -> Self {
+        Self { table }
+    }
+
+    /// Records an occurrence of a tag, keeping every other occurrence of the same name.
+    pub fn upsert_tag(&self, tag: &Tag) -> crate::Result<()> {
+        let key = serde_json::to_vec(&(tag.path(), tag.name()))?;
+        let mut tags: Vec<Tag> = match self.table.get(&key)? {
+            Some(value) => serde_json::from_slice(&value)?,
+            None => Vec::new(),
+        };
+        if !tags.contains(tag) {
+            tags.push(tag.clone());
+        }
+        self.table.insert(&key, serde_json::to_vec(&tags)?)?;
+        Ok(())
+    }
+
+    pub fn find(&self, name: &str) -> crate::Result<Vec<Tag>> {
+        Ok(self
+            .find_all()?
+            .into_iter()
+            .filter(|tag| tag.name() == name)
+            .collect())
+    }
+
+    /// Forgets the tags of the note at `path`, whose keys all start with the encoded path.
+    pub fn delete_by_path(&self, path: &Path) -> crate::Result<()> {
+        let mut prefix = b"[".to_vec();
+        prefix.extend(serde_json::to_vec(path)?);
+        prefix.push(b',');
+        for entry_result in self.table.scan_prefix(prefix) {
+            let (key, _) = entry_result?;
+            self.table.remove(key)?;
+        }
+        Ok(())
+    }
+
+    pub fn find_all(&self) -> crate::Result<Vec<Tag>> {
+        let mut tags = Vec::new();
+        for entry_result in self.table.iter() {
+            let (_, value) = entry_result?;
+            let occurrences: Vec<Tag> = serde_json::from_slice(&value)?;
+            tags.extend(occurrences);
+        }
+        Ok(tags)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Location;
+    use crate::repositories::RepositoryBuilder;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_tags_repository_upsert_tag() {
+        let repository_builder = RepositoryBuilder::mock();
+        let repository = repository_builder.tags();
+        let tag = Tag::mock();
+        assert!(repository.upsert_tag(&tag).is_ok());
+        assert!(repository.upsert_tag(&tag).is_ok());
+        assert_eq!(repository.find(tag.name()).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_tags_repository_find() {
+        let repository_builder = RepositoryBuilder::mock();
+        let repository = repository_builder.tags();
+        assert!(repository.find("inbox").unwrap().is_empty());
+
+        let first = Tag::new(PathBuf::from("a.md"), String::from("inbox"), None);
+        let second = Tag::new(
+            PathBuf::from("b.md"),
+            String::from("inbox"),
+            Some(Location::default()),
+        );
+        repository.upsert_tag(&first).unwrap();
+        repository.upsert_tag(&second).unwrap();
+        assert_eq!(repository.find("inbox").unwrap(), vec![first, second]);
+    }
+
+    #[test]
+    fn test_tags_repository_delete_by_path() {
+        let repository_builder = RepositoryBuilder::mock();
+        let repository = repository_builder.tags();
+        let first = Tag::new(PathBuf::from("a.md"), String::from("inbox"), None);
+        let second = Tag::new(PathBuf::from("b.md"), String::from("inbox"), None);
+        let only = Tag::new(PathBuf::from("a.md"), String::from("draft"), None);
+        for tag in [&first, &second, &only] {
+            repository.upsert_tag(tag).unwrap();
+        }
+        let nested = Tag::new(PathBuf::from("a.md/b.md"), String::from("inbox"), None);
+        repository.upsert_tag(&nested).unwrap();
+        repository.delete_by_path(Path::new("a.md")).unwrap();
+        assert_eq!(repository.find("inbox").unwrap(), vec![nested, second]);
+        assert!(repository.find("draft").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_tags_repository_find_all() {
+        let repository_builder = RepositoryBuilder::mock();
+        let repository = repository_builder.tags();
+        assert!(repository.find_all().unwrap().is_empty());
+        repository.upsert_tag(&Tag::mock()).unwrap();
+        repository
+            .upsert_tag(&Tag::new(
+                PathBuf::from("a.md"),
+                String::from("inbox"),
+                None,
+            ))
+            .unwrap();
+        assert_eq!(repository.find_all().unwrap().len(), 2);
+    }
+}
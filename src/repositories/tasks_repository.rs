@@ -0,0 +1,127 @@
+use crate::ctx::DatabaseTable;
+use crate::models::Task;
+use std::path::Path;
+
+/// Stores tasks keyed by the file and line they are written on.
+pub struct TasksRepository<'a> {
+    table: &'a DatabaseTable,
+}
+
+impl<'a> TasksRepository<'a> {
+    pub fn new(table: &'a DatabaseTable) -> Self {
+        Self { table }
+    }
+
+    pub fn upsert_task(&self, task: &Task) -> crate::Result<()> {
+        let key = serde_json::to_vec(&(task.path(), task.line()))?;
+        self.table.insert(&key, serde_json::to_vec(task)?)?;
+        Ok(())
+    }
+
+    pub fn find(&self, path: &Path, line: u32) -> crate::Result<Option<Task>> {
+        let key = serde_json::to_vec(&(path, line))?;
+        match self.table.get(&key)? {
+            Some(value) => Ok(Some(serde_json::from_slice(&value)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Forgets the tasks of the note at `path`, whose keys all start with the encoded path.
+    pub fn delete_by_path(&self, path: &Path) -> crate::Result<()> {
+        let mut prefix = b"[".to_vec();
+        prefix.extend(serde_json::to_vec(path)?);
+        prefix.push(b',');
+        for entry_result in self.table.scan_prefix(prefix) {
+            let (key, _) = entry_result?;
+            self.table.remove(key)?;
+        }
+        Ok(())
+    }
+
+    pub fn find_all(&self) -> crate::Result<Vec<Task>> {
+        let mut tasks = Vec::new();
+        for entry_result in self.table.iter() {
+            let (_, value) = entry_result?;
+            tasks.push(serde_json::from_slice(&value)?);
+        }
+        Ok(tasks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Location;
+    use crate::repositories::RepositoryBuilder;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_tasks_repository_upsert_task() {
+        let repository_builder = RepositoryBuilder::mock();
+        let repository = repository_builder.tasks();
+        let task = Task::mock();
+        assert!(repository.upsert_task(&task).is_ok());
+        assert!(repository.upsert_task(&task).is_ok());
+        assert_eq!(repository.find_all().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_tasks_repository_find() {
+        let repository_builder = RepositoryBuilder::mock();
+        let repository = repository_builder.tasks();
+        let task = Task::mock();
+        assert!(repository.find(task.path(), task.line()).unwrap().is_none());
+        repository.upsert_task(&task).unwrap();
+        assert_eq!(
+            repository.find(task.path(), task.line()).unwrap(),
+            Some(task)
+        );
+    }
+
+    #[test]
+    fn test_tasks_repository_delete_by_path() {
+        let repository_builder = RepositoryBuilder::mock();
+        let repository = repository_builder.tasks();
+        let task = |path: &str, line: u32| {
+            Task::new(
+                PathBuf::from(path),
+                String::from("Task"),
+                false,
+                Location::mock(line, 0, 0),
+            )
+        };
+        for (path, line) in [("a.md", 1), ("a.md", 4), ("a.md.bak", 1), ("b.md", 2)] {
+            repository.upsert_task(&task(path, line)).unwrap();
+        }
+        repository.delete_by_path(Path::new("a.md")).unwrap();
+        let mut paths: Vec<PathBuf> = repository
+            .find_all()
+            .unwrap()
+            .into_iter()
+            .map(|task| task.path().clone())
+            .collect();
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![PathBuf::from("a.md.bak"), PathBuf::from("b.md")]
+        );
+    }
+
+    #[test]
+    fn test_tasks_repository_find_all() {
+        let repository_builder = RepositoryBuilder::mock();
+        let repository = repository_builder.tasks();
+        assert!(repository.find_all().unwrap().is_empty());
+        repository.upsert_task(&Task::mock()).unwrap();
+        let location = Location::mock(3, 0, 0);
+        repository
+            .upsert_task(&Task::new(
+                PathBuf::from("note.md"),
+                String::from("Other"),
+                true,
+                location,
+            ))
+            .unwrap();
+        assert_eq!(repository.find_all().unwrap().len(), 2);
+    }
+}
@@ -1,2 +1,14 @@
 mod reindex;
 pub use self::reindex::*;
+
+mod render;
+pub use self::render::*;
+
+mod tags;
+pub use self::tags::*;
+
+mod tasks;
+pub use self::tasks::*;
+
+mod search;
+pub use self::search::*;
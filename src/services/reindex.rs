@@ -1,42 +1,117 @@
-use crate::ctx::{Context, ProjectRoot};
-use crate::models::File;
+use crate::ctx::{Config, Context, ProjectRoot};
+use crate::models::{File, FileStamp};
+use crate::repositories::RepositoryBuilder;
+use std::collections::HashSet;
 use std::ffi::OsStr;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use walkdir::WalkDir;
 
-const MARKDOWN_FILE_EXTENSION: &str = "md";
-
+/// Brings the index up to date with the notes on disk. Only the notes whose stamp changed are
+/// parsed again, and the notes deleted since the last reindex are forgotten.
 pub fn reindex(context: &Context) -> crate::Result<()> {
-    context.db().drop()?;
+    let config_hash = context.config().index_hash()?;
     context.db().execute(|repository| {
-        for file_result in files_iter(context.project_root()) {
-            let mut file = file_result?;
-            for reference in file.references_mut() {
-                reference.upsert(&repository.references())?;
+        // Notes indexed with other settings may be parsed differently, so none of them are kept.
+        let metadata = repository.metadata();
+        if metadata.config_hash()? != Some(config_hash) {
+            log::info!("The index was built with other settings, rebuilding it");
+            repository.clear()?;
+            metadata.set_config_hash(config_hash)?;
+        }
+
+        let stamps = repository.stamps();
+        let mut deleted: HashSet<PathBuf> = stamps.paths()?.into_iter().collect();
+        // An index without stamps is empty or predates them, and is rebuilt from scratch.
+        if deleted.is_empty() {
+            repository.clear()?;
+        }
+
+        let mut parsed = 0;
+        for path in note_paths_iter(context.project_root(), context.config()) {
+            deleted.remove(&path);
+            let metadata = std::fs::metadata(&path)?;
+            let stored = stamps.find(&path)?;
+            if stored
+                .as_ref()
+                .is_some_and(|stored| stored.matches_metadata(&metadata))
+            {
+                continue;
+            }
+            let text = std::fs::read_to_string(&path)?;
+            let stamp = FileStamp::new(&metadata, &text);
+            if !stored
+                .as_ref()
+                .is_some_and(|stored| stored.matches_content(&stamp))
+            {
+                if stored.is_some() {
+                    remove_file(repository, &path)?;
+                }
+                let mut file = File::parse_from_str(path.clone(), &text, context.config())?;
+                index_file(repository, &mut file, &text)?;
+                parsed += 1;
             }
-            file.create(&repository.files())?;
+            stamps.upsert_stamp(&path, &stamp)?;
+        }
+
+        for path in deleted.iter() {
+            remove_file(repository, path)?;
+            stamps.delete(path)?;
         }
+        log::info!(
+            "reindexed {parsed} changed notes and removed {} deleted notes",
+            deleted.len()
+        );
         Ok(())
-    })?;
+    })
+}
 
-    Ok(())
+/// Drops the index and parses every note again, in one transaction so that the previous index
+/// is kept when the rebuild fails.
+pub fn full_reindex(context: &Context) -> crate::Result<()> {
+    context.db().execute(|repository| {
+        repository.clear()?;
+        reindex(context)
+    })
 }
 
-fn files_iter(project_root: &ProjectRoot) -> impl Iterator<Item = crate::Result<File>> {
+fn index_file(repository: &RepositoryBuilder, file: &mut File, text: &str) -> crate::Result<()> {
+    repository.search().index_document(file.path(), text)?;
+    repository
+        .references()
+        .replace_references(file.path(), file.references())?;
+    for tag in file.tags_mut() {
+        tag.upsert(&repository.tags())?;
+    }
+    for task in file.tasks_mut() {
+        task.upsert(&repository.tasks())?;
+    }
+    file.create(&repository.files())
+}
+
+fn remove_file(repository: &RepositoryBuilder, path: &Path) -> crate::Result<()> {
+    repository.references().replace_references(path, &[])?;
+    repository.tags().delete_by_path(path)?;
+    repository.tasks().delete_by_path(path)?;
+    repository.search().remove_document(path)?;
+    repository.files().delete_file(path)
+}
+
+/// The paths of the notes in the project, in any of the formats whose extensions are configured.
+pub fn note_paths_iter<'a>(
+    project_root: &ProjectRoot,
+    config: &'a Config,
+) -> impl Iterator<Item = PathBuf> + 'a {
     WalkDir::new(project_root.file_path())
         .into_iter()
         .filter_entry(|e| !os_str_is_hidden(e.file_name()))
         .filter_map(|e| e.ok())
         .map(|e| e.path().to_path_buf())
-        .filter(|p| path_has_markdown_extension(p))
-        .map(File::parse_from_path)
+        .filter(|p| path_is_note(p, config))
 }
 
-fn path_has_markdown_extension(path: &Path) -> bool {
-    path.extension()
-        .filter(|e| &MARKDOWN_FILE_EXTENSION == e)
-        .is_some()
+fn path_is_note(path: &Path, config: &Config) -> bool {
+    config.format(path).is_some()
 }
 
 fn os_str_is_hidden(os_str: &OsStr) -> bool {
@@ -46,18 +121,193 @@ fn os_str_is_hidden(os_str: &OsStr) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::path::PathBuf;
 
     #[test]
-    fn test_reindex_service_path_has_markdown_extension() {
-        let path = PathBuf::from("test.md");
-        assert!(path_has_markdown_extension(&path));
+    fn test_reindex_service_path_is_note() {
+        let config = Config::default();
+        assert!(path_is_note(&PathBuf::from("test.md"), &config));
+        assert!(path_is_note(&PathBuf::from("test.org"), &config));
     }
 
     #[test]
-    fn test_reindex_service_path_does_not_have_markdown_extension() {
+    fn test_reindex_service_path_is_not_note() {
         let path = PathBuf::from("test.exe");
-        assert!(!path_has_markdown_extension(&path));
+        assert!(!path_is_note(&path, &Config::default()));
+    }
+
+    #[test]
+    fn test_reindex_service_note_paths_iter() -> crate::Result<()> {
+        let tmp_dir = tempfile::Builder::new().prefix("grimoire").tempdir()?;
+        std::fs::write(tmp_dir.path().join("note.md"), "# Note")?;
+        std::fs::write(tmp_dir.path().join("note.txt"), "# Text")?;
+        std::fs::create_dir(tmp_dir.path().join(".hidden"))?;
+        std::fs::write(tmp_dir.path().join(".hidden").join("hidden.md"), "# Hidden")?;
+
+        let project_root = ProjectRoot::new(tmp_dir.path().to_path_buf())?;
+        let config = Config::default();
+        let paths: Vec<PathBuf> = note_paths_iter(&project_root, &config).collect();
+        assert_eq!(paths, vec![tmp_dir.path().join("note.md")]);
+        Ok(())
+    }
+
+    fn indexed_files(context: &Context) -> crate::Result<Vec<File>> {
+        let mut files = Vec::new();
+        context.db().execute(|repository| {
+            files = repository.files().find_all()?;
+            Ok(())
+        })?;
+        files.sort_by_key(|file| file.path().clone());
+        Ok(files)
+    }
+
+    #[test]
+    fn test_reindex_service_mixed_formats() -> crate::Result<()> {
+        let tmp_dir = tempfile::Builder::new().prefix("grimoire").tempdir()?;
+        std::fs::write(tmp_dir.path().join("a.md"), "# Markdown\n[[Org]]\n")?;
+        std::fs::write(
+            tmp_dir.path().join("b.org"),
+            "* Org\n[[Markdown]] [[file:a.md][a]]\n",
+        )?;
+
+        let context =
+            Context::mock_with_project_root(ProjectRoot::new(tmp_dir.path().to_path_buf())?);
+        reindex(&context)?;
+        let files = indexed_files(&context)?;
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].header(), "Markdown");
+        assert_eq!(files[1].header(), "Org");
+        let references = files[1].references();
+        assert_eq!(references.len(), 2);
+        assert_eq!(references[0].header(), "Markdown");
+        assert_eq!(
+            references[1].target_path(),
+            Some(&tmp_dir.path().join("a.md"))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_reindex_service_without_header() -> crate::Result<()> {
+        let tmp_dir = tempfile::Builder::new().prefix("grimoire").tempdir()?;
+        std::fs::write(tmp_dir.path().join("daily-log.md"), "Just some text\n")?;
+
+        let context =
+            Context::mock_with_project_root(ProjectRoot::new(tmp_dir.path().to_path_buf())?);
+        reindex(&context)?;
+        let files = indexed_files(&context)?;
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].header(), "daily-log");
+        assert!(files[0].header_is_synthetic());
+        Ok(())
+    }
+
+    #[test]
+    fn test_reindex_service_incremental() -> crate::Result<()> {
+        let tmp_dir = tempfile::Builder::new().prefix("grimoire").tempdir()?;
+        let root = tmp_dir.path();
+        std::fs::write(root.join("kept.md"), "# Kept\n[[Changed]] #inbox\n")?;
+        std::fs::write(
+            root.join("changed.md"),
+            "# Changed\n- [ ] old task #inbox\n",
+        )?;
+        std::fs::write(root.join("deleted.md"), "# Deleted\n[[Kept]] #draft\n")?;
+
+        let context = Context::mock_with_project_root(ProjectRoot::new(root.to_path_buf())?);
+        reindex(&context)?;
+        let stamp = |context: &Context, name: &str| -> crate::Result<Option<FileStamp>> {
+            let mut stamp = None;
+            context.db().execute(|repository| {
+                stamp = repository.stamps().find(&root.join(name))?;
+                Ok(())
+            })?;
+            Ok(stamp)
+        };
+        let kept_stamp = stamp(&context, "kept.md")?;
+        assert!(kept_stamp.is_some());
+
+        std::fs::write(root.join("changed.md"), "# Renamed\n- [ ] new task\n")?;
+        std::fs::remove_file(root.join("deleted.md"))?;
+        std::fs::write(root.join("added.md"), "# Added\n")?;
+        reindex(&context)?;
+
+        let headers: Vec<String> = indexed_files(&context)?
+            .iter()
+            .map(|file| file.header())
+            .collect();
+        assert_eq!(headers, vec!["Added", "Renamed", "Kept"]);
+        assert_eq!(stamp(&context, "kept.md")?, kept_stamp);
+        assert!(stamp(&context, "deleted.md")?.is_none());
+
+        let tags = crate::services::all_tags(&context)?;
+        assert_eq!(tags.len(), 1);
+        assert_eq!(tags[0].path(), &root.join("kept.md"));
+        let tasks = crate::services::find_tasks(&context, &Default::default())?;
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].text(), "new task");
+        let mut references = Vec::new();
+        context.db().execute(|repository| {
+            references = repository.references().find_by_target("Kept")?;
+            Ok(())
+        })?;
+        assert!(references.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_reindex_service_full_reindex() -> crate::Result<()> {
+        let tmp_dir = tempfile::Builder::new().prefix("grimoire").tempdir()?;
+        std::fs::write(tmp_dir.path().join("note.md"), "# Note\n")?;
+
+        let context =
+            Context::mock_with_project_root(ProjectRoot::new(tmp_dir.path().to_path_buf())?);
+        reindex(&context)?;
+        full_reindex(&context)?;
+        full_reindex(&context)?;
+        assert_eq!(indexed_files(&context)?.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_reindex_service_failed_full_reindex_keeps_the_index() -> crate::Result<()> {
+        let tmp_dir = tempfile::Builder::new().prefix("grimoire").tempdir()?;
+        std::fs::write(tmp_dir.path().join("note.md"), "# Note\n")?;
+
+        let context =
+            Context::mock_with_project_root(ProjectRoot::new(tmp_dir.path().to_path_buf())?);
+        reindex(&context)?;
+        // A note that isn't valid UTF-8 can't be read, so the rebuild fails.
+        std::fs::write(tmp_dir.path().join("broken.md"), [0xff, 0xfe])?;
+        assert!(full_reindex(&context).is_err());
+
+        let files = indexed_files(&context)?;
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].header(), "Note");
+        let mut stamps = Vec::new();
+        context.db().execute(|repository| {
+            stamps = repository.stamps().paths()?;
+            Ok(())
+        })?;
+        assert_eq!(stamps, vec![tmp_dir.path().join("note.md")]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_reindex_service_config_change() -> crate::Result<()> {
+        let tmp_dir = tempfile::Builder::new().prefix("grimoire").tempdir()?;
+        std::fs::write(
+            tmp_dir.path().join("note.md"),
+            "---\ntitle: Title\n---\n# Heading\n",
+        )?;
+
+        let context =
+            Context::mock_with_project_root(ProjectRoot::new(tmp_dir.path().to_path_buf())?);
+        reindex(&context)?;
+        assert_eq!(indexed_files(&context)?[0].header(), "Title");
+
+        let context = context.with_config(toml::from_str("header = \"first-h1\"")?);
+        reindex(&context)?;
+        assert_eq!(indexed_files(&context)?[0].header(), "Heading");
+        Ok(())
     }
 
     #[test]
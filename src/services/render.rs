@@ -0,0 +1,303 @@
+use crate::ctx::Context;
+use crate::models::{File, Reference, ReferenceKind};
+use std::path::{Path, PathBuf};
+
+/// Renders a note, identified by its header or its path, with every embed expanded.
+pub fn render(context: &Context, note: &str) -> crate::Result<String> {
+    let path = PathBuf::from(note);
+    let file = match path.is_file() {
+        true => File::parse_from_path_with_config(path, context.config())?,
+        false => match find_file(context, note)? {
+            Some(file) => file,
+            None => return Err(crate::errors::note_not_found(note)),
+        },
+    };
+    let mut renderer = Renderer::new(context);
+    renderer.stack.push(embed_key(file.path(), None, None));
+    renderer.render_file(&file, None, None)
+}
+
+/// Renders the content targeted by an embed in `source`, or `None` when its note can't be found.
+pub fn render_reference(
+    context: &Context,
+    source: &File,
+    reference: &Reference,
+) -> crate::Result<Option<String>> {
+    let mut renderer = Renderer::new(context);
+    renderer.stack.push(embed_key(source.path(), None, None));
+    renderer.expand(reference)
+}
+
+fn find_file(context: &Context, header: &str) -> crate::Result<Option<File>> {
+    let mut file_option = None;
+    context.db().execute(|repository| {
+        file_option = repository.files().find(header, &context.link_resolver())?;
+        Ok(())
+    })?;
+    Ok(file_option)
+}
+
+struct Renderer<'a> {
+    context: &'a Context,
+    stack: Vec<String>,
+}
+
+impl<'a> Renderer<'a> {
+    fn new(context: &'a Context) -> Self {
+        Self {
+            context,
+            stack: Vec::new(),
+        }
+    }
+
+    fn render_file(
+        &mut self,
+        file: &File,
+        section: Option<&str>,
+        block: Option<&str>,
+    ) -> crate::Result<String> {
+        let text = std::fs::read_to_string(file.path())?;
+        // The indexed note may be older than the text on disk, so its locations are read again
+        // from the text being rendered.
+        let file = &File::parse_from_str(file.path().clone(), &text, self.context.config())?;
+        let lines: Vec<&str> = text.lines().collect();
+
+        let rendered_lines = match (block, section) {
+            (Some(block), _) => match file.block(block) {
+                Some(block) => {
+                    let line_position = block.location().line() as usize;
+                    let end = block.location().start.utf8_column as usize;
+                    let line = lines.get(line_position).copied().unwrap_or_default();
+                    let line = line.get(..end).unwrap_or(line);
+                    vec![self.render_line(file, line_position, line.trim_end())?]
+                }
+                None => Vec::new(),
+            },
+            (None, Some(section)) => match file.heading(section) {
+                Some(heading) => {
+                    let start = heading.location().line() as usize;
+                    let end = file
+                        .headings()
+                        .iter()
+                        .map(|other| (other.location().line() as usize, other.level()))
+                        .find(|(line, level)| *line > start && *level <= heading.level())
+                        .map(|(line, _)| line)
+                        .unwrap_or(lines.len());
+                    self.render_lines(file, &lines, start, end)?
+                }
+                None => Vec::new(),
+            },
+            (None, None) => self.render_lines(file, &lines, 0, lines.len())?,
+        };
+        Ok(rendered_lines.join("\n"))
+    }
+
+    fn render_lines(
+        &mut self,
+        file: &File,
+        lines: &[&str],
+        start: usize,
+        end: usize,
+    ) -> crate::Result<Vec<String>> {
+        (start..end.min(lines.len()))
+            .map(|line_position| self.render_line(file, line_position, lines[line_position]))
+            .collect()
+    }
+
+    fn render_line(
+        &mut self,
+        file: &File,
+        line_position: usize,
+        line: &str,
+    ) -> crate::Result<String> {
+        let mut embeds: Vec<&Reference> = file
+            .references()
+            .iter()
+            .filter(|reference| reference.kind() == &ReferenceKind::Embed)
+            .filter(|reference| reference.location().line() as usize == line_position)
+            .collect();
+        embeds.sort_by_key(|reference| reference.location().start.offset);
+
+        let mut rendered = String::new();
+        let mut cursor = 0;
+        for embed in embeds {
+            let start = embed.location().start.utf8_column as usize;
+            let end = embed.location().end.utf8_column as usize;
+            let (before, text) = match (line.get(cursor..start), line.get(start..end)) {
+                (Some(before), Some(text)) => (before, text),
+                _ => continue,
+            };
+            rendered.push_str(before);
+            match self.expand(embed)? {
+                Some(expansion) => rendered.push_str(&expansion),
+                None => rendered.push_str(text),
+            }
+            cursor = end;
+        }
+        rendered.push_str(line.get(cursor..).unwrap_or_default());
+        Ok(rendered)
+    }
+
+    fn expand(&mut self, embed: &Reference) -> crate::Result<Option<String>> {
+        let mut file_option = None;
+        self.context.db().execute(|repository| {
+            file_option = repository
+                .files()
+                .find_target(embed, &self.context.link_resolver())?;
+            Ok(())
+        })?;
+        let file = match file_option {
+            Some(file) => file,
+            None => {
+                log::warn!(
+                    "Skipping embed of missing note {}",
+                    embed.target_description()
+                );
+                return Ok(None);
+            }
+        };
+        let key = embed_key(file.path(), embed.section(), embed.block());
+        if self.stack.contains(&key) {
+            log::warn!("Skipping cyclic embed of {:?}", key);
+            return Ok(None);
+        }
+        self.stack.push(key);
+        let rendered = self.render_file(&file, embed.section(), embed.block());
+        self.stack.pop();
+        rendered.map(Some)
+    }
+}
+
+/// Notes may share a header, so the notes being expanded are told apart by path.
+fn embed_key(path: &Path, section: Option<&str>, block: Option<&str>) -> String {
+    format!(
+        "{}#{}^{}",
+        path.display(),
+        section.unwrap_or_default(),
+        block.unwrap_or_default()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index(context: &Context, data: &str) -> (tempfile::TempDir, PathBuf) {
+        let (tmp_dir, path) = File::mock_disk_file(Some(String::from(data)));
+        let mut file = File::parse_from_path(path.clone()).unwrap();
+        context
+            .db()
+            .execute(|repository| file.create(&repository.files()))
+            .unwrap();
+        (tmp_dir, path)
+    }
+
+    #[test]
+    fn test_render_without_embeds() {
+        let context = Context::mock();
+        let _note = index(&context, "# Plain\n\nSome text");
+        assert_eq!(render(&context, "Plain").unwrap(), "# Plain\n\nSome text");
+    }
+
+    #[test]
+    fn test_render_by_path() {
+        let context = Context::mock();
+        let (_tmp_dir, path) = File::mock_disk_file(Some(String::from("# Unindexed\n")));
+        assert_eq!(
+            render(&context, path.to_str().unwrap()).unwrap(),
+            "# Unindexed"
+        );
+    }
+
+    #[test]
+    fn test_render_missing_note() {
+        let context = Context::mock();
+        assert_eq!(
+            render(&context, "Missing").unwrap_err().to_string(),
+            crate::errors::note_not_found("Missing").to_string()
+        );
+    }
+
+    #[test]
+    fn test_render_expands_embeds_recursively() {
+        let context = Context::mock();
+        let _leaf = index(&context, "# Leaf\n\nleaf text ^quote\n");
+        let _middle = index(
+            &context,
+            "# Middle\n\n## Part\nbefore ![[Leaf^quote]] after\n## Other\nskipped\n",
+        );
+        let _root = index(&context, "# Root\n![[Middle#Part]]\n![[Missing]]");
+
+        assert_eq!(
+            render(&context, "Root").unwrap(),
+            "# Root\n## Part\nbefore leaf text after\n![[Missing]]"
+        );
+    }
+
+    #[test]
+    fn test_render_note_changed_since_indexed() {
+        let context = Context::mock();
+        let _leaf = index(&context, "# Leaf\nleaf\n");
+        let (_root_dir, root_path) = index(&context, "# Root\nabc![[Leaf]]\n");
+        // The embed used to start at byte 3, which is now inside the emoji.
+        std::fs::write(&root_path, "# Root\n😀![[Leaf]]\n").unwrap();
+
+        assert_eq!(render(&context, "Root").unwrap(), "# Root\n😀# Leaf\nleaf");
+    }
+
+    #[test]
+    fn test_render_detects_cycles() {
+        let context = Context::mock();
+        let _first = index(&context, "# First\n![[Second]]");
+        let _second = index(&context, "# Second\n![[First]]");
+
+        assert_eq!(
+            render(&context, "First").unwrap(),
+            "# First\n# Second\n![[First]]"
+        );
+    }
+
+    #[test]
+    fn test_render_notes_sharing_a_header() {
+        let tmp_dir = tempfile::Builder::new()
+            .prefix("grimoire")
+            .tempdir()
+            .unwrap();
+        let project_root = crate::ctx::ProjectRoot::new(tmp_dir.path().to_path_buf()).unwrap();
+        let context = Context::mock_with_project_root(project_root);
+        let outer = tmp_dir.path().join("outer.md");
+        std::fs::write(&outer, "# Meeting\n![[/inner]]\n").unwrap();
+        std::fs::write(tmp_dir.path().join("inner.md"), "# Meeting\ninner text\n").unwrap();
+        crate::services::reindex(&context).unwrap();
+
+        assert_eq!(
+            render(&context, outer.to_str().unwrap()).unwrap(),
+            "# Meeting\n# Meeting\ninner text"
+        );
+    }
+
+    #[test]
+    fn test_render_reference_missing_note() {
+        let context = Context::mock();
+        let source = File::mock(Some(String::from("# Source\n![[Missing]]\n")));
+        let reference = source.references().first().unwrap();
+
+        assert_eq!(
+            render_reference(&context, &source, reference).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_render_reference() {
+        let context = Context::mock();
+        let _target = index(&context, "# Target\n\n## Section\ncontent\n");
+        let source = File::mock(Some(String::from("# Source\n![[Target#Section]]\n")));
+        let reference = source.references().first().unwrap();
+
+        assert_eq!(
+            render_reference(&context, &source, reference).unwrap(),
+            Some(String::from("## Section\ncontent"))
+        );
+    }
+}
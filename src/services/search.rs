@@ -0,0 +1,282 @@
+use crate::ctx::Context;
+use crate::models::{SearchDocument, SearchResult, Snippet};
+use crate::parsers::terms;
+use crate::repositories::RepositoryBuilder;
+use std::collections::{BTreeSet, HashMap};
+use std::path::PathBuf;
+
+// The usual BM25 parameters: how quickly repeated matches stop adding to the score, and how much
+// longer notes are penalized.
+const K1: f64 = 1.2;
+const B: f64 = 0.75;
+const MAX_SNIPPETS: usize = 3;
+
+/// A part of a query that every matching note must contain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Clause {
+    Term(String),
+    /// A word ending with `*`, matching every word it starts.
+    Prefix(String),
+    /// Quoted words, or a word the tokenizer splits, matching those words in a row.
+    Phrase(Vec<String>),
+}
+
+impl Clause {
+    fn width(&self) -> u32 {
+        match self {
+            Clause::Phrase(terms) => terms.len() as u32,
+            _ => 1,
+        }
+    }
+}
+
+/// Finds the notes containing every word, `"quoted phrase"` and `prefix*` of the query, ranked
+/// by BM25 with the best matches first.
+pub fn search(context: &Context, query: &str, limit: usize) -> crate::Result<Vec<SearchResult>> {
+    let clauses = parse_query(query);
+    let mut results = Vec::new();
+    if clauses.is_empty() {
+        return Ok(results);
+    }
+    context.db().execute(|repository| {
+        results = rank(repository, &clauses)?;
+        Ok(())
+    })?;
+    results.truncate(limit);
+    Ok(results)
+}
+
+fn parse_query(query: &str) -> Vec<Clause> {
+    let mut clauses = Vec::new();
+    for (index, part) in query.split('"').enumerate() {
+        // Parts at odd indexes are between quotes.
+        if index % 2 == 1 {
+            clauses.extend(clause(terms(part)));
+            continue;
+        }
+        for word in part.split_whitespace() {
+            let words = terms(word);
+            match (word.ends_with('*'), words.as_slice()) {
+                (true, [prefix]) => clauses.push(Clause::Prefix(prefix.clone())),
+                _ => clauses.extend(clause(words)),
+            }
+        }
+    }
+    clauses
+}
+
+fn clause(mut words: Vec<String>) -> Option<Clause> {
+    match words.len() {
+        0 => None,
+        1 => words.pop().map(Clause::Term),
+        _ => Some(Clause::Phrase(words)),
+    }
+}
+
+/// The positions each note matches a clause at, as the position of the first word.
+fn find_matches(
+    repository: &RepositoryBuilder,
+    clause: &Clause,
+) -> crate::Result<HashMap<PathBuf, Vec<u32>>> {
+    let search = repository.search();
+    let mut matches: HashMap<PathBuf, Vec<u32>> = HashMap::new();
+    match clause {
+        Clause::Term(term) => matches.extend(search.find_postings(term)?),
+        Clause::Prefix(prefix) => {
+            for (_, path, positions) in search.find_postings_with_prefix(prefix)? {
+                matches.entry(path).or_default().extend(positions);
+            }
+            matches.values_mut().for_each(|positions| positions.sort());
+        }
+        Clause::Phrase(terms) => {
+            matches.extend(search.find_postings(&terms[0])?);
+            for (offset, term) in terms.iter().enumerate().skip(1) {
+                let postings: HashMap<PathBuf, BTreeSet<u32>> = search
+                    .find_postings(term)?
+                    .into_iter()
+                    .map(|(path, positions)| (path, positions.into_iter().collect()))
+                    .collect();
+                matches.retain(|path, starts| match postings.get(path) {
+                    Some(positions) => {
+                        starts.retain(|start| positions.contains(&(start + offset as u32)));
+                        !starts.is_empty()
+                    }
+                    None => false,
+                });
+            }
+        }
+    }
+    Ok(matches)
+}
+
+fn rank(repository: &RepositoryBuilder, clauses: &[Clause]) -> crate::Result<Vec<SearchResult>> {
+    let lengths: HashMap<PathBuf, u32> = repository.search().lengths()?.into_iter().collect();
+    let count = lengths.len() as f64;
+    let average_length = lengths.values().map(|length| *length as f64).sum::<f64>() / count;
+
+    let mut scores: Option<HashMap<PathBuf, f64>> = None;
+    let mut spans: HashMap<PathBuf, Vec<(u32, u32)>> = HashMap::new();
+    for clause in clauses {
+        let matches = find_matches(repository, clause)?;
+        let frequency = matches.len() as f64;
+        let idf = (1.0 + (count - frequency + 0.5) / (frequency + 0.5)).ln();
+
+        let mut clause_scores = HashMap::new();
+        for (path, starts) in matches {
+            let previous = match &scores {
+                Some(scores) => match scores.get(&path) {
+                    Some(score) => *score,
+                    None => continue,
+                },
+                None => 0.0,
+            };
+            let length = lengths.get(&path).copied().unwrap_or_default() as f64;
+            let frequency = starts.len() as f64;
+            let saturation =
+                frequency * (K1 + 1.0) / (frequency + K1 * (1.0 - B + B * length / average_length));
+            clause_scores.insert(path.clone(), previous + idf * saturation);
+            let width = clause.width();
+            spans
+                .entry(path)
+                .or_default()
+                .extend(starts.into_iter().map(|start| (start, start + width - 1)));
+        }
+        scores = Some(clause_scores);
+    }
+
+    let mut results = Vec::new();
+    for (path, score) in scores.unwrap_or_default() {
+        let document = match repository.search().find_document(&path)? {
+            Some(document) => document,
+            None => continue,
+        };
+        let header = match repository.files().find_by_path(&path)? {
+            Some(file) => file.header(),
+            None => continue,
+        };
+        let snippets = snippets(&document, spans.remove(&path).unwrap_or_default());
+        results.push(SearchResult::new(path, header, score, snippets));
+    }
+    results.sort_by(|a, b| {
+        b.score()
+            .total_cmp(&a.score())
+            .then_with(|| a.path().cmp(b.path()))
+    });
+    Ok(results)
+}
+
+/// The first few distinct ranges of lines the matches span, in the order they appear.
+fn snippets(document: &SearchDocument, spans: Vec<(u32, u32)>) -> Vec<Snippet> {
+    let lines: BTreeSet<(u32, u32)> = spans
+        .into_iter()
+        .filter_map(|(start, end)| Some((document.token_line(start)?, document.token_line(end)?)))
+        .collect();
+    lines
+        .into_iter()
+        .take(MAX_SNIPPETS)
+        .map(|(start_line, end_line)| {
+            let text = (start_line..=end_line)
+                .filter_map(|line| document.line(line))
+                .collect::<Vec<_>>()
+                .join("\n");
+            Snippet::new(start_line, end_line, text)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ctx::ProjectRoot;
+
+    fn context(notes: &[(&str, &str)]) -> crate::Result<(tempfile::TempDir, Context)> {
+        let tmp_dir = tempfile::Builder::new().prefix("grimoire").tempdir()?;
+        for (name, text) in notes {
+            std::fs::write(tmp_dir.path().join(name), text)?;
+        }
+        let context =
+            Context::mock_with_project_root(ProjectRoot::new(tmp_dir.path().to_path_buf())?);
+        crate::services::reindex(&context)?;
+        Ok((tmp_dir, context))
+    }
+
+    fn headers(results: &[SearchResult]) -> Vec<&str> {
+        results.iter().map(|result| result.header()).collect()
+    }
+
+    #[test]
+    fn test_parse_query() {
+        assert_eq!(
+            parse_query("Bread \"sour dough\" rec* well-known \"\" *"),
+            vec![
+                Clause::Term(String::from("bread")),
+                Clause::Phrase(vec![String::from("sour"), String::from("dough")]),
+                Clause::Prefix(String::from("rec")),
+                Clause::Phrase(vec![String::from("well"), String::from("known")]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_search_ranks_by_bm25() -> crate::Result<()> {
+        let (_tmp_dir, context) = context(&[
+            ("once.md", "# Once\nbread and butter and jam and more\n"),
+            ("twice.md", "# Twice\nbread\nmore bread\n"),
+            ("none.md", "# None\nbutter\n"),
+        ])?;
+        let results = search(&context, "bread", 10)?;
+        assert_eq!(headers(&results), vec!["Twice", "Once"]);
+        assert!(results[0].score() > results[1].score());
+        assert_eq!(
+            results[0].snippets(),
+            &[
+                Snippet::new(1, 1, String::from("bread")),
+                Snippet::new(2, 2, String::from("more bread")),
+            ]
+        );
+
+        assert_eq!(
+            headers(&search(&context, "bread butter", 10)?),
+            vec!["Once"]
+        );
+        assert_eq!(search(&context, "bread", 1)?.len(), 1);
+        assert!(search(&context, "bread cheese", 10)?.is_empty());
+        assert!(search(&context, "  ", 10)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_phrases_and_prefixes() -> crate::Result<()> {
+        let (_tmp_dir, context) = context(&[
+            ("a.md", "# Sourdough\nA sour\ndough starter\n"),
+            ("b.md", "# Dough\ndough that is sour\n"),
+        ])?;
+        let results = search(&context, "\"sour dough\"", 10)?;
+        assert_eq!(headers(&results), vec!["Sourdough"]);
+        assert_eq!(
+            results[0].snippets(),
+            &[Snippet::new(1, 2, String::from("A sour\ndough starter"))]
+        );
+
+        let results = search(&context, "sour*", 10)?;
+        let mut found = headers(&results);
+        found.sort();
+        assert_eq!(found, vec!["Dough", "Sourdough"]);
+        assert_eq!(headers(&search(&context, "start*", 10)?), vec!["Sourdough"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_follows_reindex() -> crate::Result<()> {
+        let (tmp_dir, context) = context(&[("note.md", "# Note\nold words\n")])?;
+        std::fs::write(tmp_dir.path().join("note.md"), "# Note\nnew words\n")?;
+        crate::services::reindex(&context)?;
+        assert!(search(&context, "old", 10)?.is_empty());
+        assert_eq!(headers(&search(&context, "new", 10)?), vec!["Note"]);
+
+        std::fs::remove_file(tmp_dir.path().join("note.md"))?;
+        crate::services::reindex(&context)?;
+        assert!(search(&context, "words", 10)?.is_empty());
+        Ok(())
+    }
+}
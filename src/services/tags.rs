@@ -0,0 +1,84 @@
+use crate::ctx::Context;
+use crate::models::Tag;
+use std::collections::BTreeMap;
+
+/// Counts the occurrences of every indexed tag, sorted by name.
+pub fn tag_counts(context: &Context) -> crate::Result<Vec<(String, usize)>> {
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for tag in all_tags(context)? {
+        *counts.entry(String::from(tag.name())).or_default() += 1;
+    }
+    Ok(counts.into_iter().collect())
+}
+
+/// Finds the occurrences of a tag, including the tags nested under it.
+pub fn find_tags(context: &Context, name: &str) -> crate::Result<Vec<Tag>> {
+    Ok(all_tags(context)?
+        .into_iter()
+        .filter(|tag| tag.matches(name))
+        .collect())
+}
+
+pub fn all_tags(context: &Context) -> crate::Result<Vec<Tag>> {
+    let mut tags = Vec::new();
+    context.db().execute(|repository| {
+        tags = repository.tags().find_all()?;
+        Ok(())
+    })?;
+    Ok(tags)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn index(context: &Context, tags: &[(&str, &str)]) {
+        context
+            .db()
+            .execute(|repository| {
+                for (path, name) in tags {
+                    Tag::new(PathBuf::from(path), String::from(*name), None)
+                        .upsert(&repository.tags())?;
+                }
+                Ok(())
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn test_tag_counts() {
+        let context = Context::mock();
+        index(
+            &context,
+            &[
+                ("a.md", "inbox"),
+                ("b.md", "inbox"),
+                ("a.md", "project/lsp"),
+            ],
+        );
+        assert_eq!(
+            tag_counts(&context).unwrap(),
+            vec![(String::from("inbox"), 2), (String::from("project/lsp"), 1)]
+        );
+    }
+
+    #[test]
+    fn test_find_tags_includes_nested_tags() {
+        let context = Context::mock();
+        index(
+            &context,
+            &[
+                ("a.md", "project"),
+                ("b.md", "project/lsp"),
+                ("c.md", "projects"),
+            ],
+        );
+        let paths: Vec<PathBuf> = find_tags(&context, "project")
+            .unwrap()
+            .into_iter()
+            .map(|tag| tag.path().clone())
+            .collect();
+        assert_eq!(paths, vec![PathBuf::from("a.md"), PathBuf::from("b.md")]);
+    }
+}
@@ -0,0 +1,114 @@
+use crate::ctx::Context;
+use crate::models::Task;
+
+/// Selects tasks by status and due date.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TaskFilter {
+    pub done: Option<bool>,
+    /// Only keeps the tasks due strictly before this `YYYY-MM-DD` date.
+    pub due_before: Option<String>,
+}
+
+impl TaskFilter {
+    pub fn matches(&self, task: &Task) -> bool {
+        let status = self.done.is_none_or(|done| task.done() == done);
+        let due = match &self.due_before {
+            Some(date) => task.due().is_some_and(|due| due < date.as_str()),
+            None => true,
+        };
+        status && due
+    }
+}
+
+/// Finds the indexed tasks matching `filter`, sorted by path and line.
+pub fn find_tasks(context: &Context, filter: &TaskFilter) -> crate::Result<Vec<Task>> {
+    if let Some(date) = &filter.due_before {
+        if !crate::parsers::is_date(date) {
+            return Err(crate::errors::invalid_date(date));
+        }
+    }
+    let mut tasks = Vec::new();
+    context.db().execute(|repository| {
+        tasks = repository.tasks().find_all()?;
+        Ok(())
+    })?;
+    tasks.retain(|task| filter.matches(task));
+    tasks.sort_by(|a, b| (a.path(), a.line()).cmp(&(b.path(), b.line())));
+    Ok(tasks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Location;
+    use std::path::PathBuf;
+
+    fn index(context: &Context, tasks: &[(&str, u32, bool, Option<&str>)]) {
+        context
+            .db()
+            .execute(|repository| {
+                for (path, line, done, due) in tasks {
+                    let location = Location::mock(*line, 0, 0);
+                    Task::new(PathBuf::from(path), String::from("task"), *done, location)
+                        .with_due(due.map(String::from))
+                        .upsert(&repository.tasks())?;
+                }
+                Ok(())
+            })
+            .unwrap();
+    }
+
+    fn lines(tasks: Vec<Task>) -> Vec<(PathBuf, u32)> {
+        tasks
+            .into_iter()
+            .map(|task| (task.path().clone(), task.line()))
+            .collect()
+    }
+
+    #[test]
+    fn test_find_tasks() {
+        let context = Context::mock();
+        index(
+            &context,
+            &[
+                ("b.md", 1, false, Some("2024-06-01")),
+                ("a.md", 4, true, Some("2024-01-01")),
+                ("a.md", 2, false, None),
+            ],
+        );
+        let all = find_tasks(&context, &TaskFilter::default()).unwrap();
+        assert_eq!(
+            lines(all),
+            vec![
+                (PathBuf::from("a.md"), 2),
+                (PathBuf::from("a.md"), 4),
+                (PathBuf::from("b.md"), 1)
+            ]
+        );
+
+        let open = TaskFilter {
+            done: Some(false),
+            ..TaskFilter::default()
+        };
+        assert_eq!(find_tasks(&context, &open).unwrap().len(), 2);
+
+        let due = TaskFilter {
+            done: None,
+            due_before: Some(String::from("2024-06-01")),
+        };
+        assert_eq!(
+            lines(find_tasks(&context, &due).unwrap()),
+            vec![(PathBuf::from("a.md"), 4)]
+        );
+    }
+
+    #[test]
+    fn test_find_tasks_invalid_date() {
+        let context = Context::mock();
+        let filter = TaskFilter {
+            done: None,
+            due_before: Some(String::from("next week")),
+        };
+        assert!(find_tasks(&context, &filter).is_err());
+    }
+}
@@ -1,18 +1,33 @@
-use lsp_server::{Connection, Message, Request, RequestId, Response};
-use lsp_types::{request::GotoDefinition, GotoDefinitionResponse};
+use lsp_server::{
+    Connection, ErrorCode, Message, Notification, Request, RequestId, Response, ResponseError,
+};
+use lsp_types::notification::{DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument};
+use lsp_types::request::{
+    CodeActionRequest, Completion, DocumentDiagnosticRequest, GotoDefinition, HoverRequest,
+    References, Rename, WorkspaceDiagnosticRequest, WorkspaceSymbolRequest,
+};
+use lsp_types::GotoDefinitionResponse;
 
 use crate::ctx::Context;
+use crate::lsp::{Documents, SearchRequest};
 
-use log::info;
+use log::{error, info};
 use std::cmp::Ordering;
 
 #[derive(clap::Args, Debug)]
 #[clap(about, author, version)]
-pub struct Lsp {}
+pub struct Lsp {
+    /// Keeps the index in memory instead of writing it to the project
+    #[clap(long)]
+    in_memory: bool,
+}
 
 impl Lsp {
-    pub fn call(_args: Lsp) -> crate::Result<()> {
-        let context = crate::ctx::Context::new(())?;
+    pub fn call(args: Lsp) -> crate::Result<()> {
+        let context = match args.in_memory {
+            true => crate::ctx::Context::new(crate::ctx::ContextArgs::in_memory())?,
+            false => crate::ctx::Context::new(())?,
+        };
 
         info!("reindexing the project");
         crate::services::reindex(&context)?;
@@ -32,6 +47,7 @@ impl Lsp {
 
     fn main_loop(context: &Context, connection: Connection) -> crate::Result<()> {
         info!("starting main loop");
+        let mut documents = Documents::with_config(context.config().clone());
 
         for msg in &connection.receiver {
             match msg {
@@ -43,32 +59,122 @@ impl Lsp {
                         return Ok(());
                     }
 
-                    match Self::cast::<GotoDefinition>(req) {
+                    let req = match Self::cast::<GotoDefinition>(req) {
                         Ok((id, params)) => {
                             info!("got gotoDefinition request #{}: {:?}", id, params);
 
                             let url = params.text_document_position_params.text_document.uri;
                             let position = params.text_document_position_params.position;
-                            let mut references =
-                                crate::lsp::find_markdown_references(context, url, position)?;
-
-                            let response: Option<GotoDefinitionResponse> =
-                                match references.len().cmp(&1) {
-                                    Ordering::Greater => {
-                                        Some(GotoDefinitionResponse::Array(references))
+                            let response = crate::lsp::find_markdown_references(
+                                context, &documents, url, position,
+                            )
+                            .map(
+                                |mut references| -> Option<GotoDefinitionResponse> {
+                                    match references.len().cmp(&1) {
+                                        Ordering::Greater => {
+                                            Some(GotoDefinitionResponse::Array(references))
+                                        }
+                                        Ordering::Equal => Some(GotoDefinitionResponse::Scalar(
+                                            references.pop().unwrap(),
+                                        )),
+                                        Ordering::Less => None,
                                     }
-                                    Ordering::Less => Some(GotoDefinitionResponse::Scalar(
-                                        references.pop().unwrap(),
-                                    )),
-                                    Ordering::Equal => None,
-                                };
-
-                            let lsp_response = Response {
-                                id,
-                                result: Some(serde_json::to_value(&response)?),
-                                error: None,
-                            };
-                            connection.sender.send(Message::Response(lsp_response))?;
+                                },
+                            );
+
+                            Self::respond(&connection, id, response)?;
+                            continue;
+                        }
+                        Err(req) => req,
+                    };
+
+                    let req = match Self::cast::<DocumentDiagnosticRequest>(req) {
+                        Ok((id, params)) => {
+                            info!("got textDocument/diagnostic request #{}: {:?}", id, params);
+                            let report =
+                                crate::lsp::document_diagnostics(context, &documents, params);
+                            Self::respond(&connection, id, report)?;
+                            continue;
+                        }
+                        Err(req) => req,
+                    };
+
+                    let req = match Self::cast::<Completion>(req) {
+                        Ok((id, params)) => {
+                            info!("got completion request #{}: {:?}", id, params);
+                            let response = crate::lsp::completion(context, &documents, params);
+                            Self::respond(&connection, id, response)?;
+                            continue;
+                        }
+                        Err(req) => req,
+                    };
+
+                    let req = match Self::cast::<HoverRequest>(req) {
+                        Ok((id, params)) => {
+                            info!("got hover request #{}: {:?}", id, params);
+                            let hover = crate::lsp::hover(context, &documents, params);
+                            Self::respond(&connection, id, hover)?;
+                            continue;
+                        }
+                        Err(req) => req,
+                    };
+
+                    let req = match Self::cast::<Rename>(req) {
+                        Ok((id, params)) => {
+                            info!("got rename request #{}: {:?}", id, params);
+                            let edit = crate::lsp::rename(context, &documents, params);
+                            Self::respond(&connection, id, edit)?;
+                            continue;
+                        }
+                        Err(req) => req,
+                    };
+
+                    let req = match Self::cast::<References>(req) {
+                        Ok((id, params)) => {
+                            info!("got references request #{}: {:?}", id, params);
+                            let locations = crate::lsp::references(context, &documents, params);
+                            Self::respond(&connection, id, locations)?;
+                            continue;
+                        }
+                        Err(req) => req,
+                    };
+
+                    let req = match Self::cast::<WorkspaceSymbolRequest>(req) {
+                        Ok((id, params)) => {
+                            info!("got workspace/symbol request #{}: {:?}", id, params);
+                            let symbols = crate::lsp::workspace_symbols(context, params);
+                            Self::respond(&connection, id, symbols)?;
+                            continue;
+                        }
+                        Err(req) => req,
+                    };
+
+                    let req = match Self::cast::<CodeActionRequest>(req) {
+                        Ok((id, params)) => {
+                            info!("got codeAction request #{}: {:?}", id, params);
+                            let actions = crate::lsp::code_actions(context, &documents, params);
+                            Self::respond(&connection, id, actions)?;
+                            continue;
+                        }
+                        Err(req) => req,
+                    };
+
+                    let req = match Self::cast::<SearchRequest>(req) {
+                        Ok((id, params)) => {
+                            info!("got grimoire/search request #{}: {:?}", id, params);
+                            let matches = crate::lsp::search(context, params);
+                            Self::respond(&connection, id, matches)?;
+                            continue;
+                        }
+                        Err(req) => req,
+                    };
+
+                    match Self::cast::<WorkspaceDiagnosticRequest>(req) {
+                        Ok((id, params)) => {
+                            info!("got workspace/diagnostic request #{}: {:?}", id, params);
+                            let report =
+                                crate::lsp::workspace_diagnostics(context, &documents, params);
+                            Self::respond(&connection, id, report)?;
                             continue;
                         }
                         Err(req) => req,
@@ -79,12 +185,69 @@ impl Lsp {
                 }
                 Message::Notification(not) => {
                     info!("got notification: {:?}", not);
+
+                    let not = match Self::cast_notification::<DidOpenTextDocument>(not) {
+                        Ok(params) => {
+                            documents.open(params);
+                            continue;
+                        }
+                        Err(not) => not,
+                    };
+
+                    let not = match Self::cast_notification::<DidChangeTextDocument>(not) {
+                        Ok(params) => {
+                            documents.change(params);
+                            continue;
+                        }
+                        Err(not) => not,
+                    };
+
+                    match Self::cast_notification::<DidCloseTextDocument>(not) {
+                        Ok(params) => {
+                            documents.close(params);
+                            continue;
+                        }
+                        Err(not) => not,
+                    };
                 }
             }
         }
         Ok(())
     }
 
+    /// Answers a request, with an error response when handling it failed so that the server keeps
+    /// running.
+    fn respond<T>(
+        connection: &Connection,
+        id: RequestId,
+        result: crate::Result<T>,
+    ) -> crate::Result<()>
+    where
+        T: serde::Serialize,
+    {
+        let response = match result {
+            Ok(result) => Response {
+                id,
+                result: Some(serde_json::to_value(result)?),
+                error: None,
+            },
+            Err(err) => {
+                error!("request #{} failed: {:?}", id, err);
+                Response {
+                    id,
+                    result: None,
+                    error: Some(ResponseError {
+                        code: ErrorCode::InternalError as i32,
+                        message: err.to_string(),
+                        data: None,
+                    }),
+                }
+            }
+        };
+        connection.sender.send(Message::Response(response))?;
+        Ok(())
+    }
+
     fn cast<R>(req: Request) -> std::result::Result<(RequestId, R::Params), Request>
     where
         R: lsp_types::request::Request,
@@ -92,4 +255,12 @@ impl Lsp {
     {
         req.extract(R::METHOD)
     }
+
+    fn cast_notification<N>(not: Notification) -> std::result::Result<N::Params, Notification>
+    where
+        N: lsp_types::notification::Notification,
+        N::Params: serde::de::DeserializeOwned,
+    {
+        not.extract(N::METHOD)
+    }
 }
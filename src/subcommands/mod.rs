@@ -6,3 +6,15 @@ pub use self::init::*;
 
 mod lsp;
 pub use self::lsp::*;
+
+mod render;
+pub use self::render::*;
+
+mod search;
+pub use self::search::*;
+
+mod tags;
+pub use self::tags::*;
+
+mod tasks;
+pub use self::tasks::*;
@@ -1,10 +1,17 @@
 #[derive(clap::Args, Debug)]
 #[clap(about, author, version)]
-pub struct Reindex {}
+pub struct Reindex {
+    /// Rebuilds the index from scratch instead of only parsing the notes that changed
+    #[clap(long)]
+    full: bool,
+}
 
 impl Reindex {
-    pub fn call(_args: Reindex) -> crate::Result<()> {
+    pub fn call(args: Reindex) -> crate::Result<()> {
         let context = crate::ctx::Context::new(())?;
-        crate::services::reindex(&context)
+        match args.full {
+            true => crate::services::full_reindex(&context),
+            false => crate::services::reindex(&context),
+        }
     }
 }
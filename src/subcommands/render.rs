@@ -0,0 +1,15 @@
+#[derive(clap::Args, Debug)]
+#[clap(about, author, version)]
+pub struct Render {
+    /// Header or path of the note to render
+    note: String,
+}
+
+impl Render {
+    pub fn call(args: Render) -> crate::Result<()> {
+        let context = crate::ctx::Context::new(())?;
+        crate::services::reindex(&context)?;
+        println!("{}", crate::services::render(&context, &args.note)?);
+        Ok(())
+    }
+}
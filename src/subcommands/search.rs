@@ -0,0 +1,25 @@
+#[derive(clap::Args, Debug)]
+#[clap(about, author, version)]
+pub struct Search {
+    /// Words, "quoted phrases" and prefix* terms that every matching note contains
+    query: String,
+    /// The number of notes to list at most
+    #[clap(long, default_value_t = 20)]
+    limit: usize,
+}
+
+impl Search {
+    pub fn call(args: Search) -> crate::Result<()> {
+        let context = crate::ctx::Context::new(())?;
+        crate::services::reindex(&context)?;
+        let root = context.project_root().file_path();
+        for result in crate::services::search(&context, &args.query, args.limit)? {
+            let path = result.path().strip_prefix(&root).unwrap_or(result.path());
+            for snippet in result.snippets() {
+                let text = snippet.text().replace('\n', " ");
+                println!("{}:{}\t{}", path.display(), snippet.start_line() + 1, text);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,14 @@
+#[derive(clap::Args, Debug)]
+#[clap(about, author, version)]
+pub struct Tags {}
+
+impl Tags {
+    pub fn call(_args: Tags) -> crate::Result<()> {
+        let context = crate::ctx::Context::new(())?;
+        crate::services::reindex(&context)?;
+        for (name, count) in crate::services::tag_counts(&context)? {
+            println!("#{name}\t{count}");
+        }
+        Ok(())
+    }
+}
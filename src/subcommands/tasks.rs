@@ -0,0 +1,40 @@
+#[derive(clap::Args, Debug)]
+#[clap(about, author, version)]
+pub struct Tasks {
+    /// Only lists unchecked tasks
+    #[clap(long, conflicts_with = "done")]
+    open: bool,
+    /// Only lists checked tasks
+    #[clap(long)]
+    done: bool,
+    /// Only lists tasks with a `due:` date before DATE, written as YYYY-MM-DD
+    #[clap(long, value_name = "DATE")]
+    due_before: Option<String>,
+}
+
+impl Tasks {
+    pub fn call(args: Tasks) -> crate::Result<()> {
+        let context = crate::ctx::Context::new(())?;
+        crate::services::reindex(&context)?;
+        let filter = crate::services::TaskFilter {
+            done: match (args.open, args.done) {
+                (true, _) => Some(false),
+                (_, true) => Some(true),
+                _ => None,
+            },
+            due_before: args.due_before,
+        };
+        let root = context.project_root().file_path();
+        for task in crate::services::find_tasks(&context, &filter)? {
+            let path = task.path().strip_prefix(&root).unwrap_or(task.path());
+            let checkbox = if task.done() { "[x]" } else { "[ ]" };
+            println!(
+                "{}:{}\t{checkbox} {}",
+                path.display(),
+                task.line() + 1,
+                task.text()
+            );
+        }
+        Ok(())
+    }
+}